@@ -0,0 +1,62 @@
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+use ruint::aliases::U256;
+use sandstorm_binary::Memory;
+use sandstorm_binary::MemoryStore;
+use sandstorm_binary::SparseMemory;
+use sandstorm_binary::Word;
+
+const NUM_CELLS: usize = 1 << 16;
+
+/// Cells written by a typical program i.e. one contiguous block
+fn contiguous_cells() -> Vec<(usize, Word<Fp>)> {
+    (1..=NUM_CELLS)
+        .map(|address| (address, Word::new(U256::from(address))))
+        .collect()
+}
+
+/// Cells spread over a large address range e.g. a program that writes to
+/// addresses around 10^8
+fn scattered_cells() -> Vec<(usize, Word<Fp>)> {
+    (1..=NUM_CELLS)
+        .map(|i| (i * 1543, Word::new(U256::from(i))))
+        .collect()
+}
+
+fn read_all<M: MemoryStore<Fp>>(memory: &M, cells: &[(usize, Word<Fp>)]) -> usize {
+    cells
+        .iter()
+        .filter(|&&(address, _)| memory.get_checked(address).is_some())
+        .count()
+}
+
+fn memory_benches(c: &mut Criterion) {
+    for (pattern, cells) in [
+        ("contiguous", contiguous_cells()),
+        ("scattered", scattered_cells()),
+    ] {
+        let mut group = c.benchmark_group(format!("memory_{pattern}"));
+        group.bench_with_input(BenchmarkId::new("build", "dense"), &cells, |b, cells| {
+            b.iter(|| cells.iter().copied().collect::<Memory<Fp>>())
+        });
+        group.bench_with_input(BenchmarkId::new("build", "sparse"), &cells, |b, cells| {
+            b.iter(|| cells.iter().copied().collect::<SparseMemory<Fp>>())
+        });
+
+        let dense = cells.iter().copied().collect::<Memory<Fp>>();
+        let sparse = cells.iter().copied().collect::<SparseMemory<Fp>>();
+        group.bench_with_input(BenchmarkId::new("read", "dense"), &cells, |b, cells| {
+            b.iter(|| read_all(&dense, cells))
+        });
+        group.bench_with_input(BenchmarkId::new("read", "sparse"), &cells, |b, cells| {
+            b.iter(|| read_all(&sparse, cells))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, memory_benches);
+criterion_main!(benches);
@@ -1,8 +1,14 @@
+use crate::instruction::ApUpdateKind;
+use crate::types::MemoryAddress;
+use crate::FlagGroup;
+use crate::Layout;
+use crate::SegmentKind;
+use crate::Word;
 use ruint::aliases::U256;
 use std::error::Error;
 use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct InvalidFieldElementError {
     pub value: U256,
     pub modulus: U256,
@@ -19,3 +25,679 @@ impl Display for InvalidFieldElementError {
 }
 
 impl Error for InvalidFieldElementError {}
+
+/// Reasons a field element can't be decoded as a Cairo instruction
+#[derive(Debug, Clone, Copy)]
+pub enum InvalidInstruction {
+    /// There is no instruction at the given address
+    Missing { address: usize },
+    /// Instructions are encoded in 63 bits (three 16 bit offsets and 15 flags)
+    TooLarge { value: U256 },
+    /// A flag group has an invalid combination of flags set
+    InvalidFlagGroup { flag_group: FlagGroup, value: u8 },
+    /// Calls always update `ap` by 2 so `ap_update` must be regular
+    CallWithApUpdate { ap_update: ApUpdateKind },
+    /// Conditional jumps need an unused `res` (so `res_logic` must be `op1`),
+    /// a `nop` opcode and can't update `ap` by `res`
+    InvalidJnz,
+    /// Immediate values are stored in the cell after the instruction so
+    /// `off_op1` must be 1
+    InvalidImmediateOffset { off_op1: i16 },
+}
+
+impl Display for InvalidInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { address } => write!(f, "No instruction at address {address}"),
+            Self::TooLarge { value } => {
+                write!(f, "Invalid instruction: {value} does not fit in 63 bits")
+            }
+            Self::InvalidFlagGroup { flag_group, value } => {
+                write!(
+                    f,
+                    "Invalid instruction: {flag_group:?} has invalid value {value}"
+                )
+            }
+            Self::CallWithApUpdate { ap_update } => {
+                write!(
+                    f,
+                    "Invalid instruction: call has ap update {ap_update:?} but must be regular"
+                )
+            }
+            Self::InvalidJnz => write!(
+                f,
+                "Invalid instruction: jnz must have res logic op1, opcode nop and no ap += res"
+            ),
+            Self::InvalidImmediateOffset { off_op1 } => write!(
+                f,
+                "Invalid instruction: immediate has off_op1 {off_op1} but must be 1"
+            ),
+        }
+    }
+}
+
+impl Error for InvalidInstruction {}
+
+/// Reasons an instruction can't be executed
+#[derive(Debug, Clone, Copy)]
+pub enum SimError {
+    InvalidInstruction(InvalidInstruction),
+    /// There is no value in memory at the given address
+    MissingValue {
+        address: usize,
+    },
+    /// A computed address doesn't fit in a `usize` e.g. it's negative
+    InvalidAddress {
+        value: U256,
+    },
+}
+
+impl From<InvalidInstruction> for SimError {
+    fn from(err: InvalidInstruction) -> Self {
+        Self::InvalidInstruction(err)
+    }
+}
+
+impl Display for SimError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidInstruction(err) => write!(f, "{err}"),
+            Self::MissingValue { address } => write!(f, "No value in memory at address {address}"),
+            Self::InvalidAddress { value } => write!(f, "Invalid address {value}"),
+        }
+    }
+}
+
+impl Error for SimError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicMemorySizeError {
+    /// The memory argument is sized by the number of steps
+    TooManyEntries { entries: usize, n_steps: u64 },
+    /// Public memory must be in the program, execution or output segment
+    AddressOutOfRange { address: MemoryAddress },
+}
+
+impl Display for PublicMemorySizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyEntries { entries, n_steps } => write!(
+                f,
+                "Public memory has {entries} entries but must have at most n_steps={n_steps}"
+            ),
+            Self::AddressOutOfRange { address } => write!(
+                f,
+                "Public memory address {address} is not in the program, execution or output segment"
+            ),
+        }
+    }
+}
+
+impl Error for PublicMemorySizeError {}
+
+/// Reasons a memory segment doesn't hold the expected values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentValidationError {
+    /// The address isn't in the memory
+    Missing { address: MemoryAddress },
+    /// The value in memory doesn't match the expected value
+    Mismatch {
+        address: MemoryAddress,
+        expected: U256,
+        actual: U256,
+    },
+}
+
+impl Display for SegmentValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { address } => write!(f, "No value in memory at address {address}"),
+            Self::Mismatch {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Memory at address {address} has value {actual} but expected {expected}"
+            ),
+        }
+    }
+}
+
+impl Error for SegmentValidationError {}
+
+/// Memory segments aren't laid out in the order used by `cairo-run`. See
+/// [crate::AirPublicInput::validate_segment_adjacency].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentError {
+    /// Unused cells between two consecutive segments
+    Gap {
+        after: SegmentKind,
+        before: SegmentKind,
+        gap_size: u32,
+    },
+    /// Segment `b` starts before segment `a` ends
+    Overlap { a: SegmentKind, b: SegmentKind },
+    /// A segment starts before a segment that should precede it
+    WrongOrder {
+        expected_before: SegmentKind,
+        expected_after: SegmentKind,
+    },
+}
+
+impl Display for SegmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gap {
+                after,
+                before,
+                gap_size,
+            } => write!(
+                f,
+                "{gap_size} unused cells between the {after:?} and {before:?} segments"
+            ),
+            Self::Overlap { a, b } => write!(f, "{a:?} and {b:?} segments overlap"),
+            Self::WrongOrder {
+                expected_before,
+                expected_after,
+            } => write!(
+                f,
+                "{expected_before:?} segment must start before the {expected_after:?} segment"
+            ),
+        }
+    }
+}
+
+impl Error for SegmentError {}
+
+/// Memory address 0 is reserved for dummy accesses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    Zero,
+}
+
+impl Display for AddressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Zero => write!(f, "Invalid address: 0 is reserved for dummy accesses"),
+        }
+    }
+}
+
+impl Error for AddressError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidMemoryEntry {
+    Address(AddressError),
+    Value(InvalidFieldElementError),
+}
+
+impl From<AddressError> for InvalidMemoryEntry {
+    fn from(err: AddressError) -> Self {
+        Self::Address(err)
+    }
+}
+
+impl From<InvalidFieldElementError> for InvalidMemoryEntry {
+    fn from(err: InvalidFieldElementError) -> Self {
+        Self::Value(err)
+    }
+}
+
+impl Display for InvalidMemoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(err) => err.fmt(f),
+            Self::Value(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for InvalidMemoryEntry {}
+
+/// Errors when decoding a public memory page in the format passed to the
+/// on-chain verifier. See [crate::sharp::decode_public_memory_page].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The page data is missing its size or hash
+    TooShort {
+        len: usize,
+    },
+    /// The number of address/value pairs doesn't match the page size
+    SizeMismatch {
+        size: U256,
+        pairs: usize,
+    },
+    /// An address is 0 or doesn't fit in a [MemoryAddress]
+    InvalidAddress {
+        address: U256,
+    },
+    FieldElementOutOfRange(InvalidFieldElementError),
+    /// The hash of the decoded entries doesn't match the page hash
+    HashMismatch {
+        computed: [u8; 32],
+        claimed: [u8; 32],
+    },
+}
+
+impl From<InvalidFieldElementError> for DecodeError {
+    fn from(err: InvalidFieldElementError) -> Self {
+        Self::FieldElementOutOfRange(err)
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooShort { len } => {
+                write!(
+                    f,
+                    "page has {len} values but needs at least a size and hash"
+                )
+            }
+            Self::SizeMismatch { size, pairs } => {
+                write!(f, "page size is {size} but has {pairs} address/value pairs")
+            }
+            Self::InvalidAddress { address } => write!(f, "invalid address {address}"),
+            Self::FieldElementOutOfRange(err) => err.fmt(f),
+            Self::HashMismatch { computed, claimed } => write!(
+                f,
+                "page hash is {:#x} but the entries hash to {:#x}",
+                U256::from_be_bytes(*claimed),
+                U256::from_be_bytes(*computed)
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Reasons a segment can't be split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitError {
+    /// The split address must be strictly inside the segment
+    AddressOutOfRange(MemoryAddress),
+}
+
+impl Display for SplitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddressOutOfRange(address) => {
+                write!(
+                    f,
+                    "Invalid split: address {address} is not inside the segment"
+                )
+            }
+        }
+    }
+}
+
+impl Error for SplitError {}
+
+/// Reasons a binary trace or memory file can't be parsed
+#[derive(Debug)]
+pub enum ParseError {
+    Io(std::io::Error),
+    /// The data ends part way through an entry
+    Truncated,
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(err),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Read error: {err}"),
+            Self::Truncated => write!(f, "Invalid data: ends part way through an entry"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Errors when loading an input from a JSON file
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+#[cfg(feature = "serde")]
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for LoadError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Read error: {err}"),
+            Self::Json(err) => write!(f, "Invalid JSON: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error for LoadError {}
+
+/// The code doesn't belong to any layout. See [crate::Layout::sharp_code].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownLayoutCode(pub U256);
+
+impl Display for UnknownLayoutCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown layout code: {:#x}", self.0)
+    }
+}
+
+impl Error for UnknownLayoutCode {}
+
+/// The string isn't the name of any layout. See [crate::Layout::ALL].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLayout(pub String);
+
+impl Display for UnknownLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = crate::Layout::ALL.map(|layout| layout.to_string());
+        write!(
+            f,
+            "Unknown layout '{}', expected one of: {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownLayout {}
+
+/// The prime of a compiled program doesn't match the field modulus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeMismatch {
+    /// Modulus of the field as a `0x` prefixed hex string
+    pub expected: String,
+    /// Prime specified by the program
+    pub actual: String,
+}
+
+impl Display for PrimeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { expected, actual } = self;
+        write!(
+            f,
+            "Program prime {actual} doesn't match the field modulus {expected}"
+        )
+    }
+}
+
+impl Error for PrimeMismatch {}
+
+/// A 16-bit part of a range check value is outside `[rc_min, rc_max]` or the
+/// value has too many parts. See
+/// [crate::AirPublicInput::validate_range_checks].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeCheckError {
+    pub index: u32,
+    pub value: U256,
+    pub rc_min: u16,
+    pub rc_max: u16,
+}
+
+impl Display for RangeCheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            index,
+            value,
+            rc_min,
+            rc_max,
+        } = self;
+        write!(
+            f,
+            "Range check instance {index} with value {value:#x} has parts outside \
+             [{rc_min}, {rc_max}]"
+        )
+    }
+}
+
+impl Error for RangeCheckError {}
+
+/// The instances of a builtin aren't indexed `0, 1, ..., n-1`. See
+/// [crate::AirPrivateInput::check_builtin_continuity].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContinuityError {
+    pub builtin: SegmentKind,
+    pub expected_index: u32,
+    pub actual_index: u32,
+}
+
+impl Display for ContinuityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            builtin,
+            expected_index,
+            actual_index,
+        } = self;
+        write!(
+            f,
+            "Expected {builtin:?} instance {expected_index} but found instance {actual_index}"
+        )
+    }
+}
+
+impl Error for ContinuityError {}
+
+/// Builtin instances can't be padded to fill a trace. See
+/// [crate::AirPrivateInput::pad_to_power_of_two].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaddingError {
+    /// The builtin has more instances than fit in the trace
+    TooManyInstances {
+        builtin: SegmentKind,
+        instances: u64,
+        max_instances: u64,
+    },
+    /// Instance indices of a trace with `2^log_n_steps` steps don't fit in a
+    /// `u32`
+    TooManySteps { log_n_steps: u32 },
+}
+
+impl Display for PaddingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyInstances {
+                builtin,
+                instances,
+                max_instances,
+            } => write!(
+                f,
+                "{builtin:?} has {instances} instances but only {max_instances} fit"
+            ),
+            Self::TooManySteps { log_n_steps } => {
+                write!(f, "2^{log_n_steps} steps is too many")
+            }
+        }
+    }
+}
+
+impl Error for PaddingError {}
+
+/// A builtin required by the layout has no memory segment. See
+/// [crate::AirPublicInput::validate_layout_builtins].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingBuiltin {
+    pub layout: Layout,
+    pub builtin: SegmentKind,
+}
+
+impl Display for MissingBuiltin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { layout, builtin } = self;
+        write!(f, "Layout {layout} requires a {builtin:?} segment")
+    }
+}
+
+impl Error for MissingBuiltin {}
+
+/// The private input doesn't match the public input. See
+/// [crate::AirPrivateInput::validate_against_public].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The trace file doesn't have `n_steps` register states
+    StepCountMismatch { expected: u64, actual: u64 },
+    /// The number of builtin instances doesn't fill the builtin's segment
+    InstanceCountMismatch {
+        segment: SegmentKind,
+        segment_size: u32,
+        cells_per_instance: u32,
+        instances: u32,
+    },
+    /// The signature of an ECDSA instance doesn't verify
+    InvalidEcdsaSignature { index: u32 },
+    /// A 16-bit part of a range check value is outside `[rc_min, rc_max]`
+    RangeCheckOutOfBounds {
+        index: u32,
+        value: U256,
+        rc_min: u16,
+        rc_max: u16,
+    },
+    /// The memory segments of the public input aren't laid out correctly
+    Segments(SegmentError),
+    /// The instances of a builtin have a gap or a duplicate index
+    NonContiguousInstances {
+        builtin: SegmentKind,
+        expected_index: u32,
+        actual_index: u32,
+    },
+}
+
+impl From<SegmentError> for ValidationError {
+    fn from(err: SegmentError) -> Self {
+        Self::Segments(err)
+    }
+}
+
+impl From<ContinuityError> for ValidationError {
+    fn from(err: ContinuityError) -> Self {
+        let ContinuityError {
+            builtin,
+            expected_index,
+            actual_index,
+        } = err;
+        Self::NonContiguousInstances {
+            builtin,
+            expected_index,
+            actual_index,
+        }
+    }
+}
+
+impl From<RangeCheckError> for ValidationError {
+    fn from(err: RangeCheckError) -> Self {
+        let RangeCheckError {
+            index,
+            value,
+            rc_min,
+            rc_max,
+        } = err;
+        Self::RangeCheckOutOfBounds {
+            index,
+            value,
+            rc_min,
+            rc_max,
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StepCountMismatch { expected, actual } => {
+                write!(f, "Expected {expected} steps but the trace has {actual}")
+            }
+            Self::InstanceCountMismatch {
+                segment,
+                segment_size,
+                cells_per_instance,
+                instances,
+            } => write!(
+                f,
+                "{segment:?} segment has {segment_size} cells but there are {instances} instances \
+                 of {cells_per_instance} cells"
+            ),
+            Self::InvalidEcdsaSignature { index } => {
+                write!(f, "ECDSA instance {index} has an invalid signature")
+            }
+            Self::RangeCheckOutOfBounds {
+                index,
+                value,
+                rc_min,
+                rc_max,
+            } => write!(
+                f,
+                "Range check instance {index} with value {value:#x} has parts outside \
+                 [{rc_min}, {rc_max}]"
+            ),
+            Self::Segments(err) => write!(f, "Invalid memory segments: {err}"),
+            Self::NonContiguousInstances {
+                builtin,
+                expected_index,
+                actual_index,
+            } => write!(
+                f,
+                "Expected {builtin:?} instance {expected_index} but found instance {actual_index}"
+            ),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// A public memory entry doesn't match the memory. See
+/// [crate::Memory::validate_public_memory].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InconsistencyError<F> {
+    pub address: MemoryAddress,
+    /// Value of the cell or `None` if the program never wrote to it
+    pub memory_value: Option<Word<F>>,
+    /// Value of the public memory entry
+    pub claimed_value: U256,
+}
+
+impl<F> Display for InconsistencyError<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            address,
+            memory_value,
+            claimed_value,
+        } = self;
+        match memory_value {
+            Some(word) => write!(
+                f,
+                "Public memory claims {claimed_value:#x} at address {address} but memory has {:#x}",
+                word.0
+            ),
+            None => write!(
+                f,
+                "Public memory claims {claimed_value:#x} at address {address} but the cell is empty"
+            ),
+        }
+    }
+}
+
+impl<F: std::fmt::Debug> Error for InconsistencyError<F> {}
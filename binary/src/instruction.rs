@@ -0,0 +1,178 @@
+//! Decoded form of a Cairo instruction
+//! https://eprint.iacr.org/2021/1063.pdf section 4.5
+
+/// Values of [crate::FlagGroup::Opcode]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opcode {
+    Nop = 0,
+    Call = 1,
+    Ret = 2,
+    AssertEq = 4,
+}
+
+impl Opcode {
+    pub const fn from_flag_group(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Nop,
+            1 => Self::Call,
+            2 => Self::Ret,
+            4 => Self::AssertEq,
+            _ => return None,
+        })
+    }
+}
+
+/// Values of [crate::FlagGroup::PcUpdate]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcUpdateKind {
+    /// `pc = pc + instruction_size`
+    Regular = 0,
+    /// `pc = res`
+    JumpAbs = 1,
+    /// `pc = pc + res`
+    JumpRel = 2,
+    /// `pc = pc + op1` if `dst != 0` otherwise a regular update
+    Jnz = 4,
+}
+
+impl PcUpdateKind {
+    pub const fn from_flag_group(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Regular,
+            1 => Self::JumpAbs,
+            2 => Self::JumpRel,
+            4 => Self::Jnz,
+            _ => return None,
+        })
+    }
+}
+
+/// Values of [crate::FlagGroup::ApUpdate]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApUpdateKind {
+    /// `ap` is unchanged (or `ap + 2` for calls)
+    Regular = 0,
+    /// `ap = ap + res`
+    Add = 1,
+    /// `ap = ap + 1`
+    Add1 = 2,
+}
+
+impl ApUpdateKind {
+    pub const fn from_flag_group(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Regular,
+            1 => Self::Add,
+            2 => Self::Add1,
+            _ => return None,
+        })
+    }
+}
+
+/// Values of [crate::FlagGroup::ResLogic]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResLogic {
+    /// `res = op1`
+    Op1 = 0,
+    /// `res = op0 + op1`
+    Add = 1,
+    /// `res = op0 * op1`
+    Mul = 2,
+}
+
+impl ResLogic {
+    pub const fn from_flag_group(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Op1,
+            1 => Self::Add,
+            2 => Self::Mul,
+            _ => return None,
+        })
+    }
+}
+
+/// Values of [crate::FlagGroup::DstReg]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DstReg {
+    Ap = 0,
+    Fp = 1,
+}
+
+impl DstReg {
+    pub const fn from_flag_group(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Ap,
+            1 => Self::Fp,
+            _ => return None,
+        })
+    }
+}
+
+/// Values of [crate::FlagGroup::Op0Reg]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op0Reg {
+    Ap = 0,
+    Fp = 1,
+}
+
+impl Op0Reg {
+    pub const fn from_flag_group(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Ap,
+            1 => Self::Fp,
+            _ => return None,
+        })
+    }
+}
+
+/// Values of [crate::FlagGroup::Op1Src]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op1Src {
+    /// `op1 = [op0 + off_op1]`
+    Op0 = 0,
+    /// `op1 = [pc + off_op1]` i.e. an immediate value when `off_op1 = 1`
+    Pc = 1,
+    /// `op1 = [fp + off_op1]`
+    Fp = 2,
+    /// `op1 = [ap + off_op1]`
+    Ap = 4,
+}
+
+impl Op1Src {
+    pub const fn from_flag_group(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::Op0,
+            1 => Self::Pc,
+            2 => Self::Fp,
+            4 => Self::Ap,
+            _ => return None,
+        })
+    }
+}
+
+/// All flag groups and offsets of a Cairo instruction. Offsets are signed
+/// i.e. the biased representation `off + 2^15` stored in the word has been
+/// removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub opcode: Opcode,
+    pub pc_update: PcUpdateKind,
+    pub ap_update: ApUpdateKind,
+    pub res_logic: ResLogic,
+    pub dst_reg: DstReg,
+    pub op0_reg: Op0Reg,
+    pub op1_src: Op1Src,
+    pub off_dst: i16,
+    pub off_op0: i16,
+    pub off_op1: i16,
+}
+
+/// Removes the `2^15` bias from an instruction offset
+pub(crate) const fn decode_offset(biased: u16) -> i16 {
+    biased.wrapping_sub(0x8000) as i16
+}
+
+/// Inverse of [decode_offset]
+pub(crate) const fn encode_offset(offset: i16) -> u16 {
+    (offset as u16).wrapping_add(0x8000)
+}
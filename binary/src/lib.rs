@@ -8,24 +8,84 @@ use ark_ff::PrimeField;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use ark_serialize::Valid;
+use errors::AddressError;
+use errors::ContinuityError;
+use errors::InconsistencyError;
+use errors::InvalidFieldElementError;
+use errors::InvalidInstruction;
+use errors::InvalidMemoryEntry;
+#[cfg(feature = "serde")]
+use errors::LoadError;
+use errors::MissingBuiltin;
+use errors::PaddingError;
+use errors::ParseError;
+use errors::PrimeMismatch;
+use errors::PublicMemorySizeError;
+use errors::RangeCheckError;
+use errors::SegmentError;
+use errors::SegmentValidationError;
+use errors::SimError;
+use errors::SplitError;
+use errors::UnknownLayout;
+use errors::UnknownLayoutCode;
+use errors::ValidationError;
+use instruction::decode_offset;
+use instruction::encode_offset;
+use instruction::ApUpdateKind;
+use instruction::DecodedInstruction;
+use instruction::DstReg;
+use instruction::Op0Reg;
+use instruction::Op1Src;
+use instruction::Opcode;
+use instruction::PcUpdateKind;
+use instruction::ResLogic;
 use num_bigint::BigUint;
 use ruint::aliases::U256;
 use ruint::uint;
+#[cfg(feature = "serde")]
 use serde::Deserialize;
+#[cfg(feature = "serde")]
 use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde_utils::deserialize_hex_str_memory_entries;
+#[cfg(feature = "serde")]
+use serde_utils::deserialize_vec_hex_str;
+#[cfg(feature = "serde")]
+use serde_utils::hex_str;
+#[cfg(feature = "serde")]
+use serde_utils::serialize_hex_str_memory_entries;
+use sha3::digest::consts::U32;
+use sha3::Digest;
+use sharp::hash_memory_page;
+use std::array;
+use std::cmp::min;
+use std::collections::HashMap;
 use std::fmt::Display;
+#[cfg(feature = "serde")]
+use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Read;
+use std::io::Write;
+use std::iter::zip;
+use std::iter::Enumerate;
+use std::iter::FilterMap;
 use std::marker::PhantomData;
 use std::ops::Deref;
+#[cfg(feature = "serde")]
+use std::path::Path;
 use std::path::PathBuf;
-use utils::deserialize_hex_str;
-use utils::deserialize_hex_str_memory_entries;
-use utils::deserialize_vec_hex_str;
+use std::str::FromStr;
+use types::MemoryAddress;
 use utils::field_bytes;
+use utils::read_u64_le;
 
-mod errors;
+pub mod errors;
+pub mod instruction;
+#[cfg(feature = "serde")]
+mod serde_utils;
+pub mod sharp;
+pub mod types;
 mod utils;
 
 // https://eprint.iacr.org/2021/1063.pdf figure 3
@@ -48,16 +108,24 @@ pub const _OFFSET: usize = 2usize.pow(16);
 pub const HALF_OFFSET: usize = 2usize.pow(15);
 
 /// Holds register values
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RegisterState {
     pub ap: usize,
     pub fp: usize,
     pub pc: usize,
 }
 
+impl Display for RegisterState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pc={:#x} ap={:#x} fp={:#x}", self.pc, self.ap, self.fp)
+    }
+}
+
 /// SHARP layouts: <https://www.youtube.com/live/jPxD9h7BdzU?feature=share&t=2800>
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Layout {
     Plain = 0,
     Small = 1,
@@ -89,34 +157,134 @@ impl Display for Layout {
 }
 
 impl Layout {
-    const SHARP_CODE_STARKNET: u128 = 8319381555716711796;
-    const SHARP_CODE_RECURSIVE: u128 = 2110234636557836973669;
+    pub const ALL: [Self; 8] = [
+        Self::Plain,
+        Self::Small,
+        Self::Dex,
+        Self::Recursive,
+        Self::Starknet,
+        Self::RecursiveLargeOutput,
+        Self::AllSolidity,
+        Self::StarknetWithKeccak,
+    ];
+
+    // SHARP codes are the layout names encoded as big-endian ASCII integers
+    const SHARP_CODE_PLAIN: U256 = uint!(0x706c61696e_U256);
+    const SHARP_CODE_SMALL: U256 = uint!(0x736d616c6c_U256);
+    const SHARP_CODE_DEX: U256 = uint!(0x646578_U256);
+    const SHARP_CODE_RECURSIVE: U256 = uint!(0x726563757273697665_U256);
+    const SHARP_CODE_STARKNET: U256 = uint!(0x737461726b6e6574_U256);
+    const SHARP_CODE_RECURSIVE_LARGE_OUTPUT: U256 =
+        uint!(0x7265637572736976655f6c617267655f6f7574707574_U256);
+    const SHARP_CODE_ALL_SOLIDITY: U256 = uint!(0x616c6c5f736f6c6964697479_U256);
+    const SHARP_CODE_STARKNET_WITH_KECCAK: U256 =
+        uint!(0x737461726b6e65745f776974685f6b656363616b_U256);
 
     // Returns the unique code used by SHARP associated to this layout
-    pub const fn sharp_code(&self) -> u128 {
+    pub const fn sharp_code(&self) -> U256 {
         match self {
-            Self::Starknet => Self::SHARP_CODE_STARKNET,
+            Self::Plain => Self::SHARP_CODE_PLAIN,
+            Self::Small => Self::SHARP_CODE_SMALL,
+            Self::Dex => Self::SHARP_CODE_DEX,
             Self::Recursive => Self::SHARP_CODE_RECURSIVE,
-            _ => unimplemented!(),
+            Self::Starknet => Self::SHARP_CODE_STARKNET,
+            Self::RecursiveLargeOutput => Self::SHARP_CODE_RECURSIVE_LARGE_OUTPUT,
+            Self::AllSolidity => Self::SHARP_CODE_ALL_SOLIDITY,
+            Self::StarknetWithKeccak => Self::SHARP_CODE_STARKNET_WITH_KECCAK,
+        }
+    }
+
+    /// Number of steps per instance of each builtin. Layouts without a prover
+    /// implementation have no entries. The AIR constants of the layouts crate
+    /// are taken from this table.
+    pub const fn builtin_ratios(&self) -> &'static [(SegmentKind, u32)] {
+        match self {
+            Self::Starknet => &[
+                (SegmentKind::Pedersen, 32),
+                (SegmentKind::RangeCheck, 16),
+                (SegmentKind::Ecdsa, 2048),
+                (SegmentKind::Bitwise, 64),
+                (SegmentKind::EcOp, 1024),
+                (SegmentKind::Poseidon, 32),
+            ],
+            Self::Recursive => &[
+                (SegmentKind::Pedersen, 128),
+                (SegmentKind::RangeCheck, 8),
+                (SegmentKind::Bitwise, 8),
+            ],
+            _ => &[],
+        }
+    }
+
+    /// Number of steps per instance of `builtin`. `None` if the layout has no
+    /// entry for it in [Self::builtin_ratios].
+    pub const fn builtin_ratio(&self, builtin: SegmentKind) -> Option<u32> {
+        let ratios = self.builtin_ratios();
+        let mut i = 0;
+        while i < ratios.len() {
+            let (kind, ratio) = ratios[i];
+            if kind as u8 == builtin as u8 {
+                return Some(ratio);
+            }
+            i += 1;
         }
+        None
+    }
+
+    /// Returns true if the layout has an output builtin. Only the plain
+    /// layout doesn't.
+    pub const fn has_output(&self) -> bool {
+        !matches!(self, Self::Plain)
     }
 
-    pub const fn from_sharp_code(code: u128) -> Self {
-        match code {
-            Self::SHARP_CODE_STARKNET => Self::Starknet,
-            Self::SHARP_CODE_RECURSIVE => Self::Recursive,
-            _ => unimplemented!(),
+    /// Builtins whose memory segments must be present in programs run with
+    /// this layout. Matches the layouts in cairo-lang.
+    pub const fn required_builtins(&self) -> &'static [SegmentKind] {
+        use SegmentKind::*;
+        match self {
+            Self::Plain => &[],
+            Self::Small | Self::Dex => &[Pedersen, RangeCheck, Ecdsa],
+            Self::Recursive => &[Pedersen, RangeCheck, Bitwise],
+            Self::RecursiveLargeOutput => &[Pedersen, RangeCheck, Bitwise, Poseidon],
+            Self::AllSolidity => &[Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp],
+            Self::Starknet => &[Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Poseidon],
+            Self::StarknetWithKeccak => {
+                &[Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Keccak, Poseidon]
+            }
         }
     }
+
+    pub fn from_sharp_code(code: U256) -> Result<Self, UnknownLayoutCode> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.sharp_code() == code)
+            .ok_or(UnknownLayoutCode(code))
+    }
+}
+
+impl FromStr for Layout {
+    type Err = UnknownLayout;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.to_string() == s)
+            .ok_or_else(|| UnknownLayout(s.to_string()))
+    }
 }
 
+/// Layouts are encoded as their SHARP code in 16 big-endian bytes. This is
+/// the encoding used by proofs serialized before SHARP codes became [U256].
+/// Codes that don't fit (layouts with names longer than 16 characters e.g.
+/// [Layout::StarknetWithKeccak]) can't be serialized.
 impl CanonicalSerialize for Layout {
     fn serialize_with_mode<W: ark_serialize::Write>(
         &self,
         writer: W,
         compress: ark_serialize::Compress,
     ) -> Result<(), ark_serialize::SerializationError> {
-        self.sharp_code()
+        u128::try_from(self.sharp_code())
+            .map_err(|_| ark_serialize::SerializationError::NotEnoughSpace)?
             .to_be_bytes()
             .serialize_with_mode(writer, compress)
     }
@@ -138,9 +306,10 @@ impl CanonicalDeserialize for Layout {
         compress: ark_serialize::Compress,
         validate: ark_serialize::Validate,
     ) -> Result<Self, ark_serialize::SerializationError> {
-        Ok(Self::from_sharp_code(u128::from_be_bytes(
-            <[u8; 16]>::deserialize_with_mode(reader, compress, validate)?,
-        )))
+        let code = U256::from(u128::from_be_bytes(<[u8; 16]>::deserialize_with_mode(
+            reader, compress, validate,
+        )?));
+        Self::from_sharp_code(code).map_err(|_| ark_serialize::SerializationError::InvalidData)
     }
 }
 
@@ -149,15 +318,59 @@ pub struct RegisterStates(Vec<RegisterState>);
 
 impl RegisterStates {
     /// Parses trace data in the format outputted by a `cairo-run`.
-    pub fn from_reader(r: impl Read) -> Self {
-        // TODO: errors
+    pub fn from_reader(r: impl Read) -> Result<Self, ParseError> {
         let mut reader = BufReader::new(r);
         let mut register_states = Vec::new();
-        while reader.has_data_left().unwrap() {
-            let entry: RegisterState = bincode::deserialize_from(&mut reader).unwrap();
-            register_states.push(entry);
+        while reader.has_data_left()? {
+            let ap = read_u64_le(&mut reader)? as usize;
+            let fp = read_u64_le(&mut reader)? as usize;
+            let pc = read_u64_le(&mut reader)? as usize;
+            register_states.push(RegisterState { ap, fp, pc });
+        }
+        Ok(RegisterStates(register_states))
+    }
+
+    /// Formats the register states of steps `[start, end)` as a table.
+    /// The range is clamped to the length of the trace.
+    pub fn display_range(&self, start: usize, end: usize) -> impl Display + '_ {
+        let end = min(end, self.len());
+        let start = min(start, end);
+        RegisterStatesTable {
+            first_step: start,
+            states: &self[start..end],
+        }
+    }
+}
+
+/// Number of rows shown when displaying [RegisterStates]
+const REGISTER_STATES_DISPLAY_ROWS: usize = 10;
+
+impl Display for RegisterStates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_range(0, REGISTER_STATES_DISPLAY_ROWS))?;
+        if self.len() > REGISTER_STATES_DISPLAY_ROWS {
+            writeln!(
+                f,
+                "... {} more rows",
+                self.len() - REGISTER_STATES_DISPLAY_ROWS
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct RegisterStatesTable<'a> {
+    first_step: usize,
+    states: &'a [RegisterState],
+}
+
+impl Display for RegisterStatesTable<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:>8}  registers", "step")?;
+        for (i, state) in self.states.iter().enumerate() {
+            writeln!(f, "{:>8}  {state}", self.first_step + i)?;
         }
-        RegisterStates(register_states)
+        Ok(())
     }
 }
 
@@ -174,11 +387,10 @@ pub struct Memory<F>(Vec<Option<Word<F>>>);
 
 impl<F: Field> Memory<F> {
     /// Parses the partial memory data outputted by a `cairo-run`.
-    pub fn from_reader(r: impl Read) -> Self
+    pub fn from_reader(r: impl Read) -> Result<Self, ParseError>
     where
         F: PrimeField,
     {
-        // TODO: errors
         // TODO: each builtin has its own memory segment.
         // check it also contains other builtins
         // this file contains the contiguous memory segments:
@@ -187,28 +399,186 @@ impl<F: Field> Memory<F> {
         // - builtin 0
         // - builtin 1
         // - ...
-        let mut reader = BufReader::new(r);
-        let mut partial_memory = Vec::new();
-        let mut max_address = 0;
-        let mut word_bytes = Vec::new();
-        word_bytes.resize(field_bytes::<F>(), 0);
-        while reader.has_data_left().unwrap() {
-            // TODO: ensure always deserializes u64 and both are always little-endian
-            let address = bincode::deserialize_from(&mut reader).unwrap();
-            reader.read_exact(&mut word_bytes).unwrap();
-            let word = U256::try_from_le_slice(&word_bytes).unwrap();
-            partial_memory.push((address, Word::new(word)));
-            max_address = std::cmp::max(max_address, address);
-        }
+        let partial_memory = read_memory_cells(r)?;
+        let max_address = partial_memory.iter().map(|&(address, _)| address).max();
 
         // TODO: DOC: None used for nondeterministic values?
-        let mut memory = vec![None; max_address + 1];
-        for (address, word) in partial_memory {
-            // TODO: once arkworks v4 release remove num_bigint
-            memory[address] = Some(word);
+        let mut memory = Self::with_capacity(max_address.unwrap_or_default());
+        memory.extend(partial_memory);
+        Ok(memory)
+    }
+
+    /// Returns the word at `address` or `None` if the cell is empty or out of
+    /// bounds
+    pub fn get_checked(&self, address: usize) -> Option<Word<F>> {
+        self.get(address).copied().flatten()
+    }
+
+    /// Creates an empty memory with room for addresses up to `max_addr`
+    pub fn with_capacity(max_addr: usize) -> Self {
+        Memory(Vec::with_capacity(max_addr + 1))
+    }
+
+    /// Creates a memory holding the values of `entries`. Unspecified cells
+    /// are left empty.
+    pub fn from_entries(entries: impl IntoIterator<Item = MemoryEntry<F>>) -> Self
+    where
+        F: PrimeField,
+    {
+        entries
+            .into_iter()
+            .map(|entry| (entry.address.into(), entry.to_word()))
+            .collect()
+    }
+
+    /// Returns the `(address, word)` pairs of all occupied cells sorted by
+    /// address
+    pub fn sorted_accesses(&self) -> Vec<(usize, Word<F>)> {
+        self.iter()
+            .enumerate()
+            .filter_map(|(address, word)| Some((address, (*word)?)))
+            .collect()
+    }
+
+    /// Consuming version of [Memory::sorted_accesses]
+    pub fn into_sorted_accesses(self) -> Vec<(usize, Word<F>)> {
+        self.into_iter().collect()
+    }
+
+    /// Checks the cells pointed to by the `pc` of each register state hold
+    /// valid instructions
+    pub fn validate_instructions(
+        &self,
+        register_states: &RegisterStates,
+    ) -> Result<(), InvalidInstruction> {
+        for &RegisterState { pc, .. } in register_states.iter() {
+            let instruction = self
+                .get(pc)
+                .copied()
+                .flatten()
+                .ok_or(InvalidInstruction::Missing { address: pc })?;
+            instruction.validate()?;
         }
+        Ok(())
+    }
+
+    /// Number of cells that hold a value
+    pub fn occupied_count(&self) -> usize {
+        self.iter().filter(|word| word.is_some()).count()
+    }
+
+    /// Checks every public memory entry matches the value of its cell.
+    /// Returns the first entry that doesn't match or points to an empty cell.
+    pub fn validate_public_memory(
+        &self,
+        entries: &[MemoryEntry<U256>],
+    ) -> Result<(), InconsistencyError<F>> {
+        for &MemoryEntry { address, value } in entries {
+            let memory_value = self.get_checked(address.into());
+            if memory_value.map(|word| word.0) != Some(value) {
+                return Err(InconsistencyError {
+                    address,
+                    memory_value,
+                    claimed_value: value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a hex dump of the occupied cells in `[start_addr, end_addr)`.
+    /// Each line has the form `0x{address:08x}: {bytes} | {ascii}` where
+    /// `bytes` are the 32 little-endian bytes of the cell's value and `ascii`
+    /// shows printable bytes (other bytes are shown as `.`).
+    pub fn hex_dump(
+        &self,
+        writer: &mut impl Write,
+        start_addr: usize,
+        end_addr: usize,
+    ) -> std::io::Result<()> {
+        let end_addr = min(end_addr, self.len());
+        for address in start_addr..end_addr {
+            let Some(word) = self[address] else {
+                continue;
+            };
+            let bytes = word.0.to_le_bytes::<32>();
+            let hex = bytes.map(|b| format!("{b:02x}")).join(" ");
+            let ascii = bytes
+                .iter()
+                .map(|&b| match b {
+                    0x20..=0x7e => b as char,
+                    _ => '.',
+                })
+                .collect::<String>();
+            writeln!(writer, "{address:#010x}: {hex} | {ascii}")?;
+        }
+        Ok(())
+    }
+
+    /// Writes a hex dump of the cells in `[begin_addr, stop_ptr)` of a segment
+    pub fn hex_dump_segment(
+        &self,
+        segment: &Segment,
+        writer: &mut impl Write,
+    ) -> std::io::Result<()> {
+        self.hex_dump(writer, segment.begin_addr.into(), segment.stop_ptr.into())
+    }
+
+    /// Returns the occupied cells in `[begin_addr, stop_ptr)` of a segment
+    /// e.g. the values written to the output segment
+    pub fn read_segment(&self, segment: Segment) -> Vec<MemoryEntry<F>>
+    where
+        F: PrimeField,
+    {
+        let Segment {
+            begin_addr,
+            stop_ptr,
+        } = segment;
+        (begin_addr.0..stop_ptr.0)
+            .map(MemoryAddress)
+            .filter_map(|address| {
+                let word = (*self.get(usize::from(address))?)?;
+                Some(MemoryEntry::from_word(address, word))
+            })
+            .collect()
+    }
+}
+
+impl<F: Field> Extend<(usize, Word<F>)> for Memory<F> {
+    fn extend<T: IntoIterator<Item = (usize, Word<F>)>>(&mut self, iter: T) {
+        for (address, word) in iter {
+            if address >= self.0.len() {
+                self.0.resize(address + 1, None);
+            }
+            self.0[address] = Some(word);
+        }
+    }
+}
+
+impl<F: Field> FromIterator<(usize, Word<F>)> for Memory<F> {
+    fn from_iter<T: IntoIterator<Item = (usize, Word<F>)>>(iter: T) -> Self {
+        let mut memory = Memory(Vec::new());
+        memory.extend(iter);
+        memory
+    }
+}
 
-        Memory(memory)
+/// Iterates over the occupied cells in order of address
+impl<F: Field> IntoIterator for Memory<F> {
+    type Item = (usize, Word<F>);
+    type IntoIter = FilterMap<
+        Enumerate<std::vec::IntoIter<Option<Word<F>>>>,
+        fn((usize, Option<Word<F>>)) -> Option<(usize, Word<F>)>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        fn occupied<F>((address, word): (usize, Option<Word<F>>)) -> Option<(usize, Word<F>)> {
+            Some((address, word?))
+        }
+        self.0
+            .into_iter()
+            .enumerate()
+            .filter_map(occupied as fn(_) -> _)
     }
 }
 
@@ -220,9 +590,147 @@ impl<F: Field> Deref for Memory<F> {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Sparse alternative to [Memory] that only stores occupied cells. Uses less
+/// space than [Memory] for programs that access high addresses at the cost of
+/// slower accesses.
+#[derive(Debug)]
+pub struct SparseMemory<F>(HashMap<usize, Word<F>>);
+
+impl<F: Field> SparseMemory<F> {
+    pub fn new() -> Self {
+        SparseMemory(HashMap::new())
+    }
+
+    /// Parses the partial memory data outputted by a `cairo-run`.
+    pub fn from_reader(r: impl Read) -> Result<Self, ParseError>
+    where
+        F: PrimeField,
+    {
+        Ok(read_memory_cells(r)?.into_iter().collect())
+    }
+
+    /// Returns the word at `address` or `None` if the cell is empty
+    pub fn get_checked(&self, address: usize) -> Option<Word<F>> {
+        self.0.get(&address).copied()
+    }
+
+    /// Number of cells that hold a value
+    pub fn occupied_count(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns the `(address, word)` pairs of all occupied cells sorted by
+    /// address
+    pub fn sorted_accesses(&self) -> Vec<(usize, Word<F>)> {
+        let mut accesses = self.0.iter().map(|(&a, &w)| (a, w)).collect::<Vec<_>>();
+        accesses.sort_unstable_by_key(|&(address, _)| address);
+        accesses
+    }
+}
+
+impl<F: Field> Default for SparseMemory<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Field> Extend<(usize, Word<F>)> for SparseMemory<F> {
+    fn extend<T: IntoIterator<Item = (usize, Word<F>)>>(&mut self, iter: T) {
+        self.0.extend(iter)
+    }
+}
+
+impl<F: Field> FromIterator<(usize, Word<F>)> for SparseMemory<F> {
+    fn from_iter<T: IntoIterator<Item = (usize, Word<F>)>>(iter: T) -> Self {
+        SparseMemory(HashMap::from_iter(iter))
+    }
+}
+
+/// Iterates over the occupied cells in arbitrary order
+impl<F: Field> IntoIterator for SparseMemory<F> {
+    type Item = (usize, Word<F>);
+    type IntoIter = std::collections::hash_map::IntoIter<usize, Word<F>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Storage for the cells of a Cairo memory. [Memory] is dense and best for
+/// the mostly contiguous memory of a typical `cairo-run`. [SparseMemory] is
+/// best when a program accesses a few cells at high addresses.
+pub trait MemoryStore<F: Field>:
+    Sized
+    + Extend<(usize, Word<F>)>
+    + FromIterator<(usize, Word<F>)>
+    + IntoIterator<Item = (usize, Word<F>)>
+{
+    /// Parses the partial memory data outputted by a `cairo-run`.
+    fn from_reader(r: impl Read) -> Result<Self, ParseError>
+    where
+        F: PrimeField;
+
+    /// Returns the word at `address` or `None` if the cell is empty
+    fn get_checked(&self, address: usize) -> Option<Word<F>>;
+
+    /// Number of cells that hold a value
+    fn occupied_count(&self) -> usize;
+}
+
+impl<F: Field> MemoryStore<F> for Memory<F> {
+    fn from_reader(r: impl Read) -> Result<Self, ParseError>
+    where
+        F: PrimeField,
+    {
+        Memory::from_reader(r)
+    }
+
+    fn get_checked(&self, address: usize) -> Option<Word<F>> {
+        Memory::get_checked(self, address)
+    }
+
+    fn occupied_count(&self) -> usize {
+        Memory::occupied_count(self)
+    }
+}
+
+impl<F: Field> MemoryStore<F> for SparseMemory<F> {
+    fn from_reader(r: impl Read) -> Result<Self, ParseError>
+    where
+        F: PrimeField,
+    {
+        SparseMemory::from_reader(r)
+    }
+
+    fn get_checked(&self, address: usize) -> Option<Word<F>> {
+        SparseMemory::get_checked(self, address)
+    }
+
+    fn occupied_count(&self) -> usize {
+        SparseMemory::occupied_count(self)
+    }
+}
+
+/// Reads the `(address, word)` pairs of a memory file outputted by `cairo-run`
+fn read_memory_cells<F: PrimeField>(r: impl Read) -> Result<Vec<(usize, Word<F>)>, ParseError> {
+    let mut reader = BufReader::new(r);
+    let mut cells = Vec::new();
+    let mut word_bytes = Vec::new();
+    word_bytes.resize(field_bytes::<F>(), 0);
+    while reader.has_data_left()? {
+        let address = read_u64_le(&mut reader)? as usize;
+        reader.read_exact(&mut word_bytes)?;
+        // field elements are at most 32 bytes so always fit
+        let word = U256::from_le_slice(&word_bytes);
+        cells.push((address, Word::new(word)));
+    }
+    Ok(cells)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MemoryEntry<T> {
-    pub address: u32,
+    pub address: MemoryAddress,
     pub value: T,
 }
 
@@ -241,18 +749,66 @@ impl<T: CanonicalSerialize> CanonicalSerialize for MemoryEntry<T> {
     }
 }
 
+impl<T> MemoryEntry<T> {
+    /// Creates a memory entry. Errors if the address is reserved.
+    pub fn new_checked(address: MemoryAddress, value: T) -> Result<Self, AddressError> {
+        let entry = Self { address, value };
+        entry.validate_address()?;
+        Ok(entry)
+    }
+
+    /// Checks the address isn't 0 which is reserved for dummy accesses
+    pub fn validate_address(&self) -> Result<(), AddressError> {
+        if self.address == MemoryAddress(0) {
+            return Err(AddressError::Zero);
+        }
+        Ok(())
+    }
+
+    /// Address as a field element e.g. for evaluating the memory constraints
+    pub fn address_as_felt<F: Field>(&self) -> F {
+        self.address.into_felt()
+    }
+}
+
+impl<F: PrimeField> MemoryEntry<F> {
+    pub fn from_word(address: MemoryAddress, word: Word<F>) -> Self {
+        MemoryEntry {
+            address,
+            value: word.into_felt(),
+        }
+    }
+
+    pub fn to_word(&self) -> Word<F> {
+        Word::from_felt(self.value)
+    }
+}
+
+impl<F: PrimeField> From<(MemoryAddress, Word<F>)> for MemoryEntry<F> {
+    fn from((address, word): (MemoryAddress, Word<F>)) -> Self {
+        Self::from_word(address, word)
+    }
+}
+
 impl MemoryEntry<U256> {
     /// Converts into an equivalent memory entry where the value is a field
-    /// element. Returns none if the value is outside the range of the field.
-    pub fn try_into_felt_entry<F: PrimeField>(self) -> Option<MemoryEntry<F>> {
+    /// element. Errors if the address is reserved or if the value is outside
+    /// the range of the field.
+    pub fn try_into_felt_entry<F: PrimeField>(self) -> Result<MemoryEntry<F>, InvalidMemoryEntry> {
+        self.validate_address()?;
         let value = BigUint::from(self.value);
-        if value < F::MODULUS.into() {
-            Some(MemoryEntry {
+        let modulus: BigUint = F::MODULUS.into();
+        if value < modulus {
+            Ok(MemoryEntry {
                 address: self.address,
                 value: value.into(),
             })
         } else {
-            None
+            Err(InvalidFieldElementError {
+                value: self.value,
+                modulus: U256::try_from(modulus).unwrap(),
+            }
+            .into())
         }
     }
 }
@@ -271,502 +827,3797 @@ impl<T: CanonicalDeserialize> CanonicalDeserialize for MemoryEntry<T> {
         validate: ark_serialize::Validate,
     ) -> Result<Self, ark_serialize::SerializationError> {
         let value = T::deserialize_with_mode(&mut reader, compress, validate)?;
-        let address = u32::deserialize_with_mode(reader, compress, validate)?;
+        let address = MemoryAddress::deserialize_with_mode(reader, compress, validate)?;
         Ok(Self { value, address })
     }
 }
 
-#[derive(
-    Serialize,
-    Deserialize,
-    Clone,
-    Copy,
-    Debug,
-    PartialEq,
-    Eq,
-    CanonicalSerialize,
-    CanonicalDeserialize,
-)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Segment {
-    pub begin_addr: u32,
-    pub stop_ptr: u32,
+    pub begin_addr: MemoryAddress,
+    pub stop_ptr: MemoryAddress,
+}
+
+impl Segment {
+    /// Returns `None` if `stop_ptr < begin_addr`
+    pub const fn new_checked(begin_addr: MemoryAddress, stop_ptr: MemoryAddress) -> Option<Self> {
+        if stop_ptr.0 < begin_addr.0 {
+            return None;
+        }
+        Some(Segment {
+            begin_addr,
+            stop_ptr,
+        })
+    }
+
+    /// Segment starting at `begin_addr` that holds `count` builtin instances
+    pub fn from_instances(begin_addr: MemoryAddress, count: u32, cells_per_instance: u32) -> Self {
+        Segment {
+            begin_addr,
+            stop_ptr: begin_addr + count * cells_per_instance,
+        }
+    }
+
+    /// Number of cells in the segment
+    pub fn len(&self) -> u32 {
+        self.stop_ptr - self.begin_addr
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of whole builtin instances in the segment
+    pub fn instance_count(&self, cells_per_instance: u32) -> u32 {
+        self.len() / cells_per_instance
+    }
+
+    /// Splits the segment into `[begin_addr, split_addr)` and
+    /// `[split_addr, stop_ptr)`. Both parts must be non-empty.
+    pub const fn split_at(
+        self,
+        split_addr: MemoryAddress,
+    ) -> Result<(Segment, Segment), SplitError> {
+        if split_addr.0 <= self.begin_addr.0 || split_addr.0 >= self.stop_ptr.0 {
+            return Err(SplitError::AddressOutOfRange(split_addr));
+        }
+        Ok((
+            Segment {
+                begin_addr: self.begin_addr,
+                stop_ptr: split_addr,
+            },
+            Segment {
+                begin_addr: split_addr,
+                stop_ptr: self.stop_ptr,
+            },
+        ))
+    }
+
+    /// Shrinks the segment to at most `max_size` cells
+    pub fn truncate_to_size(self, max_size: u32) -> Segment {
+        Segment {
+            begin_addr: self.begin_addr,
+            stop_ptr: min(
+                self.stop_ptr,
+                MemoryAddress(self.begin_addr.0.saturating_add(max_size)),
+            ),
+        }
+    }
 }
 
-#[derive(Deserialize, Clone, Copy, Debug, CanonicalDeserialize, CanonicalSerialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MemorySegments {
     pub program: Segment,
     pub execution: Segment,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub output: Option<Segment>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub pedersen: Option<Segment>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub range_check: Option<Segment>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub ecdsa: Option<Segment>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub bitwise: Option<Segment>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub ec_op: Option<Segment>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub poseidon: Option<Segment>,
+    /// NOTE: this field is part of the `CanonicalSerialize` encoding of the
+    /// public input which seeds the public coin. Adding it changed the proof
+    /// transcript so proofs generated before it was added don't verify.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub keccak: Option<Segment>,
 }
 
-#[derive(Deserialize, Clone, Debug, CanonicalDeserialize, CanonicalSerialize)]
-#[serde(bound = "F: PrimeField")]
-pub struct AirPublicInput<F: Field> {
-    pub rc_min: u16,
-    pub rc_max: u16,
-    pub n_steps: u64,
-    pub layout: Layout,
-    pub memory_segments: MemorySegments,
-    #[serde(deserialize_with = "deserialize_hex_str_memory_entries")]
-    pub public_memory: Vec<MemoryEntry<F>>,
-}
+impl MemorySegments {
+    /// Creates memory segments without any builtin or output segments. Use the
+    /// `with_*_segment` methods to add them.
+    pub const fn new(program: Segment, execution: Segment) -> Self {
+        Self {
+            program,
+            execution,
+            output: None,
+            pedersen: None,
+            range_check: None,
+            ecdsa: None,
+            bitwise: None,
+            ec_op: None,
+            poseidon: None,
+            keccak: None,
+        }
+    }
 
-impl<F: Field> AirPublicInput<F> {
-    pub fn initial_pc(&self) -> u32 {
-        self.memory_segments.program.begin_addr
+    pub const fn with_output_segment(mut self, segment: Segment) -> Self {
+        self.output = Some(segment);
+        self
     }
 
-    pub fn final_pc(&self) -> u32 {
-        self.memory_segments.program.stop_ptr
+    pub const fn without_output_segment(mut self) -> Self {
+        self.output = None;
+        self
     }
 
-    pub fn initial_ap(&self) -> u32 {
-        self.memory_segments.execution.begin_addr
+    pub const fn with_pedersen_segment(mut self, segment: Segment) -> Self {
+        self.pedersen = Some(segment);
+        self
     }
 
-    pub fn final_ap(&self) -> u32 {
-        self.memory_segments.execution.stop_ptr
+    pub const fn without_pedersen_segment(mut self) -> Self {
+        self.pedersen = None;
+        self
     }
 
-    pub fn public_memory_padding(&self) -> MemoryEntry<F> {
-        *self.public_memory.iter().find(|e| e.address == 1).unwrap()
+    pub const fn with_range_check_segment(mut self, segment: Segment) -> Self {
+        self.range_check = Some(segment);
+        self
     }
-}
 
-#[derive(Deserialize, Clone, Copy, Debug)]
-pub struct Signature {
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub r: U256,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub w: U256,
-}
+    pub const fn without_range_check_segment(mut self) -> Self {
+        self.range_check = None;
+        self
+    }
 
-#[derive(Deserialize, Clone, Copy, Debug)]
-pub struct EcdsaInstance {
-    pub index: u32,
-    #[serde(rename = "pubkey", deserialize_with = "deserialize_hex_str")]
-    pub pubkey_x: U256,
-    #[serde(rename = "msg", deserialize_with = "deserialize_hex_str")]
-    pub message: U256,
-    #[serde(rename = "signature_input")]
-    pub signature: Signature,
-}
+    pub const fn with_ecdsa_segment(mut self, segment: Segment) -> Self {
+        self.ecdsa = Some(segment);
+        self
+    }
 
-impl EcdsaInstance {
-    /// Get the memory address for this instance
-    /// Output is of the form (pubkey_addr, msg_addr)
-    pub fn mem_addr(&self, ecdsa_segment_addr: u32) -> (u32, u32) {
-        let instance_offset = ecdsa_segment_addr + self.index * 2;
-        (instance_offset, instance_offset + 1)
+    pub const fn without_ecdsa_segment(mut self) -> Self {
+        self.ecdsa = None;
+        self
     }
-}
 
-#[derive(Deserialize, Clone, Copy, Debug)]
-pub struct PedersenInstance {
-    pub index: u32,
-    #[serde(rename = "x", deserialize_with = "deserialize_hex_str")]
-    pub a: U256,
-    #[serde(rename = "y", deserialize_with = "deserialize_hex_str")]
-    pub b: U256,
-}
+    pub const fn with_bitwise_segment(mut self, segment: Segment) -> Self {
+        self.bitwise = Some(segment);
+        self
+    }
 
-impl PedersenInstance {
-    pub fn new_empty(index: u32) -> Self {
-        Self {
-            index,
-            a: U256::ZERO,
-            b: U256::ZERO,
-        }
+    pub const fn without_bitwise_segment(mut self) -> Self {
+        self.bitwise = None;
+        self
     }
 
-    /// Get the memory address for this instance
-    /// Output is of the form (a_addr, b_addr, output_addr)
-    pub fn mem_addr(&self, pedersen_segment_addr: u32) -> (u32, u32, u32) {
-        let instance_offset = pedersen_segment_addr + self.index * 3;
-        (instance_offset, instance_offset + 1, instance_offset + 2)
+    pub const fn with_ec_op_segment(mut self, segment: Segment) -> Self {
+        self.ec_op = Some(segment);
+        self
     }
-}
 
-#[derive(Deserialize, Clone, Copy, Debug)]
-pub struct RangeCheckInstance {
-    pub index: u32,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub value: U256,
-}
+    pub const fn without_ec_op_segment(mut self) -> Self {
+        self.ec_op = None;
+        self
+    }
 
-impl RangeCheckInstance {
-    pub fn new_empty(index: u32) -> Self {
-        Self {
-            index,
-            value: U256::ZERO,
-        }
+    pub const fn with_poseidon_segment(mut self, segment: Segment) -> Self {
+        self.poseidon = Some(segment);
+        self
     }
 
-    /// Get the memory address for this instance
-    pub fn mem_addr(&self, range_check_segment_addr: u32) -> u32 {
-        range_check_segment_addr + self.index
+    pub const fn without_poseidon_segment(mut self) -> Self {
+        self.poseidon = None;
+        self
     }
-}
-
-#[derive(Deserialize, Clone, Copy, Debug)]
-pub struct BitwiseInstance {
-    pub index: u32,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub x: U256,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub y: U256,
-}
 
-impl BitwiseInstance {
-    pub fn new_empty(index: u32) -> Self {
-        Self {
-            index,
-            x: U256::ZERO,
-            y: U256::ZERO,
-        }
+    pub const fn with_keccak_segment(mut self, segment: Segment) -> Self {
+        self.keccak = Some(segment);
+        self
     }
 
-    /// Get the memory address for this instance
-    /// Output is of the form (x_addr, y_addr, x&y_addr, x^y_addr, x|y_addr)
-    // TODO: better to use struct. Could cause bug if user gets ordering wrong.
-    pub fn mem_addr(&self, bitwise_segment_addr: u32) -> (u32, u32, u32, u32, u32) {
-        let instance_offset = bitwise_segment_addr + self.index * 5;
-        (
-            instance_offset,
-            instance_offset + 1,
-            instance_offset + 2,
-            instance_offset + 3,
-            instance_offset + 4,
-        )
+    pub const fn without_keccak_segment(mut self) -> Self {
+        self.keccak = None;
+        self
     }
-}
 
-/// Elliptic Curve operation instance for `p + m * q` on an elliptic curve
-#[derive(Deserialize, Clone, Copy, Debug)]
-pub struct EcOpInstance {
-    pub index: u32,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub p_x: U256,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub p_y: U256,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub q_x: U256,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub q_y: U256,
-    #[serde(deserialize_with = "deserialize_hex_str")]
-    pub m: U256,
-}
+    /// Initial value of the allocation pointer `ap`
+    pub const fn initial_ap(&self) -> MemoryAddress {
+        self.execution.begin_addr
+    }
 
-impl EcOpInstance {
-    /// Get the memory address for this instance
-    /// Output is of the form (p_x_addr, p_y_addr, q_x_addr, q_y_addr, m_addr,
-    /// r_x_addr, r_y_addr)
-    pub fn mem_addr(&self, ec_op_segment_addr: u32) -> (u32, u32, u32, u32, u32, u32, u32) {
-        let instance_offset = ec_op_segment_addr + self.index * 7;
-        (
-            instance_offset,
-            instance_offset + 1,
-            instance_offset + 2,
-            instance_offset + 3,
-            instance_offset + 4,
-            instance_offset + 5,
-            instance_offset + 6,
-        )
+    /// Initial value of the frame pointer `fp`.
+    /// NOTE: in proof mode `fp` starts at the same address as `ap` (the
+    /// return `fp` and `pc` are placed by the bootstrapping `call main`
+    /// instruction). The AIR constrains `fp_0 = ap_0` so this intentionally
+    /// isn't `begin_addr + 2`.
+    pub const fn initial_fp(&self) -> MemoryAddress {
+        self.execution.begin_addr
     }
-}
 
-#[derive(Deserialize, Clone, Copy, Debug)]
-pub struct PoseidonInstance {
-    pub index: u32,
-    #[serde(rename = "input_s0", deserialize_with = "deserialize_hex_str")]
-    pub input0: U256,
-    #[serde(rename = "input_s1", deserialize_with = "deserialize_hex_str")]
-    pub input1: U256,
-    #[serde(rename = "input_s2", deserialize_with = "deserialize_hex_str")]
-    pub input2: U256,
-}
+    /// Minimum size of the execution segment for a run that ends with
+    /// `ap = final_ap`. The memory outputted by `cairo-run` can be larger
+    /// since nondeterministic accesses (e.g. hints) can write above `ap`.
+    /// Returns `None` if `final_ap` is below `initial_ap`.
+    pub const fn execution_segment_min_size(
+        initial_ap: MemoryAddress,
+        final_ap: MemoryAddress,
+    ) -> Option<u32> {
+        final_ap.0.checked_sub(initial_ap.0)
+    }
 
-impl PoseidonInstance {
-    pub fn new_empty(index: u32) -> Self {
-        Self {
-            index,
-            input0: U256::ZERO,
-            input1: U256::ZERO,
-            input2: U256::ZERO,
+    /// Returns the segment of a builtin or `None` if the program doesn't use
+    /// it. Always `None` for the program, execution and output segments.
+    pub const fn segment_for_builtin(&self, builtin: SegmentKind) -> Option<Segment> {
+        match builtin {
+            SegmentKind::Pedersen => self.pedersen,
+            SegmentKind::RangeCheck => self.range_check,
+            SegmentKind::Ecdsa => self.ecdsa,
+            SegmentKind::Bitwise => self.bitwise,
+            SegmentKind::EcOp => self.ec_op,
+            SegmentKind::Keccak => self.keccak,
+            SegmentKind::Poseidon => self.poseidon,
+            SegmentKind::Program | SegmentKind::Execution | SegmentKind::Output => None,
         }
     }
 
-    /// Get the memory address for this instance
-    /// Output is of the form (input0_addr, input1_addr, input2_addr,
-    /// output0_addr, output1_addr, output2_addr)
-    pub fn mem_addr(&self, poseidon_segment_addr: u32) -> (u32, u32, u32, u32, u32, u32) {
-        let instance_offset = poseidon_segment_addr + self.index * 6;
-        (
-            instance_offset,
-            instance_offset + 1,
-            instance_offset + 2,
-            instance_offset + 3,
-            instance_offset + 4,
-            instance_offset + 5,
-        )
+    /// Iterates over the builtins that have a segment
+    pub fn active_builtins(&self) -> impl Iterator<Item = (SegmentKind, Segment)> + '_ {
+        SegmentKind::BUILTINS
+            .into_iter()
+            .filter_map(|builtin| Some((builtin, self.segment_for_builtin(builtin)?)))
     }
-}
 
-#[derive(Debug, Deserialize)]
-pub struct AirPrivateInput {
-    pub trace_path: PathBuf,
-    pub memory_path: PathBuf,
-    pub pedersen: Vec<PedersenInstance>,
-    pub range_check: Vec<RangeCheckInstance>,
-    #[serde(default)]
-    pub ecdsa: Vec<EcdsaInstance>,
-    #[serde(default)]
-    pub bitwise: Vec<BitwiseInstance>,
-    #[serde(default)]
-    pub ec_op: Vec<EcOpInstance>,
-    #[serde(default)]
-    pub poseidon: Vec<PoseidonInstance>,
+    /// Total number of memory cells used by builtins. Excludes the program,
+    /// execution and output segments.
+    pub fn total_builtin_cells(&self) -> u32 {
+        [
+            self.pedersen,
+            self.range_check,
+            self.ecdsa,
+            self.bitwise,
+            self.ec_op,
+            self.poseidon,
+            self.keccak,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|segment| segment.len())
+        .sum()
+    }
+
+    /// Ratio of builtin memory cells to steps. Each step produces at most one
+    /// builtin cell so this is at most 1 for valid programs.
+    pub fn builtin_memory_ratio(&self, n_steps: u64) -> f64 {
+        self.total_builtin_cells() as f64 / n_steps as f64
+    }
 }
 
-#[derive(Clone, Deserialize, Debug)]
-#[serde(bound = "F: PrimeField")]
-pub struct CompiledProgram<F: Field> {
-    #[serde(deserialize_with = "deserialize_vec_hex_str")]
-    pub data: Vec<F>,
-    pub prime: String,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SegmentKind {
+    Program,
+    Execution,
+    Output,
+    Pedersen,
+    RangeCheck,
+    Ecdsa,
+    Bitwise,
+    EcOp,
+    Keccak,
+    Poseidon,
 }
 
-impl<F: Field> CompiledProgram<F> {
-    pub fn program_memory(&self) -> Vec<MemoryEntry<F>> {
-        self.data
-            .iter()
-            .enumerate()
-            .map(|(i, &value)| {
-                // address 0 is reserved for dummy accesses (it's null pointer)
-                MemoryEntry {
-                    address: i as u32 + 1,
-                    value,
-                }
-            })
-            .collect()
-    }
+impl SegmentKind {
+    /// All segments in the order `cairo-run` lays them out in memory
+    pub const ALL: [Self; 10] = [
+        Self::Program,
+        Self::Execution,
+        Self::Output,
+        Self::Pedersen,
+        Self::RangeCheck,
+        Self::Ecdsa,
+        Self::Bitwise,
+        Self::EcOp,
+        Self::Keccak,
+        Self::Poseidon,
+    ];
+
+    /// Segments of builtins with instances i.e. all but the program,
+    /// execution and output segments
+    pub const BUILTINS: [Self; 7] = [
+        Self::Pedersen,
+        Self::RangeCheck,
+        Self::Ecdsa,
+        Self::Bitwise,
+        Self::EcOp,
+        Self::Keccak,
+        Self::Poseidon,
+    ];
 }
 
-/// Represents a Cairo word
-/// Value is a field element in the range `[0, Fp::MODULUS)`
-/// Stored as a U256 to make binary decompositions more efficient
+/// Converts between absolute memory addresses and offsets within a segment
 #[derive(Clone, Copy, Debug)]
-pub struct Word<F>(pub U256, PhantomData<F>);
+pub struct SegmentAddressMapper<'a> {
+    segments: &'a MemorySegments,
+}
 
-impl<F> Word<F> {
-    /// Calculates $\tilde{f_i}$ - https://eprint.iacr.org/2021/1063.pdf
-    pub fn get_flag_prefix(&self, flag: Flag) -> u16 {
-        if flag == Flag::Zero {
-            return 0;
-        }
+impl<'a> SegmentAddressMapper<'a> {
+    pub const fn new(segments: &'a MemorySegments) -> Self {
+        Self { segments }
+    }
 
-        let flag = flag as usize;
-        let prefix = self.0 >> (FLAGS_BIT_OFFSET + flag);
-        let mask = (uint!(1_U256) << (15 - flag)) - uint!(1_U256);
-        (prefix & mask).try_into().unwrap()
+    /// Returns the address range `[begin, end)` of a segment or `None` if the
+    /// segment isn't used
+    pub fn bounds(&self, kind: SegmentKind) -> Option<(MemoryAddress, MemoryAddress)> {
+        let segments = self.segments;
+        let segment = match kind {
+            // the program segment's `stop_ptr` is the final pc rather than the end
+            // of the program. The execution segment starts right after the program.
+            SegmentKind::Program => {
+                return Some((segments.program.begin_addr, segments.execution.begin_addr))
+            }
+            SegmentKind::Execution => segments.execution,
+            SegmentKind::Output => segments.output?,
+            SegmentKind::Pedersen => segments.pedersen?,
+            SegmentKind::RangeCheck => segments.range_check?,
+            SegmentKind::Ecdsa => segments.ecdsa?,
+            SegmentKind::Bitwise => segments.bitwise?,
+            SegmentKind::EcOp => segments.ec_op?,
+            SegmentKind::Keccak => segments.keccak?,
+            SegmentKind::Poseidon => segments.poseidon?,
+        };
+        Some((segment.begin_addr, segment.stop_ptr))
     }
 
-    pub fn get_op0_addr(&self, ap: usize, fp: usize) -> usize {
-        // TODO: put the if statement first good for rust quiz
-        self.get_off_op0() as usize + if self.get_flag(Flag::Op0Reg) { fp } else { ap }
-            - HALF_OFFSET
+    /// Returns the segment containing `addr` and the offset of `addr` within
+    /// that segment
+    pub fn classify(&self, addr: MemoryAddress) -> Option<(SegmentKind, u32)> {
+        SegmentKind::ALL.into_iter().find_map(|kind| {
+            let (begin, end) = self.bounds(kind)?;
+            (begin..end).contains(&addr).then(|| (kind, addr - begin))
+        })
     }
 
-    pub fn get_dst_addr(&self, ap: usize, fp: usize) -> usize {
-        self.get_off_dst() as usize + if self.get_flag(Flag::DstReg) { fp } else { ap }
-            - HALF_OFFSET
+    /// Returns the absolute address of `offset` within a segment or `None` if
+    /// the offset is outside the segment
+    pub fn to_absolute(&self, kind: SegmentKind, offset: u32) -> Option<MemoryAddress> {
+        let (begin, end) = self.bounds(kind)?;
+        let addr = MemoryAddress(begin.0.checked_add(offset)?);
+        (addr < end).then_some(addr)
     }
+}
 
-    pub fn get_flag(&self, flag: Flag) -> bool {
-        self.0.bit(FLAGS_BIT_OFFSET + flag as usize)
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F: PrimeField"))]
+pub struct AirPublicInput<F: Field> {
+    pub rc_min: u16,
+    pub rc_max: u16,
+    pub n_steps: u64,
+    pub layout: Layout,
+    pub memory_segments: MemorySegments,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "serialize_hex_str_memory_entries",
+            deserialize_with = "deserialize_hex_str_memory_entries"
+        )
+    )]
+    pub public_memory: Vec<MemoryEntry<F>>,
+}
+
+impl<F: Field> AirPublicInput<F> {
+    pub fn initial_pc(&self) -> MemoryAddress {
+        self.memory_segments.program.begin_addr
     }
 
-    pub fn get_off_dst(&self) -> u16 {
-        let prefix = self.0 >> OFF_DST_BIT_OFFSET;
-        let mask = U256::from(OFF_MASK);
-        (prefix & mask).try_into().unwrap()
+    pub fn final_pc(&self) -> MemoryAddress {
+        self.memory_segments.program.stop_ptr
     }
 
-    pub fn get_off_op0(&self) -> u16 {
-        let prefix = self.0 >> OFF_OP0_BIT_OFFSET;
-        let mask = U256::from(OFF_MASK);
-        (prefix & mask).try_into().unwrap()
+    pub fn initial_ap(&self) -> MemoryAddress {
+        self.memory_segments.initial_ap()
     }
 
-    pub fn get_off_op1(&self) -> u16 {
-        let prefix = self.0 >> OFF_OP1_BIT_OFFSET;
-        let mask = U256::from(OFF_MASK);
-        (prefix & mask).try_into().unwrap()
+    pub fn initial_fp(&self) -> MemoryAddress {
+        self.memory_segments.initial_fp()
     }
 
-    pub fn get_flag_group(&self, flag_group: FlagGroup) -> u8 {
-        match flag_group {
-            FlagGroup::DstReg => self.get_flag(Flag::DstReg) as u8,
-            FlagGroup::Op0Reg => self.get_flag(Flag::Op0Reg) as u8,
-            FlagGroup::Op1Src => {
-                self.get_flag(Flag::Op1Imm) as u8
-                    + self.get_flag(Flag::Op1Fp) as u8 * 2
-                    + self.get_flag(Flag::Op1Ap) as u8 * 4
+    pub fn final_ap(&self) -> MemoryAddress {
+        self.memory_segments.execution.stop_ptr
+    }
+
+    /// Checks the values of all range check instances lie in
+    /// `[rc_min, rc_max]`
+    pub fn validate_range_checks(&self, private: &AirPrivateInput) -> Result<(), RangeCheckError> {
+        let (rc_min, rc_max) = (self.rc_min, self.rc_max);
+        for instance in &private.range_check {
+            if !instance.is_valid_for_range(rc_min, rc_max) {
+                return Err(RangeCheckError {
+                    index: instance.index,
+                    value: instance.value,
+                    rc_min,
+                    rc_max,
+                });
             }
-            FlagGroup::ResLogic => {
-                self.get_flag(Flag::ResAdd) as u8 + self.get_flag(Flag::ResMul) as u8 * 2
+        }
+        Ok(())
+    }
+
+    /// Checks the memory segments are in the order `cairo-run` lays them out:
+    /// program, execution, output and then the builtins. Segments mustn't
+    /// overlap and the segment after the execution segment must start where
+    /// the execution segment stops. Gaps are allowed elsewhere since the
+    /// program segment's `stop_ptr` is the final pc and each builtin segment
+    /// is allocated for its padded number of instances.
+    pub fn validate_segment_adjacency(&self) -> Result<(), SegmentError> {
+        let segments = &self.memory_segments;
+        let ordered = [
+            (SegmentKind::Program, Some(segments.program)),
+            (SegmentKind::Execution, Some(segments.execution)),
+            (SegmentKind::Output, segments.output),
+        ]
+        .into_iter()
+        .chain(
+            SegmentKind::BUILTINS
+                .into_iter()
+                .map(|builtin| (builtin, segments.segment_for_builtin(builtin))),
+        )
+        .filter_map(|(kind, segment)| Some((kind, segment?)))
+        .collect::<Vec<_>>();
+
+        for pair in ordered.windows(2) {
+            let [(a_kind, a), (b_kind, b)] = [pair[0], pair[1]];
+            if b.begin_addr < a.begin_addr {
+                return Err(SegmentError::WrongOrder {
+                    expected_before: a_kind,
+                    expected_after: b_kind,
+                });
             }
-            FlagGroup::PcUpdate => {
-                self.get_flag(Flag::PcJumpAbs) as u8
-                    + self.get_flag(Flag::PcJumpRel) as u8 * 2
-                    + self.get_flag(Flag::PcJnz) as u8 * 4
+            if b.begin_addr < a.stop_ptr {
+                return Err(SegmentError::Overlap {
+                    a: a_kind,
+                    b: b_kind,
+                });
             }
-            FlagGroup::ApUpdate => {
-                self.get_flag(Flag::ApAdd) as u8 + self.get_flag(Flag::ApAdd1) as u8 * 2
+            if a_kind == SegmentKind::Execution && b.begin_addr > a.stop_ptr {
+                return Err(SegmentError::Gap {
+                    after: a_kind,
+                    before: b_kind,
+                    gap_size: b.begin_addr.0 - a.stop_ptr.0,
+                });
             }
-            FlagGroup::Opcode => {
-                self.get_flag(Flag::OpcodeCall) as u8
-                    + self.get_flag(Flag::OpcodeRet) as u8 * 2
-                    + self.get_flag(Flag::OpcodeAssertEq) as u8 * 4
+        }
+        Ok(())
+    }
+
+    /// Checks the memory segments include every builtin required by the layout
+    pub fn validate_layout_builtins(&self) -> Result<(), MissingBuiltin> {
+        let segments = &self.memory_segments;
+        for &builtin in self.layout.required_builtins() {
+            if segments.segment_for_builtin(builtin).is_none() {
+                return Err(MissingBuiltin {
+                    layout: self.layout,
+                    builtin,
+                });
             }
         }
+        Ok(())
     }
-}
 
-impl<F: PrimeField> Word<F> {
-    pub fn new(word: U256) -> Self {
-        let modulus: BigUint = F::MODULUS.into();
-        debug_assert!(BigUint::from(word) < modulus);
-        Word(word, PhantomData)
+    /// Number of instances of each builtin implied by the size of its
+    /// memory segment. A partially filled last instance is counted.
+    pub fn builtin_instance_counts(&self) -> BuiltinCounter {
+        let count = |segment: Option<Segment>, cells_per_instance: u32| {
+            segment.map_or(0, |s| s.len().div_ceil(cells_per_instance)) as usize
+        };
+        let segments = &self.memory_segments;
+        BuiltinCounter {
+            pedersen: count(segments.pedersen, PedersenInstance::CELLS_PER_INSTANCE),
+            ecdsa: count(segments.ecdsa, EcdsaInstance::CELLS_PER_INSTANCE),
+            range_check: count(segments.range_check, RangeCheckInstance::CELLS_PER_INSTANCE),
+            bitwise: count(segments.bitwise, BitwiseInstance::CELLS_PER_INSTANCE),
+            ec_op: count(segments.ec_op, EcOpInstance::CELLS_PER_INSTANCE),
+            poseidon: count(segments.poseidon, PoseidonInstance::CELLS_PER_INSTANCE),
+            keccak: count(segments.keccak, KeccakInstance::CELLS_PER_INSTANCE),
+        }
     }
 
-    pub fn get_op0(&self, ap: usize, fp: usize, mem: &Memory<F>) -> F {
-        mem[self.get_op0_addr(ap, fp)].unwrap().into_felt()
+    pub fn public_memory_padding(&self) -> MemoryEntry<F> {
+        *self
+            .public_memory
+            .iter()
+            .find(|e| e.address == MemoryAddress(1))
+            .unwrap()
     }
 
-    pub fn get_dst(&self, ap: usize, fp: usize, mem: &Memory<F>) -> F {
-        mem[self.get_dst_addr(ap, fp)].unwrap().into_felt()
+    /// Values of the output segment in address order. Returns `None` if there
+    /// is no output segment.
+    pub fn output_values(&self) -> Option<Vec<F>> {
+        let output = self.memory_segments.output?;
+        let output = output.begin_addr..output.stop_ptr;
+        let mut entries = self
+            .public_memory
+            .iter()
+            .filter(|entry| output.contains(&entry.address))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.address);
+        Some(entries.into_iter().map(|entry| entry.value).collect())
     }
 
-    pub fn get_op1_addr(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> usize {
-        self.get_off_op1() as usize
-            + match self.get_flag_group(FlagGroup::Op1Src) {
-                0 => usize::try_from(mem[self.get_op0_addr(ap, fp)].unwrap().0).unwrap(),
-                1 => pc,
-                2 => fp,
-                4 => ap,
-                _ => unreachable!(),
+    /// Checks the public memory fits in the memory argument and only contains
+    /// the program, the execution segment's initial and final stack or output.
+    pub fn validate_public_memory_size(&self) -> Result<(), PublicMemorySizeError> {
+        let entries = self.public_memory.len();
+        if entries as u64 > self.n_steps {
+            return Err(PublicMemorySizeError::TooManyEntries {
+                entries,
+                n_steps: self.n_steps,
+            });
+        }
+
+        let segments = &self.memory_segments;
+        // the program segment's stop_ptr is the final pc so the program is
+        // bounded by the start of the execution segment
+        let program = segments.program.begin_addr..segments.execution.begin_addr;
+        let execution = segments.execution.begin_addr..segments.execution.stop_ptr;
+        let output = segments.output.map(|s| s.begin_addr..s.stop_ptr);
+        for &MemoryEntry { address, .. } in &self.public_memory {
+            let in_output = output
+                .as_ref()
+                .is_some_and(|output| output.contains(&address));
+            if !program.contains(&address) && !execution.contains(&address) && !in_output {
+                return Err(PublicMemorySizeError::AddressOutOfRange { address });
             }
-            - HALF_OFFSET
+        }
+        Ok(())
     }
+}
 
-    pub fn get_op1(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> F {
-        mem[self.get_op1_addr(pc, ap, fp, mem)].unwrap().into_felt()
+impl<F: PrimeField> AirPublicInput<F> {
+    /// Hash of the public memory as `(address, value)` pairs sorted by address.
+    /// Matches the hash StarkWare's verifier computes over the public memory.
+    pub fn public_memory_hash<D: Digest<OutputSize = U32>>(&self) -> [u8; 32] {
+        let mut entries = self.public_memory.clone();
+        entries.sort_by_key(|entry| entry.address);
+        hash_memory_page::<D, F>(&entries)
     }
 
-    pub fn get_res(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> F {
-        let pc_update = self.get_flag_group(FlagGroup::PcUpdate);
-        let res_logic = self.get_flag_group(FlagGroup::ResLogic);
-        match pc_update {
-            4 => {
-                let opcode = self.get_flag_group(FlagGroup::Opcode);
-                let ap_update = self.get_flag_group(FlagGroup::ApUpdate);
-                if res_logic == 0 && opcode == 0 && ap_update != 1 {
-                    // From the Cairo whitepaper "We use the term Unused to
-                    // describe a variable that will not be used later in the
-                    // flow. As such, we don’t need to assign it a concrete
-                    // value.". Note `res` is repurposed when calculating next_pc and
-                    // stores the value of `dst^(-1)` (see air.rs for more details).
-                    self.get_dst(ap, fp, mem).inverse().unwrap_or_else(F::zero)
-                } else {
-                    unreachable!()
-                }
-            }
-            0..=2 => {
-                let op0: F = mem[self.get_op0_addr(ap, fp)].unwrap().into_felt();
-                let op1: F = mem[self.get_op1_addr(pc, ap, fp, mem)].unwrap().into_felt();
-                match res_logic {
-                    0 => op1,
-                    1 => op0 + op1,
-                    2 => op0 * op1,
-                    _ => unreachable!(),
-                }
-            }
-            _ => unreachable!(),
-        }
+    /// Checks the public memory hashes to `expected`
+    pub fn verify_public_memory_hash<D: Digest<OutputSize = U32>>(
+        &self,
+        expected: &[u8; 32],
+    ) -> bool {
+        self.public_memory_hash::<D>() == *expected
     }
+}
 
-    pub fn get_tmp0(&self, ap: usize, fp: usize, mem: &Memory<F>) -> F {
-        if self.get_flag(Flag::PcJnz) {
-            self.get_dst(ap, fp, mem)
-        } else {
-            // TODO: change
-            F::zero()
-        }
+#[cfg(feature = "serde")]
+impl<F: PrimeField> AirPublicInput<F> {
+    /// Parses a public input from JSON in the format written by `cairo-run`
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
     }
 
-    pub fn get_tmp1(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> F {
-        self.get_tmp0(ap, fp, mem) * self.get_res(pc, ap, fp, mem)
+    /// Reads a public input from JSON in the format written by `cairo-run`
+    pub fn from_reader(r: impl Read) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(r)
     }
 
-    pub fn into_felt(self) -> F {
-        BigUint::from(self.0).into()
+    /// Reads a public input from a JSON file
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let file = File::open(path)?;
+        Ok(Self::from_reader(BufReader::new(file))?)
     }
 }
 
-/// Cairo flag group
-/// https://eprint.iacr.org/2021/1063.pdf section 9.4
-#[derive(Clone, Copy)]
-pub enum FlagGroup {
-    DstReg,
-    Op0Reg,
-    Op1Src,
-    ResLogic,
-    PcUpdate,
-    ApUpdate,
-    Opcode,
+/// Functionality shared by the instances of all builtins
+pub trait BuiltinInstance {
+    /// Index of the instance within its builtin's memory segment
+    fn index(&self) -> u32;
+
+    /// Instance with all inputs set to zero. Used to pad a builtin's instances
+    fn new_empty(index: u32) -> Self
+    where
+        Self: Sized;
+
+    /// Number of memory cells used by each instance
+    fn cells_per_instance() -> u32
+    where
+        Self: Sized;
+
+    /// Name of the builtin's memory segment as used by `cairo-run`
+    fn segment_name() -> &'static str
+    where
+        Self: Sized;
 }
 
-/// Cairo flag
-/// https://eprint.iacr.org/2021/1063.pdf section 9
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Signature {
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub r: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub w: U256,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EcdsaInstance {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(rename = "pubkey", with = "hex_str"))]
+    pub pubkey_x: U256,
+    #[cfg_attr(feature = "serde", serde(rename = "msg", with = "hex_str"))]
+    pub message: U256,
+    #[cfg_attr(feature = "serde", serde(rename = "signature_input"))]
+    pub signature: Signature,
+}
+
+impl EcdsaInstance {
+    /// Number of memory cells used by each instance
+    pub const CELLS_PER_INSTANCE: u32 = 2;
+
+    /// Get the memory addresses for this instance
+    pub fn mem_addr(&self, ecdsa_segment_addr: MemoryAddress) -> EcdsaAddrs {
+        let instance_offset = ecdsa_segment_addr + self.index * Self::CELLS_PER_INSTANCE;
+        EcdsaAddrs {
+            pubkey: instance_offset,
+            msg: instance_offset + 1,
+        }
+    }
+}
+
+impl BuiltinInstance for EcdsaInstance {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn new_empty(index: u32) -> Self {
+        Self {
+            index,
+            pubkey_x: U256::ZERO,
+            message: U256::ZERO,
+            signature: Signature {
+                r: U256::ZERO,
+                w: U256::ZERO,
+            },
+        }
+    }
+
+    fn cells_per_instance() -> u32 {
+        Self::CELLS_PER_INSTANCE
+    }
+
+    fn segment_name() -> &'static str {
+        "ecdsa"
+    }
+}
+
+/// Memory addresses of an [EcdsaInstance]
+#[derive(Debug, Clone, Copy)]
+pub struct EcdsaAddrs {
+    pub pubkey: MemoryAddress,
+    pub msg: MemoryAddress,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PedersenInstance {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(rename = "x", with = "hex_str"))]
+    pub a: U256,
+    #[cfg_attr(feature = "serde", serde(rename = "y", with = "hex_str"))]
+    pub b: U256,
+}
+
+impl PedersenInstance {
+    /// Number of memory cells used by each instance
+    pub const CELLS_PER_INSTANCE: u32 = 3;
+
+    pub fn new_empty(index: u32) -> Self {
+        Self {
+            index,
+            a: U256::ZERO,
+            b: U256::ZERO,
+        }
+    }
+
+    /// Get the memory addresses for this instance
+    pub fn mem_addr(&self, pedersen_segment_addr: MemoryAddress) -> PedersenAddrs {
+        let instance_offset = pedersen_segment_addr + self.index * Self::CELLS_PER_INSTANCE;
+        PedersenAddrs {
+            a: instance_offset,
+            b: instance_offset + 1,
+            output: instance_offset + 2,
+        }
+    }
+}
+
+impl BuiltinInstance for PedersenInstance {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn new_empty(index: u32) -> Self {
+        Self::new_empty(index)
+    }
+
+    fn cells_per_instance() -> u32 {
+        Self::CELLS_PER_INSTANCE
+    }
+
+    fn segment_name() -> &'static str {
+        "pedersen"
+    }
+}
+
+/// Memory addresses of a [PedersenInstance]
+#[derive(Debug, Clone, Copy)]
+pub struct PedersenAddrs {
+    pub a: MemoryAddress,
+    pub b: MemoryAddress,
+    pub output: MemoryAddress,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RangeCheckInstance {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub value: U256,
+}
+
+impl RangeCheckInstance {
+    /// Number of memory cells used by each instance
+    pub const CELLS_PER_INSTANCE: u32 = 1;
+
+    /// Number of 16-bit parts a value is split into by the range check builtin
+    pub const N_PARTS: usize = 8;
+
+    pub fn new_empty(index: u32) -> Self {
+        Self {
+            index,
+            value: U256::ZERO,
+        }
+    }
+
+    /// Get the memory address for this instance
+    pub fn mem_addr(&self, range_check_segment_addr: MemoryAddress) -> MemoryAddress {
+        range_check_segment_addr + self.index
+    }
+
+    /// Splits the value into 16-bit limbs, least significant limb first
+    pub fn split_into_limbs(&self) -> [u16; 16] {
+        let words = self.value.as_limbs();
+        array::from_fn(|i| (words[i / 4] >> (16 * (i % 4))) as u16)
+    }
+
+    /// Inverse of [Self::split_into_limbs]
+    pub fn from_limbs(index: u32, limbs: [u16; 16]) -> Self {
+        let value = limbs
+            .iter()
+            .rev()
+            .fold(U256::ZERO, |acc, &limb| (acc << 16) | U256::from(limb));
+        Self { index, value }
+    }
+
+    /// Checks the value fits in [Self::N_PARTS] limbs that all lie in
+    /// `[rc_min, rc_max]`
+    pub fn is_valid_for_range(&self, rc_min: u16, rc_max: u16) -> bool {
+        let limbs = self.split_into_limbs();
+        let (parts, high_limbs) = limbs.split_at(Self::N_PARTS);
+        high_limbs.iter().all(|&limb| limb == 0)
+            && parts.iter().all(|part| (rc_min..=rc_max).contains(part))
+    }
+}
+
+impl BuiltinInstance for RangeCheckInstance {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn new_empty(index: u32) -> Self {
+        Self::new_empty(index)
+    }
+
+    fn cells_per_instance() -> u32 {
+        Self::CELLS_PER_INSTANCE
+    }
+
+    fn segment_name() -> &'static str {
+        "range_check"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BitwiseInstance {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub x: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub y: U256,
+}
+
+impl BitwiseInstance {
+    /// Number of memory cells used by each instance
+    pub const CELLS_PER_INSTANCE: u32 = 5;
+
+    pub fn new_empty(index: u32) -> Self {
+        Self {
+            index,
+            x: U256::ZERO,
+            y: U256::ZERO,
+        }
+    }
+
+    /// Get the memory addresses for this instance
+    pub fn mem_addr(&self, bitwise_segment_addr: MemoryAddress) -> BitwiseAddrs {
+        let instance_offset = bitwise_segment_addr + self.index * Self::CELLS_PER_INSTANCE;
+        BitwiseAddrs {
+            x: instance_offset,
+            y: instance_offset + 1,
+            x_and_y: instance_offset + 2,
+            x_xor_y: instance_offset + 3,
+            x_or_y: instance_offset + 4,
+        }
+    }
+
+    /// Returns `(x & y, x ^ y, x | y)`
+    pub fn outputs(&self) -> (U256, U256, U256) {
+        let Self { x, y, .. } = *self;
+        (x & y, x ^ y, x | y)
+    }
+
+    /// Checks the cells of this instance in the bitwise memory segment hold
+    /// the instance's inputs and outputs
+    pub fn verify_in_memory<F: PrimeField>(&self, mem: &Memory<F>, segment: Segment) -> bool {
+        let addrs = self.mem_addr(segment.begin_addr);
+        if addrs.x_or_y >= segment.stop_ptr {
+            return false;
+        }
+        let (x_and_y, x_xor_y, x_or_y) = self.outputs();
+        [
+            (addrs.x, self.x),
+            (addrs.y, self.y),
+            (addrs.x_and_y, x_and_y),
+            (addrs.x_xor_y, x_xor_y),
+            (addrs.x_or_y, x_or_y),
+        ]
+        .into_iter()
+        .all(|(address, value)| mem.get_checked(address.into()).map(|word| word.0) == Some(value))
+    }
+}
+
+impl BuiltinInstance for BitwiseInstance {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn new_empty(index: u32) -> Self {
+        Self::new_empty(index)
+    }
+
+    fn cells_per_instance() -> u32 {
+        Self::CELLS_PER_INSTANCE
+    }
+
+    fn segment_name() -> &'static str {
+        "bitwise"
+    }
+}
+
+/// Memory addresses of a [BitwiseInstance]
+#[derive(Debug, Clone, Copy)]
+pub struct BitwiseAddrs {
+    pub x: MemoryAddress,
+    pub y: MemoryAddress,
+    pub x_and_y: MemoryAddress,
+    pub x_xor_y: MemoryAddress,
+    pub x_or_y: MemoryAddress,
+}
+
+/// Elliptic Curve operation instance for `p + m * q` on an elliptic curve
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EcOpInstance {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub p_x: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub p_y: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub q_x: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub q_y: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub m: U256,
+}
+
+impl EcOpInstance {
+    /// Number of memory cells used by each instance
+    pub const CELLS_PER_INSTANCE: u32 = 7;
+
+    /// Get the memory addresses for this instance
+    pub fn mem_addr(&self, ec_op_segment_addr: MemoryAddress) -> EcOpAddrs {
+        let instance_offset = ec_op_segment_addr + self.index * Self::CELLS_PER_INSTANCE;
+        EcOpAddrs {
+            p_x: instance_offset,
+            p_y: instance_offset + 1,
+            q_x: instance_offset + 2,
+            q_y: instance_offset + 3,
+            m: instance_offset + 4,
+            r_x: instance_offset + 5,
+            r_y: instance_offset + 6,
+        }
+    }
+}
+
+impl BuiltinInstance for EcOpInstance {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn new_empty(index: u32) -> Self {
+        Self {
+            index,
+            p_x: U256::ZERO,
+            p_y: U256::ZERO,
+            q_x: U256::ZERO,
+            q_y: U256::ZERO,
+            m: U256::ZERO,
+        }
+    }
+
+    fn cells_per_instance() -> u32 {
+        Self::CELLS_PER_INSTANCE
+    }
+
+    fn segment_name() -> &'static str {
+        "ec_op"
+    }
+}
+
+/// Memory addresses of an [EcOpInstance]
+#[derive(Debug, Clone, Copy)]
+pub struct EcOpAddrs {
+    pub p_x: MemoryAddress,
+    pub p_y: MemoryAddress,
+    pub q_x: MemoryAddress,
+    pub q_y: MemoryAddress,
+    pub m: MemoryAddress,
+    pub r_x: MemoryAddress,
+    pub r_y: MemoryAddress,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PoseidonInstance {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(rename = "input_s0", with = "hex_str"))]
+    pub input0: U256,
+    #[cfg_attr(feature = "serde", serde(rename = "input_s1", with = "hex_str"))]
+    pub input1: U256,
+    #[cfg_attr(feature = "serde", serde(rename = "input_s2", with = "hex_str"))]
+    pub input2: U256,
+}
+
+impl PoseidonInstance {
+    /// Number of memory cells used by each instance
+    pub const CELLS_PER_INSTANCE: u32 = 6;
+
+    pub fn new_empty(index: u32) -> Self {
+        Self {
+            index,
+            input0: U256::ZERO,
+            input1: U256::ZERO,
+            input2: U256::ZERO,
+        }
+    }
+
+    /// Get the memory addresses for this instance
+    pub fn mem_addr(&self, poseidon_segment_addr: MemoryAddress) -> PoseidonAddrs {
+        let instance_offset = poseidon_segment_addr + self.index * Self::CELLS_PER_INSTANCE;
+        PoseidonAddrs {
+            input0: instance_offset,
+            input1: instance_offset + 1,
+            input2: instance_offset + 2,
+            output0: instance_offset + 3,
+            output1: instance_offset + 4,
+            output2: instance_offset + 5,
+        }
+    }
+}
+
+impl BuiltinInstance for PoseidonInstance {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn new_empty(index: u32) -> Self {
+        Self::new_empty(index)
+    }
+
+    fn cells_per_instance() -> u32 {
+        Self::CELLS_PER_INSTANCE
+    }
+
+    fn segment_name() -> &'static str {
+        "poseidon"
+    }
+}
+
+/// Memory addresses of a [PoseidonInstance]
+#[derive(Debug, Clone, Copy)]
+pub struct PoseidonAddrs {
+    pub input0: MemoryAddress,
+    pub input1: MemoryAddress,
+    pub input2: MemoryAddress,
+    pub output0: MemoryAddress,
+    pub output1: MemoryAddress,
+    pub output2: MemoryAddress,
+}
+
+/// Instance of the keccak builtin. Applies the Keccak-f[1600] permutation to a
+/// state of 8 200-bit inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeccakInstance {
+    pub index: u32,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s0: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s1: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s2: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s3: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s4: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s5: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s6: U256,
+    #[cfg_attr(feature = "serde", serde(with = "hex_str"))]
+    pub input_s7: U256,
+}
+
+impl KeccakInstance {
+    /// Number of memory cells used by each instance
+    pub const CELLS_PER_INSTANCE: u32 = 2 * Self::N_INPUTS as u32;
+
+    /// Number of input cells. There are as many output cells.
+    pub const N_INPUTS: usize = 8;
+
+    /// Number of bits of the Keccak state held by each input and output cell
+    pub const BITS_PER_CELL: usize = 200;
+
+    pub fn new_empty(index: u32) -> Self {
+        Self::from_inputs(index, [U256::ZERO; Self::N_INPUTS])
+    }
+
+    pub fn from_inputs(index: u32, inputs: [U256; Self::N_INPUTS]) -> Self {
+        let [input_s0, input_s1, input_s2, input_s3, input_s4, input_s5, input_s6, input_s7] =
+            inputs;
+        Self {
+            index,
+            input_s0,
+            input_s1,
+            input_s2,
+            input_s3,
+            input_s4,
+            input_s5,
+            input_s6,
+            input_s7,
+        }
+    }
+
+    pub const fn inputs(&self) -> [U256; Self::N_INPUTS] {
+        [
+            self.input_s0,
+            self.input_s1,
+            self.input_s2,
+            self.input_s3,
+            self.input_s4,
+            self.input_s5,
+            self.input_s6,
+            self.input_s7,
+        ]
+    }
+
+    /// Get the memory addresses for this instance
+    pub fn mem_addr(&self, keccak_segment_addr: MemoryAddress) -> KeccakAddrs {
+        let instance_offset = keccak_segment_addr + self.index * Self::CELLS_PER_INSTANCE;
+        let n_inputs = Self::N_INPUTS as u32;
+        KeccakAddrs {
+            input: array::from_fn(|i| instance_offset + i as u32),
+            output: array::from_fn(|i| instance_offset + n_inputs + i as u32),
+        }
+    }
+
+    /// Returns the state after the Keccak-f[1600] permutation split the same
+    /// way as the inputs. The inputs are concatenated in little-endian order to
+    /// form the 1600-bit state. Bits of an input above [Self::BITS_PER_CELL]
+    /// are ignored.
+    pub fn output(&self) -> [U256; Self::N_INPUTS] {
+        const CELL_BYTES: usize = KeccakInstance::BITS_PER_CELL / 8;
+        let mut state_bytes = [0u8; 200];
+        for (cell, input) in zip(state_bytes.chunks_exact_mut(CELL_BYTES), self.inputs()) {
+            cell.copy_from_slice(&input.to_le_bytes::<32>()[..CELL_BYTES]);
+        }
+        let mut lanes = [0u64; 25];
+        for (lane, lane_bytes) in zip(&mut lanes, state_bytes.chunks_exact(8)) {
+            *lane = u64::from_le_bytes(lane_bytes.try_into().unwrap());
+        }
+        keccak::f1600(&mut lanes);
+        for (lane_bytes, lane) in zip(state_bytes.chunks_exact_mut(8), lanes) {
+            lane_bytes.copy_from_slice(&lane.to_le_bytes());
+        }
+        array::from_fn(|i| U256::from_le_slice(&state_bytes[i * CELL_BYTES..(i + 1) * CELL_BYTES]))
+    }
+}
+
+impl BuiltinInstance for KeccakInstance {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn new_empty(index: u32) -> Self {
+        Self::new_empty(index)
+    }
+
+    fn cells_per_instance() -> u32 {
+        Self::CELLS_PER_INSTANCE
+    }
+
+    fn segment_name() -> &'static str {
+        "keccak"
+    }
+}
+
+/// Memory addresses of a [KeccakInstance]
+#[derive(Debug, Clone, Copy)]
+pub struct KeccakAddrs {
+    pub input: [MemoryAddress; KeccakInstance::N_INPUTS],
+    pub output: [MemoryAddress; KeccakInstance::N_INPUTS],
+}
+
+/// Number of instances of each builtin
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuiltinCounter {
+    pub pedersen: usize,
+    pub ecdsa: usize,
+    pub range_check: usize,
+    pub bitwise: usize,
+    pub ec_op: usize,
+    pub poseidon: usize,
+    pub keccak: usize,
+}
+
+impl BuiltinCounter {
+    /// Total number of instances over all builtins
+    pub const fn total(&self) -> usize {
+        self.pedersen
+            + self.ecdsa
+            + self.range_check
+            + self.bitwise
+            + self.ec_op
+            + self.poseidon
+            + self.keccak
+    }
+
+    /// Number of instances of `builtin`. Zero for non-builtin segments.
+    pub const fn count(&self, builtin: SegmentKind) -> usize {
+        match builtin {
+            SegmentKind::Pedersen => self.pedersen,
+            SegmentKind::RangeCheck => self.range_check,
+            SegmentKind::Ecdsa => self.ecdsa,
+            SegmentKind::Bitwise => self.bitwise,
+            SegmentKind::EcOp => self.ec_op,
+            SegmentKind::Poseidon => self.poseidon,
+            SegmentKind::Keccak => self.keccak,
+            SegmentKind::Program | SegmentKind::Execution | SegmentKind::Output => 0,
+        }
+    }
+}
+
+impl Display for BuiltinCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            pedersen,
+            ecdsa,
+            range_check,
+            bitwise,
+            ec_op,
+            poseidon,
+            keccak,
+        } = self;
+        write!(
+            f,
+            "{} builtin instances (pedersen={pedersen} range_check={range_check} ecdsa={ecdsa} \
+             bitwise={bitwise} ec_op={ec_op} poseidon={poseidon} keccak={keccak})",
+            self.total()
+        )
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AirPrivateInput {
+    pub trace_path: PathBuf,
+    pub memory_path: PathBuf,
+    pub pedersen: Vec<PedersenInstance>,
+    pub range_check: Vec<RangeCheckInstance>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ecdsa: Vec<EcdsaInstance>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bitwise: Vec<BitwiseInstance>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ec_op: Vec<EcOpInstance>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub poseidon: Vec<PoseidonInstance>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub keccak: Vec<KeccakInstance>,
+}
+
+impl AirPrivateInput {
+    /// Number of instances of each builtin
+    pub fn builtin_counts(&self) -> BuiltinCounter {
+        BuiltinCounter {
+            pedersen: self.pedersen.len(),
+            ecdsa: self.ecdsa.len(),
+            range_check: self.range_check.len(),
+            bitwise: self.bitwise.len(),
+            ec_op: self.ec_op.len(),
+            poseidon: self.poseidon.len(),
+            keccak: self.keccak.len(),
+        }
+    }
+
+    /// Checks the private input and the register states of its trace are
+    /// consistent with the public input. Catches mismatched input files before
+    /// spending time on generating a proof.
+    ///
+    /// NOTE: ECDSA signatures aren't checked here since verifying them needs
+    /// the curve arithmetic from the builtins crate. See
+    /// `builtins::ecdsa::validate_signatures`.
+    pub fn validate_against_public<F: Field>(
+        &self,
+        public: &AirPublicInput<F>,
+        register_states: &RegisterStates,
+    ) -> Result<(), ValidationError> {
+        self.check_builtin_continuity()?;
+        public.validate_segment_adjacency()?;
+        self.validate_builtins_against_public(public)?;
+        if register_states.len() as u64 != public.n_steps {
+            return Err(ValidationError::StepCountMismatch {
+                expected: public.n_steps,
+                actual: register_states.len() as u64,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the instances of each builtin are indexed `0, 1, ..., n-1`.
+    /// Returns the first gap or duplicate found.
+    pub fn check_builtin_continuity(&self) -> Result<(), ContinuityError> {
+        fn check<T: BuiltinInstance>(
+            builtin: SegmentKind,
+            instances: &[T],
+        ) -> Result<(), ContinuityError> {
+            for (expected_index, instance) in (0..).zip(instances) {
+                let actual_index = instance.index();
+                if actual_index != expected_index {
+                    return Err(ContinuityError {
+                        builtin,
+                        expected_index,
+                        actual_index,
+                    });
+                }
+            }
+            Ok(())
+        }
+
+        check(SegmentKind::Pedersen, &self.pedersen)?;
+        check(SegmentKind::RangeCheck, &self.range_check)?;
+        check(SegmentKind::Ecdsa, &self.ecdsa)?;
+        check(SegmentKind::Bitwise, &self.bitwise)?;
+        check(SegmentKind::EcOp, &self.ec_op)?;
+        check(SegmentKind::Poseidon, &self.poseidon)?;
+        check(SegmentKind::Keccak, &self.keccak)
+    }
+
+    /// Appends empty builtin instances so each builtin has `n_steps / ratio`
+    /// instances in a trace of `2^target_log_n_steps` steps of `layout`. Like
+    /// `cairo-run`, unused instances are filled with instances whose inputs
+    /// are zero (see [BuiltinInstance::new_empty]).
+    ///
+    /// Errors if a builtin has more instances than fit in the trace or if the
+    /// number of instances doesn't fit in a `u32` instance index. The instances
+    /// are left unchanged on error.
+    pub fn pad_to_power_of_two(
+        &mut self,
+        layout: Layout,
+        target_log_n_steps: u32,
+    ) -> Result<(), PaddingError> {
+        let n_steps = 1u64
+            .checked_shl(target_log_n_steps)
+            .ok_or(PaddingError::TooManySteps {
+                log_n_steps: target_log_n_steps,
+            })?;
+        let counts = self.builtin_counts();
+        for &(builtin, ratio) in layout.builtin_ratios() {
+            let instances = counts.count(builtin) as u64;
+            let max_instances = n_steps / u64::from(ratio);
+            if max_instances > u64::from(u32::MAX) {
+                return Err(PaddingError::TooManySteps {
+                    log_n_steps: target_log_n_steps,
+                });
+            }
+            if instances > max_instances {
+                return Err(PaddingError::TooManyInstances {
+                    builtin,
+                    instances,
+                    max_instances,
+                });
+            }
+        }
+
+        fn pad<T: BuiltinInstance>(instances: &mut Vec<T>, n_instances: u64) {
+            let padding = instances.len() as u32..n_instances as u32;
+            instances.extend(padding.map(T::new_empty));
+        }
+
+        for &(builtin, ratio) in layout.builtin_ratios() {
+            let n_instances = n_steps / u64::from(ratio);
+            match builtin {
+                SegmentKind::Pedersen => pad(&mut self.pedersen, n_instances),
+                SegmentKind::RangeCheck => pad(&mut self.range_check, n_instances),
+                SegmentKind::Ecdsa => pad(&mut self.ecdsa, n_instances),
+                SegmentKind::Bitwise => pad(&mut self.bitwise, n_instances),
+                SegmentKind::EcOp => pad(&mut self.ec_op, n_instances),
+                SegmentKind::Keccak => pad(&mut self.keccak, n_instances),
+                SegmentKind::Poseidon => pad(&mut self.poseidon, n_instances),
+                SegmentKind::Program | SegmentKind::Execution | SegmentKind::Output => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as [Self::validate_against_public] without checking the register
+    /// states
+    pub fn validate_builtins_against_public<F: Field>(
+        &self,
+        public: &AirPublicInput<F>,
+    ) -> Result<(), ValidationError> {
+        let segments = &public.memory_segments;
+        for (segment, segment_addrs, cells_per_instance, instances) in [
+            (
+                SegmentKind::Pedersen,
+                segments.pedersen,
+                PedersenInstance::CELLS_PER_INSTANCE,
+                self.pedersen.len(),
+            ),
+            (
+                SegmentKind::RangeCheck,
+                segments.range_check,
+                RangeCheckInstance::CELLS_PER_INSTANCE,
+                self.range_check.len(),
+            ),
+            (
+                SegmentKind::Ecdsa,
+                segments.ecdsa,
+                EcdsaInstance::CELLS_PER_INSTANCE,
+                self.ecdsa.len(),
+            ),
+            (
+                SegmentKind::Bitwise,
+                segments.bitwise,
+                BitwiseInstance::CELLS_PER_INSTANCE,
+                self.bitwise.len(),
+            ),
+            (
+                SegmentKind::EcOp,
+                segments.ec_op,
+                EcOpInstance::CELLS_PER_INSTANCE,
+                self.ec_op.len(),
+            ),
+            (
+                SegmentKind::Poseidon,
+                segments.poseidon,
+                PoseidonInstance::CELLS_PER_INSTANCE,
+                self.poseidon.len(),
+            ),
+            (
+                SegmentKind::Keccak,
+                segments.keccak,
+                KeccakInstance::CELLS_PER_INSTANCE,
+                self.keccak.len(),
+            ),
+        ] {
+            let segment_size = segment_addrs.map_or(0, |s| s.len());
+            // the last instance can be partially filled
+            let instances = instances as u32;
+            if instances != segment_size.div_ceil(cells_per_instance) {
+                return Err(ValidationError::InstanceCountMismatch {
+                    segment,
+                    segment_size,
+                    cells_per_instance,
+                    instances,
+                });
+            }
+        }
+
+        public.validate_range_checks(self)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl AirPrivateInput {
+    /// Parses a private input from JSON in the format written by `cairo-run`
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// Reads a private input from JSON in the format written by `cairo-run`
+    pub fn from_reader(r: impl Read) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(r)
+    }
+
+    /// Reads a private input from a JSON file
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let file = File::open(path)?;
+        Ok(Self::from_reader(BufReader::new(file))?)
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "F: PrimeField"))]
+pub struct CompiledProgram<F: Field> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_vec_hex_str"))]
+    pub data: Vec<F>,
+    pub prime: String,
+}
+
+impl<F: Field> CompiledProgram<F> {
+    /// Number of memory cells used by the program segment
+    pub fn program_segment_length(&self) -> u32 {
+        self.data.len() as u32
+    }
+}
+
+impl<F: PrimeField> CompiledProgram<F> {
+    /// Checks the program's prime matches the modulus of `F`
+    pub fn try_validate(&self) -> Result<(), PrimeMismatch> {
+        let modulus: BigUint = F::MODULUS.into();
+        let expected = format!("{modulus:#x}");
+        if self.prime.to_lowercase() != expected {
+            return Err(PrimeMismatch {
+                expected,
+                actual: self.prime.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Memory address of the first instruction.
+    /// Address 0 is reserved for dummy accesses (it's null pointer).
+    pub const fn entry_point_pc() -> usize {
+        1
+    }
+
+    /// Decodes the instruction at the program's entry point
+    pub fn first_instruction(&self) -> Result<Word<F>, InvalidInstruction> {
+        self.instruction_at(Self::entry_point_pc())
+    }
+
+    /// Returns true if the program starts by calling `main` with a relative
+    /// call. Programs compiled with `cairo-compile --proof_mode` allocate
+    /// space for `main`'s arguments with `ap += imm` before the call so this
+    /// instruction is skipped if present.
+    pub fn is_call_to_main(&self) -> bool {
+        let mut pc = Self::entry_point_pc();
+        let Ok(mut instruction) = self.instruction_at(pc) else {
+            return false;
+        };
+
+        let is_ap_add = instruction.get_flag_group(FlagGroup::Opcode) == 0
+            && instruction.get_flag_group(FlagGroup::ApUpdate) == 1
+            && instruction.get_flag_group(FlagGroup::PcUpdate) == 0;
+        if is_ap_add {
+            pc += instruction.size();
+            let Ok(next_instruction) = self.instruction_at(pc) else {
+                return false;
+            };
+            instruction = next_instruction;
+        }
+
+        let is_call_rel = instruction.get_flag(Flag::OpcodeCall)
+            && instruction.get_flag(Flag::PcJumpRel)
+            && instruction.get_flag(Flag::Op1Imm);
+        if !is_call_rel {
+            return false;
+        }
+
+        // the call must land inside the program
+        let Some(offset) = self.data.get(pc) else {
+            return false;
+        };
+        let target: BigUint = (F::from(pc as u64) + offset).into();
+        let first_pc = BigUint::from(Self::entry_point_pc());
+        let end_pc = &first_pc + self.data.len();
+        first_pc <= target && target < end_pc
+    }
+
+    /// Checks the program is loaded into memory starting at `begin_addr`
+    pub fn validate_program_segment(
+        &self,
+        memory: &Memory<F>,
+        begin_addr: MemoryAddress,
+    ) -> Result<(), SegmentValidationError> {
+        for (address, &expected) in (begin_addr.0..).map(MemoryAddress).zip(&self.data) {
+            let word = memory
+                .get(usize::from(address))
+                .copied()
+                .flatten()
+                .ok_or(SegmentValidationError::Missing { address })?;
+            if word.into_felt() != expected {
+                return Err(SegmentValidationError::Mismatch {
+                    address,
+                    expected: U256::try_from(Into::<BigUint>::into(expected)).unwrap(),
+                    actual: word.0,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn instruction_at(&self, pc: usize) -> Result<Word<F>, InvalidInstruction> {
+        let value = pc
+            .checked_sub(Self::entry_point_pc())
+            .and_then(|i| self.data.get(i))
+            .ok_or(InvalidInstruction::Missing { address: pc })?;
+        let instruction = Word::from_felt(*value);
+        instruction.validate()?;
+        Ok(instruction)
+    }
+}
+
+/// A [CompiledProgram] whose prime has been checked against the field `F`
+#[derive(Clone, Debug)]
+pub struct ValidatedProgram<F: Field>(CompiledProgram<F>);
+
+impl<F: PrimeField> ValidatedProgram<F> {
+    pub fn new(program: CompiledProgram<F>) -> Result<Self, PrimeMismatch> {
+        program.try_validate()?;
+        Ok(Self(program))
+    }
+
+    pub fn into_inner(self) -> CompiledProgram<F> {
+        self.0
+    }
+
+    pub fn program_memory(&self) -> Vec<MemoryEntry<F>> {
+        self.0
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                // address 0 is reserved for dummy accesses (it's null pointer)
+                MemoryEntry {
+                    address: MemoryAddress(i as u32 + 1),
+                    value,
+                }
+            })
+            .collect()
+    }
+}
+
+impl<F: Field> Deref for ValidatedProgram<F> {
+    type Target = CompiledProgram<F>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Represents a Cairo word
+/// Value is a field element in the range `[0, Fp::MODULUS)`
+/// Stored as a U256 to make binary decompositions more efficient
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Word<F>(pub U256, PhantomData<F>);
+
+impl<F> Word<F> {
+    /// Calculates $\tilde{f_i}$ - https://eprint.iacr.org/2021/1063.pdf
+    pub fn get_flag_prefix(&self, flag: Flag) -> u16 {
+        if flag == Flag::Zero {
+            return 0;
+        }
+
+        let flag = flag as usize;
+        let prefix = self.0 >> (FLAGS_BIT_OFFSET + flag);
+        let mask = (uint!(1_U256) << (15 - flag)) - uint!(1_U256);
+        (prefix & mask).try_into().unwrap()
+    }
+
+    pub fn get_op0_addr(&self, ap: usize, fp: usize) -> usize {
+        // TODO: put the if statement first good for rust quiz
+        self.get_off_op0() as usize + if self.get_flag(Flag::Op0Reg) { fp } else { ap }
+            - HALF_OFFSET
+    }
+
+    pub fn get_dst_addr(&self, ap: usize, fp: usize) -> usize {
+        self.get_off_dst() as usize + if self.get_flag(Flag::DstReg) { fp } else { ap }
+            - HALF_OFFSET
+    }
+
+    /// Number of memory cells used by the instruction.
+    /// Instructions with an immediate value take up two cells.
+    pub fn size(&self) -> usize {
+        if self.get_flag(Flag::Op1Imm) {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn get_flag(&self, flag: Flag) -> bool {
+        self.0.bit(FLAGS_BIT_OFFSET + flag as usize)
+    }
+
+    pub fn get_off_dst(&self) -> u16 {
+        let prefix = self.0 >> OFF_DST_BIT_OFFSET;
+        let mask = U256::from(OFF_MASK);
+        (prefix & mask).try_into().unwrap()
+    }
+
+    pub fn get_off_op0(&self) -> u16 {
+        let prefix = self.0 >> OFF_OP0_BIT_OFFSET;
+        let mask = U256::from(OFF_MASK);
+        (prefix & mask).try_into().unwrap()
+    }
+
+    pub fn get_off_op1(&self) -> u16 {
+        let prefix = self.0 >> OFF_OP1_BIT_OFFSET;
+        let mask = U256::from(OFF_MASK);
+        (prefix & mask).try_into().unwrap()
+    }
+
+    pub fn get_flag_group(&self, flag_group: FlagGroup) -> u8 {
+        match flag_group {
+            FlagGroup::DstReg => self.get_flag(Flag::DstReg) as u8,
+            FlagGroup::Op0Reg => self.get_flag(Flag::Op0Reg) as u8,
+            FlagGroup::Op1Src => {
+                self.get_flag(Flag::Op1Imm) as u8
+                    + self.get_flag(Flag::Op1Fp) as u8 * 2
+                    + self.get_flag(Flag::Op1Ap) as u8 * 4
+            }
+            FlagGroup::ResLogic => {
+                self.get_flag(Flag::ResAdd) as u8 + self.get_flag(Flag::ResMul) as u8 * 2
+            }
+            FlagGroup::PcUpdate => {
+                self.get_flag(Flag::PcJumpAbs) as u8
+                    + self.get_flag(Flag::PcJumpRel) as u8 * 2
+                    + self.get_flag(Flag::PcJnz) as u8 * 4
+            }
+            FlagGroup::ApUpdate => {
+                self.get_flag(Flag::ApAdd) as u8 + self.get_flag(Flag::ApAdd1) as u8 * 2
+            }
+            FlagGroup::Opcode => {
+                self.get_flag(Flag::OpcodeCall) as u8
+                    + self.get_flag(Flag::OpcodeRet) as u8 * 2
+                    + self.get_flag(Flag::OpcodeAssertEq) as u8 * 4
+            }
+        }
+    }
+
+    /// Checks the word is a valid Cairo instruction. Note the AIR relies on
+    /// these constraints (see section 9 of the Cairo whitepaper)
+    pub fn validate(&self) -> Result<(), InvalidInstruction> {
+        // the 16th flag (the zero flag) must be 0
+        if self.0.bit_len() > 63 {
+            return Err(InvalidInstruction::TooLarge { value: self.0 });
+        }
+
+        for (flag_group, valid_values) in [
+            (FlagGroup::Op1Src, &[0, 1, 2, 4][..]),
+            (FlagGroup::ResLogic, &[0, 1, 2]),
+            (FlagGroup::PcUpdate, &[0, 1, 2, 4]),
+            (FlagGroup::ApUpdate, &[0, 1, 2]),
+            (FlagGroup::Opcode, &[0, 1, 2, 4]),
+        ] {
+            let value = self.get_flag_group(flag_group);
+            if !valid_values.contains(&value) {
+                return Err(InvalidInstruction::InvalidFlagGroup { flag_group, value });
+            }
+        }
+
+        let DecodedInstruction {
+            opcode,
+            pc_update,
+            ap_update,
+            res_logic,
+            op1_src,
+            off_op1,
+            ..
+        } = self.decode();
+        if opcode == Opcode::Call && ap_update != ApUpdateKind::Regular {
+            return Err(InvalidInstruction::CallWithApUpdate { ap_update });
+        }
+        if pc_update == PcUpdateKind::Jnz
+            && (res_logic != ResLogic::Op1
+                || opcode != Opcode::Nop
+                || ap_update == ApUpdateKind::Add)
+        {
+            return Err(InvalidInstruction::InvalidJnz);
+        }
+        if op1_src == Op1Src::Pc && off_op1 != 1 {
+            return Err(InvalidInstruction::InvalidImmediateOffset { off_op1 });
+        }
+        Ok(())
+    }
+
+    /// Returns true if the word is a valid Cairo instruction. See
+    /// [Word::validate].
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Decodes all flag groups and offsets of the instruction
+    ///
+    /// # Panics
+    /// Panics if a flag group has an invalid combination of flags set
+    pub fn decode(&self) -> DecodedInstruction {
+        let flag_group = |flag_group| self.get_flag_group(flag_group);
+        DecodedInstruction {
+            opcode: Opcode::from_flag_group(flag_group(FlagGroup::Opcode)).expect("invalid opcode"),
+            pc_update: PcUpdateKind::from_flag_group(flag_group(FlagGroup::PcUpdate))
+                .expect("invalid pc update"),
+            ap_update: ApUpdateKind::from_flag_group(flag_group(FlagGroup::ApUpdate))
+                .expect("invalid ap update"),
+            res_logic: ResLogic::from_flag_group(flag_group(FlagGroup::ResLogic))
+                .expect("invalid res logic"),
+            dst_reg: DstReg::from_flag_group(flag_group(FlagGroup::DstReg)).unwrap(),
+            op0_reg: Op0Reg::from_flag_group(flag_group(FlagGroup::Op0Reg)).unwrap(),
+            op1_src: Op1Src::from_flag_group(flag_group(FlagGroup::Op1Src))
+                .expect("invalid op1 source"),
+            off_dst: decode_offset(self.get_off_dst()),
+            off_op0: decode_offset(self.get_off_op0()),
+            off_op1: decode_offset(self.get_off_op1()),
+        }
+    }
+}
+
+impl<F: PrimeField> Word<F> {
+    pub fn new(word: U256) -> Self {
+        let modulus: BigUint = F::MODULUS.into();
+        debug_assert!(BigUint::from(word) < modulus);
+        Word(word, PhantomData)
+    }
+
+    pub fn get_op0(&self, ap: usize, fp: usize, mem: &Memory<F>) -> F {
+        mem[self.get_op0_addr(ap, fp)].unwrap().into_felt()
+    }
+
+    pub fn get_dst(&self, ap: usize, fp: usize, mem: &Memory<F>) -> F {
+        mem[self.get_dst_addr(ap, fp)].unwrap().into_felt()
+    }
+
+    pub fn get_op1_addr(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> usize {
+        self.get_off_op1() as usize
+            + match self.get_flag_group(FlagGroup::Op1Src) {
+                0 => usize::try_from(mem[self.get_op0_addr(ap, fp)].unwrap().0).unwrap(),
+                1 => pc,
+                2 => fp,
+                4 => ap,
+                _ => unreachable!(),
+            }
+            - HALF_OFFSET
+    }
+
+    pub fn get_op1(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> F {
+        mem[self.get_op1_addr(pc, ap, fp, mem)].unwrap().into_felt()
+    }
+
+    pub fn get_res(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> F {
+        let pc_update = self.get_flag_group(FlagGroup::PcUpdate);
+        let res_logic = self.get_flag_group(FlagGroup::ResLogic);
+        match pc_update {
+            4 => {
+                let opcode = self.get_flag_group(FlagGroup::Opcode);
+                let ap_update = self.get_flag_group(FlagGroup::ApUpdate);
+                if res_logic == 0 && opcode == 0 && ap_update != 1 {
+                    // From the Cairo whitepaper "We use the term Unused to
+                    // describe a variable that will not be used later in the
+                    // flow. As such, we don’t need to assign it a concrete
+                    // value.". Note `res` is repurposed when calculating next_pc and
+                    // stores the value of `dst^(-1)` (see air.rs for more details).
+                    self.get_dst(ap, fp, mem).inverse().unwrap_or_else(F::zero)
+                } else {
+                    unreachable!()
+                }
+            }
+            0..=2 => {
+                let op0: F = mem[self.get_op0_addr(ap, fp)].unwrap().into_felt();
+                let op1: F = mem[self.get_op1_addr(pc, ap, fp, mem)].unwrap().into_felt();
+                match res_logic {
+                    0 => op1,
+                    1 => op0 + op1,
+                    2 => op0 * op1,
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn get_tmp0(&self, ap: usize, fp: usize, mem: &Memory<F>) -> F {
+        if self.get_flag(Flag::PcJnz) {
+            self.get_dst(ap, fp, mem)
+        } else {
+            // TODO: change
+            F::zero()
+        }
+    }
+
+    pub fn get_tmp1(&self, pc: usize, ap: usize, fp: usize, mem: &Memory<F>) -> F {
+        self.get_tmp0(ap, fp, mem) * self.get_res(pc, ap, fp, mem)
+    }
+
+    pub fn into_felt(self) -> F {
+        BigUint::from(self.0).into()
+    }
+
+    /// Inverse of [Word::into_felt]
+    pub fn from_felt(felt: F) -> Self {
+        let value: BigUint = felt.into();
+        Self::new(U256::from(value))
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self::new(U256::from(value))
+    }
+
+    /// Computes the `pc` after executing this instruction at `state`
+    pub fn next_pc(&self, state: RegisterState, mem: &Memory<F>) -> Result<usize, SimError> {
+        self.validate()?;
+        let pc = F::from(state.pc as u64);
+        let size = F::from(self.size() as u64);
+        let next_pc = match self.decode().pc_update {
+            PcUpdateKind::Regular => pc + size,
+            PcUpdateKind::JumpAbs => self.sim_res(state, mem)?,
+            PcUpdateKind::JumpRel => pc + self.sim_res(state, mem)?,
+            PcUpdateKind::Jnz => {
+                if self.sim_dst(state, mem)?.is_zero() {
+                    pc + size
+                } else {
+                    pc + self.sim_op1(state, mem)?
+                }
+            }
+        };
+        Self::felt_to_address(next_pc)
+    }
+
+    /// Computes the `ap` after executing this instruction at `state`
+    pub fn next_ap(&self, state: RegisterState, mem: &Memory<F>) -> Result<usize, SimError> {
+        self.validate()?;
+        let ap = F::from(state.ap as u64);
+        let instruction = self.decode();
+        let next_ap = match (instruction.opcode, instruction.ap_update) {
+            // calls push the return `fp` and `pc` (validation ensures a
+            // regular `ap_update`)
+            (Opcode::Call, _) => ap + F::from(2u8),
+            (_, ApUpdateKind::Regular) => ap,
+            (_, ApUpdateKind::Add) => ap + self.sim_res(state, mem)?,
+            (_, ApUpdateKind::Add1) => ap + F::one(),
+        };
+        Self::felt_to_address(next_ap)
+    }
+
+    /// Computes the `fp` after executing this instruction at `state`
+    pub fn next_fp(&self, state: RegisterState, mem: &Memory<F>) -> Result<usize, SimError> {
+        self.validate()?;
+        match self.decode().opcode {
+            Opcode::Call => Ok(state.ap + 2),
+            Opcode::Ret => Self::felt_to_address(self.sim_dst(state, mem)?),
+            Opcode::Nop | Opcode::AssertEq => Ok(state.fp),
+        }
+    }
+
+    fn sim_dst(&self, state: RegisterState, mem: &Memory<F>) -> Result<F, SimError> {
+        let base = if self.get_flag(Flag::DstReg) {
+            state.fp
+        } else {
+            state.ap
+        };
+        Self::read(mem, Self::operand_addr(base, self.get_off_dst())?)
+    }
+
+    fn sim_op0(&self, state: RegisterState, mem: &Memory<F>) -> Result<F, SimError> {
+        let base = if self.get_flag(Flag::Op0Reg) {
+            state.fp
+        } else {
+            state.ap
+        };
+        Self::read(mem, Self::operand_addr(base, self.get_off_op0())?)
+    }
+
+    fn sim_op1(&self, state: RegisterState, mem: &Memory<F>) -> Result<F, SimError> {
+        let base = match self.decode().op1_src {
+            Op1Src::Op0 => Self::felt_to_address(self.sim_op0(state, mem)?)?,
+            Op1Src::Pc => state.pc,
+            Op1Src::Fp => state.fp,
+            Op1Src::Ap => state.ap,
+        };
+        Self::read(mem, Self::operand_addr(base, self.get_off_op1())?)
+    }
+
+    fn sim_res(&self, state: RegisterState, mem: &Memory<F>) -> Result<F, SimError> {
+        let op1 = self.sim_op1(state, mem)?;
+        Ok(match self.decode().res_logic {
+            ResLogic::Op1 => op1,
+            ResLogic::Add => self.sim_op0(state, mem)? + op1,
+            ResLogic::Mul => self.sim_op0(state, mem)? * op1,
+        })
+    }
+
+    /// Address of `base + off` where `biased_offset = off + 2^15`
+    fn operand_addr(base: usize, biased_offset: u16) -> Result<usize, SimError> {
+        let address = F::from(base as u64) + F::from(biased_offset) - F::from(HALF_OFFSET as u64);
+        Self::felt_to_address(address)
+    }
+
+    fn felt_to_address(felt: F) -> Result<usize, SimError> {
+        let value = Self::from_felt(felt).0;
+        usize::try_from(value).map_err(|_| SimError::InvalidAddress { value })
+    }
+
+    fn read(mem: &Memory<F>, address: usize) -> Result<F, SimError> {
+        mem.get(address)
+            .copied()
+            .flatten()
+            .map(Word::into_felt)
+            .ok_or(SimError::MissingValue { address })
+    }
+
+    /// Assembles an instruction from its flag groups and offsets. Useful for
+    /// building synthetic instructions e.g. in tests.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_instruction(
+        opcode: Opcode,
+        pc_update: PcUpdateKind,
+        ap_update: ApUpdateKind,
+        res_logic: ResLogic,
+        dst_reg: DstReg,
+        op0_reg: Op0Reg,
+        op1_src: Op1Src,
+        off_dst: i16,
+        off_op0: i16,
+        off_op1: i16,
+    ) -> Self {
+        Self::encode(DecodedInstruction {
+            opcode,
+            pc_update,
+            ap_update,
+            res_logic,
+            dst_reg,
+            op0_reg,
+            op1_src,
+            off_dst,
+            off_op0,
+            off_op1,
+        })
+    }
+
+    /// Inverse of [Word::decode]
+    pub fn encode(instruction: DecodedInstruction) -> Self {
+        let flags = (instruction.dst_reg as u64) << Flag::DstReg as u64
+            | (instruction.op0_reg as u64) << Flag::Op0Reg as u64
+            | (instruction.op1_src as u64) << Flag::Op1Imm as u64
+            | (instruction.res_logic as u64) << Flag::ResAdd as u64
+            | (instruction.pc_update as u64) << Flag::PcJumpAbs as u64
+            | (instruction.ap_update as u64) << Flag::ApAdd as u64
+            | (instruction.opcode as u64) << Flag::OpcodeCall as u64;
+        let offsets = u64::from(encode_offset(instruction.off_dst)) << OFF_DST_BIT_OFFSET
+            | u64::from(encode_offset(instruction.off_op0)) << OFF_OP0_BIT_OFFSET
+            | u64::from(encode_offset(instruction.off_op1)) << OFF_OP1_BIT_OFFSET;
+        Self::from_u64(flags << FLAGS_BIT_OFFSET | offsets)
+    }
+}
+
+/// Executes the instruction at `state.pc` and returns the next register
+/// state. Memory must hold all values the instruction reads.
+pub fn step<F: PrimeField>(
+    state: RegisterState,
+    mem: &Memory<F>,
+) -> Result<RegisterState, SimError> {
+    let instruction = mem
+        .get(state.pc)
+        .copied()
+        .flatten()
+        .ok_or(SimError::MissingValue { address: state.pc })?;
+    Ok(RegisterState {
+        pc: instruction.next_pc(state, mem)?,
+        ap: instruction.next_ap(state, mem)?,
+        fp: instruction.next_fp(state, mem)?,
+    })
+}
+
+/// Cairo flag group
+/// https://eprint.iacr.org/2021/1063.pdf section 9.4
+#[derive(Clone, Copy, Debug)]
+pub enum FlagGroup {
+    DstReg,
+    Op0Reg,
+    Op1Src,
+    ResLogic,
+    PcUpdate,
+    ApUpdate,
+    Opcode,
+}
+
+/// Cairo flag
+/// https://eprint.iacr.org/2021/1063.pdf section 9
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(u16)]
 pub enum Flag {
     // Group: [FlagGroup::DstReg]
     DstReg = 0,
 
-    // Group: [FlagGroup::Op0]
-    Op0Reg = 1,
+    // Group: [FlagGroup::Op0]
+    Op0Reg = 1,
+
+    // Group: [FlagGroup::Op1Src]
+    Op1Imm = 2,
+    Op1Fp = 3,
+    Op1Ap = 4,
+
+    // Group: [FlagGroup::ResLogic]
+    ResAdd = 5,
+    ResMul = 6,
+
+    // Group: [FlagGroup::PcUpdate]
+    PcJumpAbs = 7,
+    PcJumpRel = 8,
+    PcJnz = 9,
+
+    // Group: [FlagGroup::ApUpdate]
+    ApAdd = 10,
+    ApAdd1 = 11,
+
+    // Group: [FlagGroup::Opcode]
+    OpcodeCall = 12,
+    OpcodeRet = 13,
+    OpcodeAssertEq = 14,
+
+    // 0 - padding to make flag cells a power-of-2
+    Zero = 15,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::errors::AddressError;
+    use crate::errors::ContinuityError;
+    use crate::errors::DecodeError;
+    use crate::errors::InconsistencyError;
+    use crate::errors::InvalidFieldElementError;
+    use crate::errors::InvalidInstruction;
+    use crate::errors::InvalidMemoryEntry;
+    use crate::errors::LoadError;
+    use crate::errors::MissingBuiltin;
+    use crate::errors::PaddingError;
+    use crate::errors::ParseError;
+    use crate::errors::PrimeMismatch;
+    use crate::errors::PublicMemorySizeError;
+    use crate::errors::RangeCheckError;
+    use crate::errors::SegmentError;
+    use crate::errors::SegmentValidationError;
+    use crate::errors::SimError;
+    use crate::errors::SplitError;
+    use crate::errors::UnknownLayout;
+    use crate::errors::UnknownLayoutCode;
+    use crate::errors::ValidationError;
+    use crate::instruction::ApUpdateKind;
+    use crate::instruction::DecodedInstruction;
+    use crate::instruction::DstReg;
+    use crate::instruction::Op0Reg;
+    use crate::instruction::Op1Src;
+    use crate::instruction::Opcode;
+    use crate::instruction::PcUpdateKind;
+    use crate::instruction::ResLogic;
+    use crate::sharp::decode_public_memory_page;
+    use crate::sharp::encode_public_memory_page;
+    use crate::sharp::hash_elements;
+    use crate::sharp::hash_memory_page;
+    use crate::types::MemoryAddress;
+    use crate::AirPrivateInput;
+    use crate::AirPublicInput;
+    use crate::BitwiseInstance;
+    use crate::BuiltinInstance;
+    use crate::CompiledProgram;
+    use crate::EcOpInstance;
+    use crate::EcdsaInstance;
+    use crate::Flag;
+    use crate::FlagGroup;
+    use crate::KeccakInstance;
+    use crate::Layout;
+    use crate::Memory;
+    use crate::MemoryEntry;
+    use crate::MemorySegments;
+    use crate::MemoryStore;
+    use crate::PedersenInstance;
+    use crate::PoseidonInstance;
+    use crate::RangeCheckInstance;
+    use crate::RegisterState;
+    use crate::RegisterStates;
+    use crate::Segment;
+    use crate::SegmentAddressMapper;
+    use crate::SegmentKind;
+    use crate::Signature;
+    use crate::SparseMemory;
+    use crate::step;
+    use crate::ValidatedProgram;
+    use crate::Word;
+    use ark_ff::PrimeField;
+    use ark_ff::UniformRand;
+    use ark_serialize::CanonicalDeserialize;
+    use ark_serialize::CanonicalSerialize;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+    use num_bigint::BigUint;
+    use ruint::aliases::U256;
+    use ruint::uint;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use sha3::Keccak256;
+    use std::array;
+    use std::fmt::Debug;
+
+    const AIR_PUBLIC_INPUT: &str = include_str!("../../example/air-public-input.json");
+    const STARKNET_AIR_PUBLIC_INPUT: &str =
+        include_str!("../../example/bootloader/air-public-input.json");
+    const PROGRAM: &str = include_str!("../../example/array-sum.json");
+    const MEMORY: &[u8] = include_bytes!("../../example/memory.bin");
+    const TRACE: &[u8] = include_bytes!("../../example/trace.bin");
+    const AIR_PRIVATE_INPUT: &str = include_str!("../../example/air-private-input.json");
+
+    #[test]
+    fn public_memory_with_more_entries_than_steps_is_invalid() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        assert_eq!(Ok(()), air_public_input.validate_public_memory_size());
+
+        air_public_input.n_steps = 16;
+
+        assert_eq!(
+            Err(PublicMemorySizeError::TooManyEntries {
+                entries: 44,
+                n_steps: 16
+            }),
+            air_public_input.validate_public_memory_size()
+        );
+    }
+
+    #[test]
+    fn public_memory_outside_public_segments_is_invalid() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let range_check_addr = air_public_input
+            .memory_segments
+            .range_check
+            .unwrap()
+            .begin_addr;
+
+        air_public_input.public_memory.push(MemoryEntry {
+            address: range_check_addr,
+            value: Fp::from(1u8),
+        });
+
+        assert_eq!(
+            Err(PublicMemorySizeError::AddressOutOfRange {
+                address: range_check_addr
+            }),
+            air_public_input.validate_public_memory_size()
+        );
+    }
+
+    fn assert_round_trips<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(value, serde_json::from_str::<T>(&json).unwrap());
+    }
+
+    #[test]
+    fn pedersen_instance_serializes_like_cairo_run() {
+        let instance = PedersenInstance {
+            index: 3,
+            a: uint!(0x1234_U256),
+            b: uint!(0x800000000000011000000000000000000000000000000000000000000000000_U256),
+        };
+
+        let json = serde_json::to_value(instance).unwrap();
+
+        assert_eq!(
+            serde_json::json!({
+                "index": 3,
+                "x": "0x1234",
+                "y": "0x800000000000011000000000000000000000000000000000000000000000000",
+            }),
+            json
+        );
+        assert_round_trips(instance);
+    }
+
+    #[test]
+    fn builtin_instances_round_trip() {
+        assert_round_trips(EcdsaInstance {
+            index: 1,
+            pubkey_x: uint!(0x1ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca_U256),
+            message: uint!(0x2_U256),
+            signature: Signature {
+                r: uint!(0x5f0e2d2b2dcf1b3cbd6e3b6b8b8a0ad30e1d6b3a5f5d2d0c7e7a4b1f8f2e3d4_U256),
+                w: uint!(0x0_U256),
+            },
+        });
+        assert_round_trips(BitwiseInstance {
+            index: 2,
+            x: uint!(0xff00_U256),
+            y: uint!(0x0ff0_U256),
+        });
+        assert_round_trips(EcOpInstance {
+            index: 3,
+            p_x: uint!(0x1_U256),
+            p_y: uint!(0x2_U256),
+            q_x: uint!(0x3_U256),
+            q_y: uint!(0x4_U256),
+            m: uint!(0x5_U256),
+        });
+        assert_round_trips(PoseidonInstance {
+            index: 4,
+            input0: uint!(0x6_U256),
+            input1: uint!(0x7_U256),
+            input2: uint!(0x8_U256),
+        });
+        assert_round_trips(KeccakInstance::from_inputs(
+            5,
+            array::from_fn(|i| U256::from(i)),
+        ));
+    }
+
+    #[test]
+    fn memory_entry_with_zero_address_is_invalid() {
+        assert_eq!(
+            Err(AddressError::Zero),
+            MemoryEntry::new_checked(MemoryAddress(0), Fp::from(1u8))
+        );
+        assert!(MemoryEntry::new_checked(MemoryAddress(1), Fp::from(1u8)).is_ok());
+
+        let entry = MemoryEntry {
+            address: MemoryAddress(0),
+            value: U256::from(1),
+        };
+        assert_eq!(
+            Err(InvalidMemoryEntry::Address(AddressError::Zero)),
+            entry.try_into_felt_entry::<Fp>()
+        );
+    }
+
+    #[test]
+    fn word_felt_conversion_round_trips() {
+        let mut rng = ark_std::test_rng();
+        for _ in 0..100 {
+            let word = Word::<Fp>::from_felt(Fp::rand(&mut rng));
+            assert_eq!(word, Word::from_felt(word.into_felt()));
+        }
+        assert_eq!(Word::<Fp>::new(U256::from(42)), Word::from_u64(42));
+    }
+
+    #[test]
+    fn segment_address_mapper_round_trips() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let mapper = SegmentAddressMapper::new(&air_public_input.memory_segments);
+
+        assert_eq!(
+            Some((SegmentKind::Program, 0)),
+            mapper.classify(MemoryAddress(1))
+        );
+        assert_eq!(
+            Some((SegmentKind::Program, 43)),
+            mapper.classify(MemoryAddress(44))
+        );
+        assert_eq!(
+            Some((SegmentKind::Execution, 0)),
+            mapper.classify(MemoryAddress(45))
+        );
+        // the output and builtin segments of the example are empty
+        assert_eq!(None, mapper.classify(MemoryAddress(76)));
+        assert_eq!(None, mapper.classify(MemoryAddress(0)));
+
+        for addr in (1..76).map(MemoryAddress) {
+            let (kind, offset) = mapper.classify(addr).unwrap();
+            assert_eq!(Some(addr), mapper.to_absolute(kind, offset));
+        }
+        assert_eq!(None, mapper.to_absolute(SegmentKind::Execution, 31));
+        assert_eq!(None, mapper.to_absolute(SegmentKind::Pedersen, 0));
+        assert_eq!(None, mapper.to_absolute(SegmentKind::Ecdsa, 0));
+    }
+
+    #[test]
+    fn air_public_input_canonical_serialization_round_trips() {
+        let air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+        assert_eq!(Layout::Starknet, air_public_input.layout);
+
+        let mut bytes = Vec::new();
+        air_public_input.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = AirPublicInput::<Fp>::deserialize_compressed(&*bytes).unwrap();
+
+        assert_eq!(air_public_input.compressed_size(), bytes.len());
+        assert_eq!(air_public_input, deserialized);
+    }
+
+    #[test]
+    fn read_segment_matches_public_memory() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let memory = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let segments = air_public_input.memory_segments;
+        let program = Segment {
+            begin_addr: segments.program.begin_addr,
+            stop_ptr: segments.execution.begin_addr,
+        };
+
+        let program_entries = memory.read_segment(program);
+
+        let public_program_entries = air_public_input
+            .public_memory
+            .iter()
+            .filter(|e| e.address < program.stop_ptr)
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(public_program_entries, program_entries);
+    }
+
+    #[test]
+    fn memory_from_entries_round_trips() {
+        let memory = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let accesses = memory.sorted_accesses();
+
+        let from_pairs = accesses.iter().copied().collect::<Memory<Fp>>();
+        let from_entries =
+            Memory::from_entries(accesses.iter().map(|&(address, word)| {
+                MemoryEntry::from_word(MemoryAddress(address as u32), word)
+            }));
+
+        assert_eq!(memory.len(), from_pairs.len());
+        assert_eq!(accesses, from_pairs.sorted_accesses());
+        assert_eq!(accesses, from_entries.sorted_accesses());
+    }
+
+    #[test]
+    fn memory_extends_past_capacity() {
+        let word = |v: u64| Word::<Fp>::new(U256::from(v));
+        let mut memory = Memory::<Fp>::with_capacity(2);
+        assert!(memory.is_empty());
+
+        memory.extend([(2, word(5)), (6, word(8))]);
+
+        assert_eq!(7, memory.len());
+        assert_eq!(vec![(2, word(5)), (6, word(8))], memory.sorted_accesses());
+    }
+
+    #[test]
+    fn sparse_memory_matches_dense_memory() {
+        fn occupied_words<M: MemoryStore<Fp>>(memory: &M, addresses: &[usize]) -> Vec<Word<Fp>> {
+            addresses
+                .iter()
+                .filter_map(|&address| memory.get_checked(address))
+                .collect()
+        }
+
+        let dense = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let sparse = SparseMemory::<Fp>::from_reader(MEMORY).unwrap();
+        let addresses = (0..dense.len() + 10).collect::<Vec<usize>>();
+
+        assert_eq!(dense.sorted_accesses(), sparse.sorted_accesses());
+        assert_eq!(
+            MemoryStore::occupied_count(&dense),
+            MemoryStore::occupied_count(&sparse)
+        );
+        assert_eq!(
+            occupied_words(&dense, &addresses),
+            occupied_words(&sparse, &addresses)
+        );
+        let mut sparse_cells = sparse.into_iter().collect::<Vec<_>>();
+        sparse_cells.sort_unstable_by_key(|&(address, _)| address);
+        assert_eq!(dense.into_iter().collect::<Vec<_>>(), sparse_cells);
+    }
+
+    #[test]
+    fn public_memory_matches_memory() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let memory = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let mut entries = air_public_input
+            .public_memory
+            .iter()
+            .map(|entry| MemoryEntry {
+                address: entry.address,
+                value: Word::from_felt(entry.value).0,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(Ok(()), memory.validate_public_memory(&entries));
+
+        let memory_value = memory.get_checked(entries[1].address.into());
+        entries[1].value += U256::from(1);
+        assert_eq!(
+            Err(InconsistencyError {
+                address: entries[1].address,
+                memory_value,
+                claimed_value: entries[1].value,
+            }),
+            memory.validate_public_memory(&entries)
+        );
+
+        let empty_address = MemoryAddress(memory.len() as u32 + 5);
+        let unwritten = [MemoryEntry {
+            address: empty_address,
+            value: U256::ZERO,
+        }];
+        assert_eq!(
+            Err(InconsistencyError {
+                address: empty_address,
+                memory_value: None,
+                claimed_value: U256::ZERO,
+            }),
+            memory.validate_public_memory(&unwritten)
+        );
+    }
+
+    #[test]
+    fn builtin_counts_match_instances_and_segments() {
+        let mut air_private_input: AirPrivateInput =
+            serde_json::from_str(AIR_PRIVATE_INPUT).unwrap();
+        air_private_input
+            .pad_to_power_of_two(Layout::Recursive, 10)
+            .unwrap();
+        let air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+        let segments = air_public_input.memory_segments;
+        let bitwise = segments.bitwise.unwrap();
+
+        let private_counts = air_private_input.builtin_counts();
+        let public_counts = air_public_input.builtin_instance_counts();
 
-    // Group: [FlagGroup::Op1Src]
-    Op1Imm = 2,
-    Op1Fp = 3,
-    Op1Ap = 4,
+        assert_eq!(8, private_counts.pedersen);
+        assert_eq!(128, private_counts.bitwise);
+        assert_eq!(264, private_counts.total());
+        assert_eq!(
+            "264 builtin instances (pedersen=8 range_check=128 ecdsa=0 bitwise=128 ec_op=0 \
+             poseidon=0 keccak=0)",
+            private_counts.to_string()
+        );
+        assert_eq!(
+            bitwise.instance_count(BitwiseInstance::CELLS_PER_INSTANCE) as usize,
+            public_counts.bitwise
+        );
+    }
 
-    // Group: [FlagGroup::ResLogic]
-    ResAdd = 5,
-    ResMul = 6,
+    #[test]
+    fn segment_len_and_instance_count() {
+        let segment = Segment::from_instances(MemoryAddress(100), 4, 3);
+        let empty = Segment::new_checked(MemoryAddress(7), MemoryAddress(7)).unwrap();
 
-    // Group: [FlagGroup::PcUpdate]
-    PcJumpAbs = 7,
-    PcJumpRel = 8,
-    PcJnz = 9,
+        assert_eq!(MemoryAddress(112), segment.stop_ptr);
+        assert_eq!(12, segment.len());
+        assert_eq!(4, segment.instance_count(3));
+        assert_eq!(2, segment.instance_count(5));
+        assert!(!segment.is_empty());
+        assert_eq!(0, empty.len());
+        assert_eq!(0, empty.instance_count(3));
+        assert!(empty.is_empty());
+        assert_eq!(empty, Segment::from_instances(MemoryAddress(7), 0, 3));
+        assert_eq!(
+            Some(segment),
+            Segment::new_checked(MemoryAddress(100), MemoryAddress(112))
+        );
+        assert_eq!(
+            None,
+            Segment::new_checked(MemoryAddress(8), MemoryAddress(7))
+        );
+    }
 
-    // Group: [FlagGroup::ApUpdate]
-    ApAdd = 10,
-    ApAdd1 = 11,
+    #[test]
+    fn bitwise_outputs() {
+        let ones = (U256::from(1) << 251) - U256::from(1);
+        let mixed = BitwiseInstance {
+            index: 0,
+            x: U256::from(0b1100),
+            y: U256::from(0b1010),
+        };
+        let zeros = BitwiseInstance::new_empty(0);
+        let all_ones = BitwiseInstance {
+            index: 0,
+            x: ones,
+            y: ones,
+        };
 
-    // Group: [FlagGroup::Opcode]
-    OpcodeCall = 12,
-    OpcodeRet = 13,
-    OpcodeAssertEq = 14,
+        assert_eq!((U256::ZERO, U256::ZERO, U256::ZERO), zeros.outputs());
+        assert_eq!((ones, U256::ZERO, ones), all_ones.outputs());
+        assert_eq!(
+            (U256::from(0b1000), U256::from(0b0110), U256::from(0b1110)),
+            mixed.outputs()
+        );
+    }
 
-    // 0 - padding to make flag cells a power-of-2
-    Zero = 15,
+    #[test]
+    fn bitwise_instance_is_verified_against_memory() {
+        let instance = BitwiseInstance {
+            index: 1,
+            x: U256::from(0b1100),
+            y: U256::from(0b1010),
+        };
+        let segment = Segment::from_instances(MemoryAddress(20), 2, 5);
+        let addrs = instance.mem_addr(segment.begin_addr);
+        let (x_and_y, x_xor_y, x_or_y) = instance.outputs();
+        let word = |v: U256| Word::<Fp>::new(v);
+        let mut memory = [
+            (addrs.x, instance.x),
+            (addrs.y, instance.y),
+            (addrs.x_and_y, x_and_y),
+            (addrs.x_xor_y, x_xor_y),
+            (addrs.x_or_y, x_or_y),
+        ]
+        .into_iter()
+        .map(|(address, value)| (address.into(), word(value)))
+        .collect::<Memory<Fp>>();
+        assert!(instance.verify_in_memory(&memory, segment));
+        assert!(
+            !instance.verify_in_memory(&memory, Segment::from_instances(MemoryAddress(20), 1, 5))
+        );
+
+        memory.extend([(addrs.x_xor_y.into(), word(x_and_y))]);
+
+        assert!(!instance.verify_in_memory(&memory, segment));
+    }
+
+    #[test]
+    fn range_check_limbs_round_trip() {
+        let value = uint!(0x0123_4567_89ab_cdef_fedc_ba98_7654_3210_U256);
+        let instance = RangeCheckInstance { index: 3, value };
+
+        let limbs = instance.split_into_limbs();
+        let round_tripped = RangeCheckInstance::from_limbs(3, limbs);
+
+        assert_eq!(0x3210, limbs[0]);
+        assert_eq!(0x0123, limbs[7]);
+        assert_eq!([0; 8], limbs[8..]);
+        assert_eq!(value, round_tripped.value);
+        assert_eq!(3, round_tripped.index);
+        assert_eq!(
+            [u16::MAX; 16],
+            RangeCheckInstance::from_limbs(0, [u16::MAX; 16]).split_into_limbs()
+        );
+        assert!(instance.is_valid_for_range(0, u16::MAX));
+        assert!(!instance.is_valid_for_range(0x0124, u16::MAX));
+        assert!(!RangeCheckInstance::from_limbs(0, [1; 16]).is_valid_for_range(0, u16::MAX));
+    }
+
+    #[test]
+    fn range_check_out_of_range_is_invalid() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let mut air_private_input: AirPrivateInput =
+            serde_json::from_str(AIR_PRIVATE_INPUT).unwrap();
+        // rc_min = 0x7ffc, rc_max = 0x8002
+        let mut limbs = [0; 16];
+        limbs[..8].copy_from_slice(&[
+            0x7ffc, 0x7ffd, 0x7ffe, 0x7fff, 0x8000, 0x8001, 0x8002, 0x8000,
+        ]);
+        air_private_input.range_check = vec![RangeCheckInstance::from_limbs(0, limbs)];
+        assert_eq!(
+            Ok(()),
+            air_public_input.validate_range_checks(&air_private_input)
+        );
+
+        limbs[5] = 0x8003;
+        let invalid = RangeCheckInstance::from_limbs(1, limbs);
+        air_private_input.range_check.push(invalid);
+
+        assert_eq!(
+            Err(RangeCheckError {
+                index: 1,
+                value: invalid.value,
+                rc_min: 0x7ffc,
+                rc_max: 0x8002,
+            }),
+            air_public_input.validate_range_checks(&air_private_input)
+        );
+    }
+
+    #[test]
+    fn memory_segments_builder_sets_optional_segments() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let expected = air_public_input.memory_segments;
+
+        let segments = MemorySegments::new(expected.program, expected.execution)
+            .with_output_segment(expected.output.unwrap())
+            .with_pedersen_segment(expected.pedersen.unwrap())
+            .with_range_check_segment(expected.range_check.unwrap())
+            .with_ecdsa_segment(Segment {
+                begin_addr: MemoryAddress(100),
+                stop_ptr: MemoryAddress(102),
+            })
+            .with_bitwise_segment(expected.bitwise.unwrap())
+            .without_ecdsa_segment();
+
+        assert_eq!(expected, segments);
+        assert_eq!(None, segments.without_output_segment().output);
+    }
+
+    #[test]
+    fn segment_split_at_divides_segment() {
+        let segment = Segment {
+            begin_addr: MemoryAddress(10),
+            stop_ptr: MemoryAddress(20),
+        };
+
+        let (left, right) = segment.split_at(MemoryAddress(15)).unwrap();
+
+        assert_eq!(
+            (MemoryAddress(10), MemoryAddress(15)),
+            (left.begin_addr, left.stop_ptr)
+        );
+        assert_eq!(
+            (MemoryAddress(15), MemoryAddress(20)),
+            (right.begin_addr, right.stop_ptr)
+        );
+        assert_eq!(
+            Err(SplitError::AddressOutOfRange(MemoryAddress(10))),
+            segment.split_at(MemoryAddress(10))
+        );
+        assert_eq!(
+            Err(SplitError::AddressOutOfRange(MemoryAddress(20))),
+            segment.split_at(MemoryAddress(20))
+        );
+        assert_eq!(MemoryAddress(15), segment.truncate_to_size(5).stop_ptr);
+        assert_eq!(segment, segment.truncate_to_size(50));
+    }
+
+    #[test]
+    fn register_states_display_as_table() {
+        let states = RegisterStates(vec![
+            RegisterState {
+                pc: 1,
+                ap: 0x20,
+                fp: 0x20,
+            },
+            RegisterState {
+                pc: 3,
+                ap: 0x21,
+                fp: 0x20,
+            },
+            RegisterState {
+                pc: 5,
+                ap: 0x22,
+                fp: 0x20,
+            },
+        ]);
+
+        assert_eq!(
+            concat!(
+                "    step  registers\n",
+                "       0  pc=0x1 ap=0x20 fp=0x20\n",
+                "       1  pc=0x3 ap=0x21 fp=0x20\n",
+                "       2  pc=0x5 ap=0x22 fp=0x20\n",
+            ),
+            states.to_string()
+        );
+        assert_eq!(
+            "    step  registers\n       1  pc=0x3 ap=0x21 fp=0x20\n",
+            states.display_range(1, 2).to_string()
+        );
+    }
+
+    #[test]
+    fn memory_entry_word_conversion_round_trips() {
+        let word = Word::<Fp>::from_u64(0x1234);
+
+        let entry = MemoryEntry::from((MemoryAddress(7), word));
+
+        assert_eq!(MemoryEntry::from_word(MemoryAddress(7), word), entry);
+        assert_eq!(Fp::from(0x1234u32), entry.value);
+        assert_eq!(word, entry.to_word());
+        assert_eq!(Fp::from(7u8), entry.address_as_felt::<Fp>());
+    }
+
+    #[test]
+    fn hex_dump_shows_little_endian_bytes() {
+        let memory = Memory::<Fp>(vec![
+            None,
+            Some(Word::from_u64(0x6948)),
+            None,
+            Some(Word::from_u64(0x0102030405060708)),
+            Some(Word::from_u64(0x7e)),
+        ]);
+        let mut output = Vec::new();
+
+        memory.hex_dump(&mut output, 0, 4).unwrap();
+
+        let zeros = " 00".repeat(24);
+        let dots = ".".repeat(24);
+        assert_eq!(
+            format!(
+                "0x00000001: 48 69 00 00 00 00 00 00{zeros} | Hi......{dots}\n\
+                 0x00000003: 08 07 06 05 04 03 02 01{zeros} | ........{dots}\n"
+            ),
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn layouts_round_trip_through_sharp_code() {
+        for layout in Layout::ALL {
+            assert_eq!(Ok(layout), Layout::from_sharp_code(layout.sharp_code()));
+        }
+        // codes are the big-endian ASCII encoding of the layout name
+        assert_eq!(
+            uint!(0x737461726b6e6574_U256),
+            Layout::Starknet.sharp_code()
+        );
+    }
+
+    #[test]
+    fn layouts_serialize_to_sixteen_bytes() {
+        for layout in Layout::ALL {
+            let mut bytes = Vec::new();
+            if layout.to_string().len() > 16 {
+                assert!(layout.serialize_compressed(&mut bytes).is_err());
+                continue;
+            }
+
+            layout.serialize_compressed(&mut bytes).unwrap();
+
+            assert_eq!(16, bytes.len());
+            assert_eq!(layout, Layout::deserialize_compressed(&*bytes).unwrap());
+        }
+    }
+
+    #[test]
+    fn unknown_sharp_code_is_rejected() {
+        let code = uint!(0x1234_U256);
+
+        let res = Layout::from_sharp_code(code);
+
+        assert_eq!(Err(UnknownLayoutCode(code)), res);
+    }
+
+    #[test]
+    fn layout_names_round_trip() {
+        for layout in Layout::ALL {
+            let name = layout.to_string();
+            let json = format!("\"{name}\"");
+
+            assert_eq!(Ok(layout), name.parse());
+            assert_eq!(json, serde_json::to_string(&layout).unwrap());
+            assert_eq!(layout, serde_json::from_str::<Layout>(&json).unwrap());
+        }
+        assert_eq!(
+            Layout::StarknetWithKeccak,
+            serde_json::from_str("\"starknet_with_keccak\"").unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_layout_name_is_rejected() {
+        let res = "starknet-with-keccak".parse::<Layout>();
+
+        assert_eq!(Err(UnknownLayout("starknet-with-keccak".to_string())), res);
+        let message = res.unwrap_err().to_string();
+        assert!(message.contains("'starknet-with-keccak'"));
+        assert!(message.contains("starknet_with_keccak"));
+    }
+
+    #[test]
+    fn truncated_trace_is_rejected() {
+        let mut trace = Vec::new();
+        for register in [7u64, 5, 1, 8, 5, 3] {
+            trace.extend(register.to_le_bytes());
+        }
+
+        let register_states = RegisterStates::from_reader(&trace[..]).unwrap();
+        let truncated = RegisterStates::from_reader(&trace[..trace.len() - 4]);
+
+        assert_eq!(2, register_states.len());
+        assert_eq!(
+            RegisterState {
+                ap: 8,
+                fp: 5,
+                pc: 3
+            },
+            register_states[1]
+        );
+        assert!(matches!(truncated, Err(ParseError::Truncated)));
+    }
+
+    #[test]
+    fn truncated_memory_is_rejected() {
+        let memory = Memory::<Fp>::from_reader(&MEMORY[..MEMORY.len() - 1]);
+        let truncated_address = Memory::<Fp>::from_reader(&MEMORY[..4]);
+
+        assert!(matches!(memory, Err(ParseError::Truncated)));
+        assert!(matches!(truncated_address, Err(ParseError::Truncated)));
+    }
+
+    #[test]
+    fn sorted_accesses_skips_empty_cells() {
+        let word = |v: u64| Some(Word::<Fp>::new(U256::from(v)));
+        let memory = Memory(vec![None, word(7), None, None, word(3), word(9), None]);
+
+        let accesses = memory
+            .sorted_accesses()
+            .into_iter()
+            .map(|(address, word)| (address, word.0))
+            .collect::<Vec<_>>();
+
+        let expected = vec![(1, U256::from(7)), (4, U256::from(3)), (5, U256::from(9))];
+        assert_eq!(expected, accesses);
+        assert_eq!(3, memory.occupied_count());
+        assert_eq!(
+            expected,
+            memory
+                .into_sorted_accesses()
+                .into_iter()
+                .map(|(address, word)| (address, word.0))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn pedersen_mem_addr_fields() {
+        let addrs = PedersenInstance::new_empty(2).mem_addr(MemoryAddress(100));
+
+        assert_eq!(MemoryAddress(106), addrs.a);
+        assert_eq!(MemoryAddress(107), addrs.b);
+        assert_eq!(MemoryAddress(108), addrs.output);
+    }
+
+    #[test]
+    fn ecdsa_mem_addr_fields() {
+        let instance = EcdsaInstance {
+            index: 2,
+            pubkey_x: U256::ZERO,
+            message: U256::ZERO,
+            signature: Signature {
+                r: U256::ZERO,
+                w: U256::ZERO,
+            },
+        };
+
+        let addrs = instance.mem_addr(MemoryAddress(100));
+
+        assert_eq!(MemoryAddress(104), addrs.pubkey);
+        assert_eq!(MemoryAddress(105), addrs.msg);
+    }
+
+    #[test]
+    fn bitwise_mem_addr_fields() {
+        let addrs = BitwiseInstance::new_empty(2).mem_addr(MemoryAddress(100));
+
+        assert_eq!(MemoryAddress(110), addrs.x);
+        assert_eq!(MemoryAddress(111), addrs.y);
+        assert_eq!(MemoryAddress(112), addrs.x_and_y);
+        assert_eq!(MemoryAddress(113), addrs.x_xor_y);
+        assert_eq!(MemoryAddress(114), addrs.x_or_y);
+    }
+
+    #[test]
+    fn ec_op_mem_addr_fields() {
+        let instance = EcOpInstance {
+            index: 2,
+            p_x: U256::ZERO,
+            p_y: U256::ZERO,
+            q_x: U256::ZERO,
+            q_y: U256::ZERO,
+            m: U256::ZERO,
+        };
+
+        let addrs = instance.mem_addr(MemoryAddress(100));
+
+        assert_eq!(MemoryAddress(114), addrs.p_x);
+        assert_eq!(MemoryAddress(115), addrs.p_y);
+        assert_eq!(MemoryAddress(116), addrs.q_x);
+        assert_eq!(MemoryAddress(117), addrs.q_y);
+        assert_eq!(MemoryAddress(118), addrs.m);
+        assert_eq!(MemoryAddress(119), addrs.r_x);
+        assert_eq!(MemoryAddress(120), addrs.r_y);
+    }
+
+    #[test]
+    fn poseidon_mem_addr_fields() {
+        let addrs = PoseidonInstance::new_empty(2).mem_addr(MemoryAddress(100));
+
+        assert_eq!(MemoryAddress(112), addrs.input0);
+        assert_eq!(MemoryAddress(113), addrs.input1);
+        assert_eq!(MemoryAddress(114), addrs.input2);
+        assert_eq!(MemoryAddress(115), addrs.output0);
+        assert_eq!(MemoryAddress(116), addrs.output1);
+        assert_eq!(MemoryAddress(117), addrs.output2);
+    }
+
+    #[test]
+    fn keccak_mem_addr_fields() {
+        let addrs = KeccakInstance::new_empty(2).mem_addr(MemoryAddress(100));
+
+        assert_eq!(MemoryAddress(132), addrs.input[0]);
+        assert_eq!(MemoryAddress(139), addrs.input[7]);
+        assert_eq!(MemoryAddress(140), addrs.output[0]);
+        assert_eq!(MemoryAddress(147), addrs.output[7]);
+    }
+
+    #[test]
+    fn keccak_output_is_keccak_f1600_permutation() {
+        // first 200 bits of Keccak-f[1600] applied to the all zero state
+        let expected = uint!(0x4dd598261ea65aa9ee84d5ccf933c0478af1258f7940e1dde7_U256);
+        let output = KeccakInstance::new_empty(0).output();
+        assert_eq!(expected, output[0]);
+
+        // bits above 200 are ignored
+        let mut instance = KeccakInstance::new_empty(0);
+        instance.input_s3 = U256::from(1) << KeccakInstance::BITS_PER_CELL;
+        assert_eq!(output, instance.output());
+        instance.input_s3 = U256::from(1);
+        assert_ne!(output, instance.output());
+    }
+
+    #[test]
+    fn keccak_instance_parses_cairo_run_format() {
+        let instance: KeccakInstance = serde_json::from_str(
+            r#"{
+                "index": 1,
+                "input_s0": "0x0",
+                "input_s1": "0x1",
+                "input_s2": "0x2",
+                "input_s3": "0x3",
+                "input_s4": "0x4",
+                "input_s5": "0x5",
+                "input_s6": "0x6",
+                "input_s7": "0x7"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            KeccakInstance::from_inputs(1, array::from_fn(|i| U256::from(i))),
+            instance
+        );
+    }
+
+    #[test]
+    fn builtin_instances_share_layout_info() {
+        fn info<T: BuiltinInstance>() -> (&'static str, u32, u32) {
+            (
+                T::segment_name(),
+                T::cells_per_instance(),
+                T::new_empty(3).index(),
+            )
+        }
+
+        assert_eq!(("pedersen", 3, 3), info::<PedersenInstance>());
+        assert_eq!(("range_check", 1, 3), info::<RangeCheckInstance>());
+        assert_eq!(("ecdsa", 2, 3), info::<EcdsaInstance>());
+        assert_eq!(("bitwise", 5, 3), info::<BitwiseInstance>());
+        assert_eq!(("ec_op", 7, 3), info::<EcOpInstance>());
+        assert_eq!(("poseidon", 6, 3), info::<PoseidonInstance>());
+        assert_eq!(("keccak", 16, 3), info::<KeccakInstance>());
+    }
+
+    #[test]
+    fn ret_instruction_decodes() {
+        let ret = Word::<Fp>::from_u64(0x208b7fff7fff7ffe);
+
+        let instruction = ret.decode();
+
+        assert_eq!(
+            DecodedInstruction {
+                opcode: Opcode::Ret,
+                pc_update: PcUpdateKind::JumpAbs,
+                ap_update: ApUpdateKind::Regular,
+                res_logic: ResLogic::Op1,
+                dst_reg: DstReg::Fp,
+                op0_reg: Op0Reg::Fp,
+                op1_src: Op1Src::Fp,
+                off_dst: -2,
+                off_op0: -1,
+                off_op1: -1,
+            },
+            instruction
+        );
+        assert_eq!(ret, Word::encode(instruction));
+    }
+
+    #[test]
+    fn instruction_encoding_round_trips() {
+        let instruction = DecodedInstruction {
+            opcode: Opcode::Call,
+            pc_update: PcUpdateKind::JumpRel,
+            ap_update: ApUpdateKind::Regular,
+            res_logic: ResLogic::Op1,
+            dst_reg: DstReg::Ap,
+            op0_reg: Op0Reg::Ap,
+            op1_src: Op1Src::Pc,
+            off_dst: 0,
+            off_op0: 1,
+            off_op1: 1,
+        };
+
+        let word = Word::<Fp>::encode(instruction);
+
+        assert_eq!(instruction, word.decode());
+        assert!(word.get_flag(Flag::OpcodeCall));
+        assert_eq!(0x8001, word.get_off_op1());
+        let instruction = DecodedInstruction {
+            opcode: Opcode::AssertEq,
+            pc_update: PcUpdateKind::Jnz,
+            ap_update: ApUpdateKind::Add1,
+            res_logic: ResLogic::Mul,
+            dst_reg: DstReg::Fp,
+            op0_reg: Op0Reg::Fp,
+            op1_src: Op1Src::Ap,
+            off_dst: i16::MIN,
+            off_op0: i16::MAX,
+            off_op1: -1,
+        };
+        assert_eq!(instruction, Word::<Fp>::encode(instruction).decode());
+    }
+
+    #[test]
+    fn instruction_from_components_round_trips() {
+        // [ap] = [fp + -3] * 5; ap++
+        let word = Word::<Fp>::from_instruction(
+            Opcode::AssertEq,
+            PcUpdateKind::Regular,
+            ApUpdateKind::Add1,
+            ResLogic::Mul,
+            DstReg::Ap,
+            Op0Reg::Fp,
+            Op1Src::Pc,
+            0,
+            -3,
+            1,
+        );
+
+        let instruction = word.decode();
+
+        assert_eq!(Opcode::AssertEq, instruction.opcode);
+        assert_eq!(PcUpdateKind::Regular, instruction.pc_update);
+        assert_eq!(ApUpdateKind::Add1, instruction.ap_update);
+        assert_eq!(ResLogic::Mul, instruction.res_logic);
+        assert_eq!(DstReg::Ap, instruction.dst_reg);
+        assert_eq!(Op0Reg::Fp, instruction.op0_reg);
+        assert_eq!(Op1Src::Pc, instruction.op1_src);
+        assert_eq!(0, instruction.off_dst);
+        assert_eq!(-3, instruction.off_op0);
+        assert_eq!(1, instruction.off_op1);
+        assert_eq!(2, word.size());
+        assert!(word.is_valid());
+    }
+
+    #[test]
+    fn step_reproduces_trace() {
+        let memory = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let register_states = RegisterStates::from_reader(TRACE).unwrap();
+
+        for states in register_states.windows(2) {
+            assert_eq!(states[1], step(states[0], &memory).unwrap());
+        }
+    }
+
+    #[test]
+    fn step_fails_on_missing_instruction() {
+        let memory = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let state = RegisterState {
+            pc: memory.len(),
+            ap: 1,
+            fp: 1,
+        };
+
+        assert!(matches!(
+            step(state, &memory),
+            Err(SimError::MissingValue { address }) if address == memory.len()
+        ));
+    }
+
+    #[test]
+    fn executed_instructions_are_valid() {
+        let memory = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let register_states = RegisterStates::from_reader(TRACE).unwrap();
+
+        assert!(memory.validate_instructions(&register_states).is_ok());
+    }
+
+    #[test]
+    fn invalid_instructions_are_rejected() {
+        let ret = DecodedInstruction {
+            opcode: Opcode::Ret,
+            pc_update: PcUpdateKind::JumpAbs,
+            ap_update: ApUpdateKind::Regular,
+            res_logic: ResLogic::Op1,
+            dst_reg: DstReg::Fp,
+            op0_reg: Op0Reg::Fp,
+            op1_src: Op1Src::Fp,
+            off_dst: -2,
+            off_op0: -1,
+            off_op1: -1,
+        };
+        let call_with_ap_add1 = DecodedInstruction {
+            opcode: Opcode::Call,
+            ap_update: ApUpdateKind::Add1,
+            ..ret
+        };
+        let jnz_with_res_add = DecodedInstruction {
+            opcode: Opcode::Nop,
+            pc_update: PcUpdateKind::Jnz,
+            res_logic: ResLogic::Add,
+            ..ret
+        };
+        let immediate_with_offset = DecodedInstruction {
+            op1_src: Op1Src::Pc,
+            off_op1: 2,
+            ..ret
+        };
+
+        assert!(Word::<Fp>::encode(ret).is_valid());
+        assert!(matches!(
+            Word::<Fp>::encode(call_with_ap_add1).validate(),
+            Err(InvalidInstruction::CallWithApUpdate {
+                ap_update: ApUpdateKind::Add1
+            })
+        ));
+        assert!(matches!(
+            Word::<Fp>::encode(jnz_with_res_add).validate(),
+            Err(InvalidInstruction::InvalidJnz)
+        ));
+        assert!(matches!(
+            Word::<Fp>::encode(immediate_with_offset).validate(),
+            Err(InvalidInstruction::InvalidImmediateOffset { off_op1: 2 })
+        ));
+        let zero_flag_set = Word::<Fp>::new(Word::<Fp>::encode(ret).0 | uint!(1_U256) << 63);
+        assert!(matches!(
+            zero_flag_set.validate(),
+            Err(InvalidInstruction::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn layouts_require_their_builtins() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let mut starknet_air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+        assert_eq!(Ok(()), air_public_input.validate_layout_builtins());
+        assert_eq!(Ok(()), starknet_air_public_input.validate_layout_builtins());
+
+        let segments = &mut starknet_air_public_input.memory_segments;
+        *segments = segments.without_ec_op_segment();
+
+        assert_eq!(
+            Err(MissingBuiltin {
+                layout: Layout::Starknet,
+                builtin: SegmentKind::EcOp,
+            }),
+            starknet_air_public_input.validate_layout_builtins()
+        );
+        // every builtin with a ratio must be required by the layout
+        for layout in Layout::ALL {
+            let required = layout.required_builtins();
+            assert!(layout
+                .builtin_ratios()
+                .iter()
+                .all(|(builtin, _)| required.contains(builtin)));
+        }
+        assert_eq!(
+            Some(64),
+            Layout::Starknet.builtin_ratio(SegmentKind::Bitwise)
+        );
+        assert_eq!(None, Layout::Recursive.builtin_ratio(SegmentKind::Ecdsa));
+    }
+
+    #[test]
+    fn output_values_are_read_from_public_memory() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+        let felt = |value: U256| Word::<Fp>::new(value).into_felt();
+        let expected = vec![
+            felt(uint!(
+                0x68cc8cebf2a51a1025eedd68a01922b720b8a65c3731f3b6df5a524e67ff516_U256
+            )),
+            felt(uint!(
+                0x15226b5454b1b92f2ae32675a3bf61e51845c15a5428fe7f5fa6e1de3e3d45_U256
+            )),
+            Fp::from(0u8),
+        ];
+        assert_eq!(Some(expected.clone()), air_public_input.output_values());
+
+        air_public_input.public_memory.reverse();
+        assert_eq!(Some(expected), air_public_input.output_values());
+
+        let segments = &mut air_public_input.memory_segments;
+        *segments = segments.without_output_segment();
+
+        assert_eq!(None, air_public_input.output_values());
+    }
+
+    #[test]
+    fn public_memory_hash_matches_known_vectors() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let starknet_air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+
+        assert_eq!(
+            uint!(0x6d66dd61911e732fd0f34e8c915a8789903eaf3adc4feab663da17640c20e1dd_U256),
+            U256::from_be_bytes(air_public_input.public_memory_hash::<Keccak256>())
+        );
+        assert_eq!(
+            uint!(0x11cb67c6f9ed21c7599ca505928803ab95a4f1f5bddce4c2cf7afc8b73b3dff4_U256),
+            U256::from_be_bytes(starknet_air_public_input.public_memory_hash::<Keccak256>())
+        );
+    }
+
+    #[test]
+    fn public_memory_hash_is_verified() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let expected = air_public_input.public_memory_hash::<Keccak256>();
+
+        air_public_input.public_memory.reverse();
+        assert!(air_public_input.verify_public_memory_hash::<Keccak256>(&expected));
+
+        air_public_input.public_memory[0].value += Fp::from(1u8);
+        assert!(!air_public_input.verify_public_memory_hash::<Keccak256>(&expected));
+    }
+
+    #[test]
+    fn sharp_hash_elements_matches_known_vectors() {
+        let hash = |inputs: &[Fp]| {
+            U256::from_be_bytes(hash_elements::<Keccak256, Fp>(inputs.iter().copied()))
+        };
+
+        // Keccak-256 of the empty string
+        assert_eq!(
+            uint!(0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470_U256),
+            hash(&[])
+        );
+        // matches `keccak256(abi.encode(1, 2, 3))` in Solidity
+        assert_eq!(
+            uint!(0x6e0c627900b24bd432fe7b1f713f1b0744091a646a9fe4a65a18dfed21f2949c_U256),
+            hash(&[Fp::from(1u8), Fp::from(2u8), Fp::from(3u8)])
+        );
+        // largest field element
+        assert_eq!(
+            uint!(0x3b4ecbf341b435aba1847e3cfd6f06059078115be574d115fe0e7288c90cc1e9_U256),
+            hash(&[-Fp::from(1u8)])
+        );
+    }
+
+    #[test]
+    fn sharp_hash_memory_page_hashes_address_value_pairs() {
+        let entries = [(7, 11u8), (8, 13)].map(|(address, value)| MemoryEntry {
+            address: MemoryAddress(address),
+            value: Fp::from(value),
+        });
+        let inputs = [Fp::from(7u8), Fp::from(11u8), Fp::from(8u8), Fp::from(13u8)];
+
+        assert_eq!(
+            hash_elements::<Keccak256, Fp>(inputs),
+            hash_memory_page::<Keccak256, Fp>(&entries)
+        );
+    }
+
+    #[test]
+    fn sharp_output_page_round_trips() {
+        let air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+        let output = air_public_input.memory_segments.output.unwrap();
+        let mut page = air_public_input
+            .public_memory
+            .into_iter()
+            .filter(|entry| (output.begin_addr..output.stop_ptr).contains(&entry.address))
+            .collect::<Vec<_>>();
+        page.sort_by_key(|entry| entry.address);
+        assert!(!page.is_empty());
+
+        let page_data = encode_public_memory_page::<Keccak256, Fp>(&page);
+
+        assert_eq!(
+            U256::from_be_bytes(hash_memory_page::<Keccak256, Fp>(&page)),
+            page_data[1]
+        );
+        assert_eq!(
+            Ok(page),
+            decode_public_memory_page::<Keccak256, Fp>(&page_data)
+        );
+    }
+
+    #[test]
+    fn sharp_public_memory_page_round_trips() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let entries = air_public_input.public_memory;
+
+        let page_data = encode_public_memory_page::<Keccak256, Fp>(&entries);
+        let decoded = decode_public_memory_page::<Keccak256, Fp>(&page_data).unwrap();
+
+        assert_eq!(2 + 2 * entries.len(), page_data.len());
+        assert_eq!(entries, decoded);
+
+        let empty_page_data = encode_public_memory_page::<Keccak256, Fp>(&[]);
+        assert_eq!(
+            Ok(Vec::new()),
+            decode_public_memory_page::<Keccak256, Fp>(&empty_page_data)
+        );
+    }
+
+    #[test]
+    fn sharp_public_memory_page_decoding_fails_on_invalid_data() {
+        let decode = |page_data: &[U256]| decode_public_memory_page::<Keccak256, Fp>(page_data);
+        let entries = [(1, 7u8), (2, 11), (5, 13)].map(|(address, value)| MemoryEntry {
+            address: MemoryAddress(address),
+            value: Fp::from(value),
+        });
+        let page_data = encode_public_memory_page::<Keccak256, Fp>(&entries);
+        let modulus = U256::from::<BigUint>(Fp::MODULUS.into());
+
+        assert_eq!(
+            Err(DecodeError::TooShort { len: 1 }),
+            decode(&page_data[..1])
+        );
+        assert_eq!(
+            Err(DecodeError::SizeMismatch {
+                size: U256::from(3),
+                pairs: 2,
+            }),
+            decode(&page_data[..6])
+        );
+
+        let mut invalid_address = page_data.clone();
+        invalid_address[2] = U256::ZERO;
+        assert_eq!(
+            Err(DecodeError::InvalidAddress {
+                address: U256::ZERO
+            }),
+            decode(&invalid_address)
+        );
+
+        let mut invalid_value = page_data.clone();
+        invalid_value[3] = modulus;
+        assert_eq!(
+            Err(DecodeError::FieldElementOutOfRange(
+                InvalidFieldElementError {
+                    value: modulus,
+                    modulus,
+                }
+            )),
+            decode(&invalid_value)
+        );
+
+        let mut tampered = page_data.clone();
+        tampered[3] += U256::from(1);
+        let computed = hash_elements::<Keccak256, Fp>([1u8, 8, 2, 11, 5, 13].map(Fp::from));
+        assert_eq!(
+            Err(DecodeError::HashMismatch {
+                computed,
+                claimed: page_data[1].to_be_bytes(),
+            }),
+            decode(&tampered)
+        );
+    }
+
+    #[test]
+    fn air_public_input_is_loaded_from_json() {
+        let expected: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../example/air-public-input.json"
+        );
+
+        assert_eq!(
+            expected,
+            AirPublicInput::from_json(AIR_PUBLIC_INPUT).unwrap()
+        );
+        assert_eq!(
+            expected,
+            AirPublicInput::from_reader(AIR_PUBLIC_INPUT.as_bytes()).unwrap()
+        );
+        assert_eq!(expected, AirPublicInput::from_path(path).unwrap());
+        assert_round_trips(expected);
+    }
+
+    #[test]
+    fn air_public_input_serializes_like_cairo_run() {
+        let memory_cells = |json: &serde_json::Value| {
+            json["public_memory"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|entry| (entry["address"].clone(), entry["value"].clone()))
+                .collect::<Vec<_>>()
+        };
+
+        for json in [AIR_PUBLIC_INPUT, STARKNET_AIR_PUBLIC_INPUT] {
+            let air_public_input = AirPublicInput::<Fp>::from_json(json).unwrap();
+            let serialized = serde_json::to_string(&air_public_input).unwrap();
+            assert_eq!(
+                air_public_input,
+                AirPublicInput::from_json(&serialized).unwrap()
+            );
+
+            // absent segments are omitted and values are hex strings
+            let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+            let actual: serde_json::Value = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(expected["memory_segments"], actual["memory_segments"]);
+            assert_eq!(memory_cells(&expected), memory_cells(&actual));
+        }
+    }
+
+    #[test]
+    fn air_private_input_is_loaded_from_json() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../example/air-private-input.json"
+        );
+
+        let from_json = AirPrivateInput::from_json(AIR_PRIVATE_INPUT).unwrap();
+        let from_reader = AirPrivateInput::from_reader(AIR_PRIVATE_INPUT.as_bytes()).unwrap();
+        let from_path = AirPrivateInput::from_path(path).unwrap();
+
+        // private inputs can't be compared so compare their JSON instead
+        let json = serde_json::to_value(&from_json).unwrap();
+        assert_eq!(json, serde_json::to_value(from_reader).unwrap());
+        assert_eq!(json, serde_json::to_value(from_path).unwrap());
+        let round_tripped = AirPrivateInput::from_json(&json.to_string()).unwrap();
+        assert_eq!(json, serde_json::to_value(round_tripped).unwrap());
+    }
+
+    #[test]
+    fn loading_input_from_path_reports_errors() {
+        let program_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../example/array-sum.json");
+
+        assert!(matches!(
+            AirPublicInput::<Fp>::from_path("missing.json"),
+            Err(LoadError::Io(_))
+        ));
+        assert!(matches!(
+            AirPrivateInput::from_path(program_path),
+            Err(LoadError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn active_builtins_skip_missing_segments() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let segments = air_public_input.memory_segments;
+
+        let active = segments.active_builtins().collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (SegmentKind::Pedersen, segments.pedersen.unwrap()),
+                (SegmentKind::RangeCheck, segments.range_check.unwrap()),
+                (SegmentKind::Bitwise, segments.bitwise.unwrap()),
+            ],
+            active
+        );
+        assert_eq!(None, segments.segment_for_builtin(SegmentKind::Ecdsa));
+        assert_eq!(None, segments.segment_for_builtin(SegmentKind::Output));
+    }
+
+    #[test]
+    fn execution_segment_min_size_rejects_ap_below_initial_ap() {
+        assert_eq!(
+            Some(20),
+            MemorySegments::execution_segment_min_size(MemoryAddress(100), MemoryAddress(120))
+        );
+        assert_eq!(
+            None,
+            MemorySegments::execution_segment_min_size(MemoryAddress(120), MemoryAddress(100))
+        );
+    }
+
+    #[test]
+    fn segments_must_be_adjacent_and_ordered() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+        assert_eq!(Ok(()), air_public_input.validate_segment_adjacency());
+        let segments = air_public_input.memory_segments;
+        // execution=[569, 1177) output=[1177, 1180) pedersen=[1180, 1186)
+        let output = segments.output.unwrap();
+
+        air_public_input.memory_segments = segments.with_output_segment(Segment {
+            begin_addr: output.begin_addr + 2,
+            stop_ptr: output.stop_ptr + 2,
+        });
+        assert_eq!(
+            Err(SegmentError::Gap {
+                after: SegmentKind::Execution,
+                before: SegmentKind::Output,
+                gap_size: 2,
+            }),
+            air_public_input.validate_segment_adjacency()
+        );
+
+        air_public_input.memory_segments = segments.with_output_segment(Segment {
+            begin_addr: output.begin_addr,
+            stop_ptr: output.stop_ptr + 1,
+        });
+        assert_eq!(
+            Err(SegmentError::Overlap {
+                a: SegmentKind::Output,
+                b: SegmentKind::Pedersen,
+            }),
+            air_public_input.validate_segment_adjacency()
+        );
+
+        air_public_input.memory_segments = segments.with_poseidon_segment(Segment {
+            begin_addr: MemoryAddress(1190),
+            stop_ptr: MemoryAddress(1190),
+        });
+        assert_eq!(
+            Err(SegmentError::WrongOrder {
+                expected_before: SegmentKind::EcOp,
+                expected_after: SegmentKind::Poseidon,
+            }),
+            air_public_input.validate_segment_adjacency()
+        );
+    }
+
+    #[test]
+    fn builtin_memory_ratio_is_at_most_one() {
+        let air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(STARKNET_AIR_PUBLIC_INPUT).unwrap();
+        assert_eq!(Layout::Starknet, air_public_input.layout);
+        let memory_segments = air_public_input.memory_segments;
+
+        assert!(memory_segments.builtin_memory_ratio(air_public_input.n_steps) <= 1.0);
+    }
+
+    #[test]
+    fn program_is_loaded_at_program_segment() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let program: CompiledProgram<Fp> = serde_json::from_str(PROGRAM).unwrap();
+        let memory = Memory::<Fp>::from_reader(MEMORY).unwrap();
+        let begin_addr = air_public_input.memory_segments.program.begin_addr;
+
+        assert_eq!(
+            Ok(()),
+            program.validate_program_segment(&memory, begin_addr)
+        );
+        assert!(matches!(
+            program.validate_program_segment(&memory, begin_addr + 1),
+            Err(SegmentValidationError::Mismatch { address, .. }) if address == begin_addr + 1
+        ));
+    }
+
+    #[test]
+    fn program_with_matching_prime_is_valid() {
+        let program: CompiledProgram<Fp> = serde_json::from_str(PROGRAM).unwrap();
+        assert_eq!(Ok(()), program.try_validate());
+
+        let program = ValidatedProgram::new(program).unwrap();
+        let memory = program.program_memory();
+
+        assert_eq!(program.data.len(), memory.len());
+        assert_eq!(MemoryAddress(1), memory[0].address);
+        assert_eq!(program.data[0], memory[0].value);
+    }
+
+    #[test]
+    fn program_with_mismatched_prime_is_invalid() {
+        let mut program: CompiledProgram<Fp> = serde_json::from_str(PROGRAM).unwrap();
+        program.prime = "0xffffffff00000001".to_string();
+
+        assert_eq!(
+            Err(PrimeMismatch {
+                expected: "0x800000000000011000000000000000000000000000000000000000000000001"
+                    .to_string(),
+                actual: "0xffffffff00000001".to_string(),
+            }),
+            program.try_validate()
+        );
+        assert!(ValidatedProgram::new(program).is_err());
+    }
+
+    #[test]
+    fn program_starting_with_call_to_main_is_detected() {
+        let program: CompiledProgram<Fp> = serde_json::from_str(PROGRAM).unwrap();
+        let first_instruction = program.first_instruction().unwrap();
+
+        // proof mode programs start with `ap += 0; call rel main`
+        assert_eq!(1, first_instruction.get_flag_group(FlagGroup::ApUpdate));
+        assert!(!first_instruction.get_flag(Flag::OpcodeCall));
+        assert!(program.is_call_to_main());
+    }
+
+    #[test]
+    fn program_without_call_to_main_is_rejected() {
+        let mut program: CompiledProgram<Fp> = serde_json::from_str(PROGRAM).unwrap();
+        let call_main = program.data[2];
+
+        // `jmp rel 0`
+        program.data = vec![Fp::from(0x10780017fff7fffu64), Fp::from(0u8)];
+        assert!(program.first_instruction().is_ok());
+        assert!(!program.is_call_to_main());
+
+        // a call that lands outside of the program
+        program.data = vec![call_main, Fp::from(0x1000u64)];
+        assert!(program.first_instruction().is_ok());
+        assert!(!program.is_call_to_main());
+
+        program.data = Vec::new();
+        assert!(program.first_instruction().is_err());
+        assert!(!program.is_call_to_main());
+    }
+
+    #[test]
+    fn private_input_matching_public_input_is_valid() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let air_private_input: AirPrivateInput = serde_json::from_str(AIR_PRIVATE_INPUT).unwrap();
+        let register_states = RegisterStates::from_reader(TRACE).unwrap();
+        assert!(air_private_input
+            .validate_against_public(&air_public_input, &register_states)
+            .is_ok());
+
+        air_public_input.n_steps *= 2;
+
+        assert_eq!(
+            Err(ValidationError::StepCountMismatch {
+                expected: 32768,
+                actual: 16384
+            }),
+            air_private_input.validate_against_public(&air_public_input, &register_states)
+        );
+    }
+
+    #[test]
+    fn private_input_with_invalid_range_check_instances_is_invalid() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let mut air_private_input: AirPrivateInput =
+            serde_json::from_str(AIR_PRIVATE_INPUT).unwrap();
+        // rc_min = 0x7ffc, rc_max = 0x8002
+        let value = uint!(0x7ffd_7ffe_7fff_8000_8001_8002_7ffc_7ffd_U256);
+        air_private_input.range_check = vec![RangeCheckInstance { index: 0, value }];
+
+        assert!(matches!(
+            air_private_input.validate_builtins_against_public(&air_public_input),
+            Err(ValidationError::InstanceCountMismatch {
+                segment: SegmentKind::RangeCheck,
+                segment_size: 0,
+                cells_per_instance: 1,
+                instances: 1
+            })
+        ));
+
+        let segments = &mut air_public_input.memory_segments;
+        let range_check_segment = segments.range_check.as_mut().unwrap();
+        range_check_segment.stop_ptr = range_check_segment.begin_addr + 1;
+        assert!(air_private_input
+            .validate_builtins_against_public(&air_public_input)
+            .is_ok());
+
+        air_private_input.range_check[0].value = value | uint!(0xffff_U256);
+
+        assert!(matches!(
+            air_private_input.validate_builtins_against_public(&air_public_input),
+            Err(ValidationError::RangeCheckOutOfBounds { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn private_input_keccak_instances_must_fill_keccak_segment() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let mut air_private_input: AirPrivateInput =
+            serde_json::from_str(AIR_PRIVATE_INPUT).unwrap();
+        air_private_input.keccak = vec![KeccakInstance::new_empty(0)];
+
+        assert_eq!(
+            Err(ValidationError::InstanceCountMismatch {
+                segment: SegmentKind::Keccak,
+                segment_size: 0,
+                cells_per_instance: 16,
+                instances: 1
+            }),
+            air_private_input.validate_builtins_against_public(&air_public_input)
+        );
+
+        let begin_addr = MemoryAddress(1000);
+        let segments = &mut air_public_input.memory_segments;
+        *segments = segments.with_keccak_segment(Segment {
+            begin_addr,
+            stop_ptr: begin_addr + 16,
+        });
+        assert_eq!(
+            Ok(()),
+            air_private_input.validate_builtins_against_public(&air_public_input)
+        );
+        assert_eq!(1, air_public_input.builtin_instance_counts().keccak);
+    }
+
+    #[test]
+    fn private_input_with_non_contiguous_instances_is_invalid() {
+        let mut air_private_input: AirPrivateInput =
+            serde_json::from_str(AIR_PRIVATE_INPUT).unwrap();
+        air_private_input.pedersen = (0..3).map(PedersenInstance::new_empty).collect();
+        assert_eq!(Ok(()), air_private_input.check_builtin_continuity());
+
+        air_private_input.pedersen[2].index = 3;
+        assert_eq!(
+            Err(ContinuityError {
+                builtin: SegmentKind::Pedersen,
+                expected_index: 2,
+                actual_index: 3,
+            }),
+            air_private_input.check_builtin_continuity()
+        );
+
+        air_private_input.pedersen[2].index = 1;
+
+        assert_eq!(
+            Err(ContinuityError {
+                builtin: SegmentKind::Pedersen,
+                expected_index: 2,
+                actual_index: 1,
+            }),
+            air_private_input.check_builtin_continuity()
+        );
+    }
+
+    #[test]
+    fn private_input_pads_to_layout_ratios() {
+        let mut air_private_input: AirPrivateInput =
+            serde_json::from_str(AIR_PRIVATE_INPUT).unwrap();
+        air_private_input.bitwise = vec![BitwiseInstance {
+            index: 0,
+            x: U256::from(3),
+            y: U256::from(5),
+        }];
+
+        // recursive layout has a pedersen ratio of 128, range check ratio of 8
+        // and bitwise ratio of 8
+        assert_eq!(
+            Ok(()),
+            air_private_input.pad_to_power_of_two(Layout::Recursive, 10)
+        );
+
+        assert_eq!(8, air_private_input.pedersen.len());
+        assert_eq!(128, air_private_input.bitwise.len());
+        assert_eq!(U256::from(3), air_private_input.bitwise[0].x);
+        assert_eq!(
+            BitwiseInstance::new_empty(127),
+            air_private_input.bitwise[127]
+        );
+        assert_eq!(128, air_private_input.range_check.len());
+        assert_eq!(U256::ZERO, air_private_input.range_check[127].value);
+        assert!(air_private_input.poseidon.is_empty());
+
+        // a trace of 2^9 steps only fits 4 pedersen instances
+        assert_eq!(
+            Err(PaddingError::TooManyInstances {
+                builtin: SegmentKind::Pedersen,
+                instances: 8,
+                max_instances: 4,
+            }),
+            air_private_input.pad_to_power_of_two(Layout::Recursive, 9)
+        );
+        assert_eq!(
+            Err(PaddingError::TooManySteps { log_n_steps: 64 }),
+            air_private_input.pad_to_power_of_two(Layout::Recursive, 64)
+        );
+    }
 }
@@ -0,0 +1,133 @@
+//! Hashing of public memory as done by SHARP (StarkWare's Shared Prover) and
+//! its on-chain verifier.
+//!
+//! The on-chain verifier doesn't receive the public memory directly. Instead
+//! each page of public memory is registered with the memory page fact
+//! registry which stores the Keccak-256 hash of the page. A page is hashed as
+//! a flat list of field elements that alternate between addresses and values:
+//!
+//! ```text
+//! [address_0, value_0, address_1, value_1, ...]
+//! ```
+//!
+//! Each element is encoded as its canonical (non-Montgomery) value in 32
+//! big-endian bytes i.e. as a Solidity `uint256` and the hash is taken over
+//! the concatenation of these encodings. The verifier then checks the page
+//! hashes in the proof's public input against the registered facts.
+//!
+//! [encode_public_memory_page] and [decode_public_memory_page] use a private
+//! encoding to store a page with its hash: `[n, hash, address_0, value_0,
+//! ..., address_{n-1}, value_{n-1}]`. This isn't SHARP's format. The
+//! verifier's public input only holds each page's first address, size and
+//! hash, and the fact registry receives the page contents separately.
+
+use crate::errors::DecodeError;
+use crate::errors::InvalidFieldElementError;
+use crate::types::MemoryAddress;
+use crate::MemoryEntry;
+use alloc::vec::Vec;
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use ruint::aliases::U256;
+use sha3::digest::consts::U32;
+use sha3::Digest;
+
+/// Hashes memory cells as `[address_0, value_0, address_1, value_1, ...]` the
+/// same way as the on-chain verifier hashes a page of public memory.
+///
+/// `D` is the hash function used by the verifier which is Keccak-256 for
+/// SHARP. Addresses and values must already be in the order the page was
+/// registered in, which for the main page is sorted by address.
+pub fn hash_elements<D: Digest<OutputSize = U32>, F: PrimeField>(
+    elements: impl IntoIterator<Item = F>,
+) -> [u8; 32] {
+    let mut hasher = D::new();
+    for element in elements {
+        hasher.update(felt_to_u256(element).to_be_bytes::<32>());
+    }
+    hasher.finalize().into()
+}
+
+/// Flattens a page of memory entries into the elements that are hashed:
+/// `[address_0, value_0, address_1, value_1, ...]`
+pub fn page_elements<F: PrimeField>(entries: &[MemoryEntry<F>]) -> impl Iterator<Item = F> + '_ {
+    entries
+        .iter()
+        .flat_map(|entry| [entry.address_as_felt(), entry.value])
+}
+
+/// Hashes a page of memory entries with [hash_elements] of its
+/// [page_elements]
+pub fn hash_memory_page<D: Digest<OutputSize = U32>, F: PrimeField>(
+    entries: &[MemoryEntry<F>],
+) -> [u8; 32] {
+    hash_elements::<D, F>(page_elements(entries))
+}
+
+/// Encodes a page of public memory as `[size, hash, address_0, value_0, ...]`
+/// where `hash` is the [hash_memory_page] of the entries. This is a private
+/// encoding for storing pages, not the format SHARP uses.
+pub fn encode_public_memory_page<D: Digest<OutputSize = U32>, F: PrimeField>(
+    entries: &[MemoryEntry<F>],
+) -> Vec<U256> {
+    let hash = hash_memory_page::<D, F>(entries);
+    let mut page_data = vec![U256::from(entries.len()), U256::from_be_bytes(hash)];
+    for entry in entries {
+        page_data.push(U256::from(entry.address.0));
+        page_data.push(felt_to_u256(entry.value));
+    }
+    page_data
+}
+
+/// Inverse of [encode_public_memory_page]. Errors if the page is malformed
+/// or if the entries don't hash to the page's hash.
+pub fn decode_public_memory_page<D: Digest<OutputSize = U32>, F: PrimeField>(
+    page_data: &[U256],
+) -> Result<Vec<MemoryEntry<F>>, DecodeError> {
+    let [size, claimed_hash, pairs @ ..] = page_data else {
+        return Err(DecodeError::TooShort {
+            len: page_data.len(),
+        });
+    };
+    if pairs.len() % 2 != 0 || U256::from(pairs.len() / 2) != *size {
+        return Err(DecodeError::SizeMismatch {
+            size: *size,
+            pairs: pairs.len() / 2,
+        });
+    }
+
+    let entries = pairs
+        .chunks_exact(2)
+        .map(|pair| {
+            let address = u32::try_from(pair[0])
+                .ok()
+                .filter(|&address| address != 0)
+                .ok_or(DecodeError::InvalidAddress { address: pair[0] })?;
+            Ok(MemoryEntry {
+                address: MemoryAddress(address),
+                value: felt_from_u256(pair[1])?,
+            })
+        })
+        .collect::<Result<Vec<MemoryEntry<F>>, DecodeError>>()?;
+
+    let computed = hash_memory_page::<D, F>(&entries);
+    let claimed = claimed_hash.to_be_bytes::<32>();
+    if computed != claimed {
+        return Err(DecodeError::HashMismatch { computed, claimed });
+    }
+    Ok(entries)
+}
+
+fn felt_to_u256<F: PrimeField>(felt: F) -> U256 {
+    let value: BigUint = felt.into();
+    U256::from(value)
+}
+
+fn felt_from_u256<F: PrimeField>(value: U256) -> Result<F, InvalidFieldElementError> {
+    let modulus = U256::from::<BigUint>(F::MODULUS.into());
+    if value < modulus {
+        Ok(From::<BigUint>::from(value.into()))
+    } else {
+        Err(InvalidFieldElementError { value, modulus })
+    }
+}
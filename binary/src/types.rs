@@ -0,0 +1,106 @@
+use ark_ff::Field;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::fmt::Display;
+use std::fmt::LowerHex;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Sub;
+
+/// Address of a memory cell. Kept distinct from `u32` so addresses can't be
+/// confused with values or offsets.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    CanonicalSerialize,
+    CanonicalDeserialize,
+)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct MemoryAddress(pub u32);
+
+impl MemoryAddress {
+    /// Address as a field element e.g. for the memory argument
+    pub fn into_felt<F: Field>(self) -> F {
+        F::from(self.0)
+    }
+}
+
+impl From<u32> for MemoryAddress {
+    fn from(address: u32) -> Self {
+        Self(address)
+    }
+}
+
+impl From<MemoryAddress> for u32 {
+    fn from(address: MemoryAddress) -> Self {
+        address.0
+    }
+}
+
+impl From<MemoryAddress> for u64 {
+    fn from(address: MemoryAddress) -> Self {
+        address.0.into()
+    }
+}
+
+impl From<MemoryAddress> for usize {
+    fn from(address: MemoryAddress) -> Self {
+        address.0 as usize
+    }
+}
+
+impl Display for MemoryAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl LowerHex for MemoryAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        LowerHex::fmt(&self.0, f)
+    }
+}
+
+/// Address `offset` cells after this address
+impl Add<u32> for MemoryAddress {
+    type Output = Self;
+
+    fn add(self, offset: u32) -> Self {
+        Self(self.0 + offset)
+    }
+}
+
+impl AddAssign<u32> for MemoryAddress {
+    fn add_assign(&mut self, offset: u32) {
+        self.0 += offset;
+    }
+}
+
+/// Address `offset` cells before this address
+impl Sub<u32> for MemoryAddress {
+    type Output = Self;
+
+    fn sub(self, offset: u32) -> Self {
+        Self(self.0 - offset)
+    }
+}
+
+/// Number of cells between two addresses
+impl Sub for MemoryAddress {
+    type Output = u32;
+
+    fn sub(self, rhs: Self) -> u32 {
+        self.0 - rhs.0
+    }
+}
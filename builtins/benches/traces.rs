@@ -0,0 +1,23 @@
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use sandstorm_builtins::ecdsa;
+use sandstorm_builtins::ecdsa::InstanceTrace;
+
+const NUM_ECDSA_INSTANCES: u32 = 1000;
+
+fn ecdsa_trace_benches(c: &mut Criterion) {
+    let instances = (0..NUM_ECDSA_INSTANCES)
+        .map(|index| InstanceTrace::new_dummy(index).instance)
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("ecdsa_traces");
+    group.sample_size(10);
+    group.bench_function("generate_1000", |b| {
+        b.iter(|| ecdsa::generate_ecdsa_traces(&instances))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ecdsa_trace_benches);
+criterion_main!(benches);
@@ -1,3 +1,4 @@
+use std::array;
 use std::ops::Deref;
 
 use binary::BitwiseInstance;
@@ -22,9 +23,7 @@ pub struct InstanceTrace<const SPACING: usize> {
 impl<const SPACING: usize> InstanceTrace<SPACING> {
     pub fn new(instance: BitwiseInstance) -> Self {
         let BitwiseInstance { x, y, .. } = instance;
-        let x_and_y = x & y;
-        let x_xor_y = x ^ y;
-        let x_or_y = x | y;
+        let (x_and_y, x_xor_y, x_or_y) = instance.outputs();
 
         let x_partition = Partition256::new(x);
         let y_partition = Partition256::new(y);
@@ -52,6 +51,48 @@ impl<const SPACING: usize> InstanceTrace<SPACING> {
     }
 }
 
+/// Trace of a bitwise instance with its inputs decomposed into bits, least
+/// significant bit first
+#[derive(Clone, Debug)]
+pub struct BitwiseTrace {
+    pub instance: BitwiseInstance,
+    pub x_bits: [bool; 256],
+    pub y_bits: [bool; 256],
+}
+
+impl BitwiseTrace {
+    pub fn new(instance: BitwiseInstance) -> Self {
+        let BitwiseInstance { x, y, .. } = instance;
+        Self {
+            instance,
+            x_bits: array::from_fn(|i| x.bit(i)),
+            y_bits: array::from_fn(|i| y.bit(i)),
+        }
+    }
+
+    /// Checks the bits decompose the inputs and that the instance's `x & y`,
+    /// `x ^ y` and `x | y` match the outputs computed bit by bit
+    pub fn verify(&self) -> bool {
+        let BitwiseInstance { x, y, .. } = self.instance;
+        let bit_pairs = || self.x_bits.iter().zip(&self.y_bits);
+        let x_and_y = compose_bits(bit_pairs().map(|(&x, &y)| x & y));
+        let x_xor_y = compose_bits(bit_pairs().map(|(&x, &y)| x ^ y));
+        let x_or_y = compose_bits(bit_pairs().map(|(&x, &y)| x | y));
+        compose_bits(self.x_bits) == x
+            && compose_bits(self.y_bits) == y
+            && self.instance.outputs() == (x_and_y, x_xor_y, x_or_y)
+    }
+}
+
+/// Composes bits, least significant first, into an integer
+fn compose_bits(bits: impl IntoIterator<Item = bool>) -> U256 {
+    let mut res = U256::ZERO;
+    for (i, bit) in bits.into_iter().enumerate() {
+        res.set_bit(i, bit);
+    }
+    res
+}
+
 /// Partitions of a 64 bit integer
 /// For example to break up the 64 bit binary integer `v` with spacing 4:
 /// ```text
@@ -135,6 +176,8 @@ pub fn dilute<const SPACING: usize>(v: U256) -> U256 {
 #[cfg(test)]
 mod tests {
     use crate::bitwise::dilute;
+    use crate::bitwise::BitwiseTrace;
+    use binary::BitwiseInstance;
     use ruint::aliases::U256;
 
     #[test]
@@ -143,4 +186,42 @@ mod tests {
 
         assert_eq!(U256::from(0b0001_0000_0001u32), dilute::<4>(input))
     }
+
+    #[test]
+    fn bitwise_trace_verifies_minimal_and_maximal_values() {
+        let instances = [
+            BitwiseInstance::new_empty(0),
+            BitwiseInstance {
+                index: 1,
+                x: U256::MAX,
+                y: U256::MAX,
+            },
+            BitwiseInstance {
+                index: 2,
+                x: U256::ZERO,
+                y: U256::MAX,
+            },
+        ];
+
+        for instance in instances {
+            let trace = BitwiseTrace::new(instance);
+
+            assert!(trace.verify());
+            assert_eq!(instance.x == U256::MAX, trace.x_bits.iter().all(|&b| b));
+            assert_eq!(instance.y == U256::MAX, trace.y_bits.iter().all(|&b| b));
+        }
+    }
+
+    #[test]
+    fn bitwise_trace_rejects_tampered_bits() {
+        let instance = BitwiseInstance {
+            index: 0,
+            x: U256::from(0b1100u32),
+            y: U256::from(0b1010u32),
+        };
+        let mut trace = BitwiseTrace::new(instance);
+        trace.x_bits[255] = true;
+
+        assert!(!trace.verify());
+    }
 }
@@ -11,11 +11,16 @@ use ark_ec::short_weierstrass::Projective;
 use ark_ec::CurveGroup;
 use ark_ec::Group;
 use binary::EcOpInstance;
+use binary::Memory;
+use binary::Segment;
 use ark_ff::Field;
+use ark_ff::PrimeField;
 use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
 use num_bigint::BigUint;
 use ruint::aliases::U256;
 use ruint::uint;
+use std::error::Error;
+use std::fmt::Display;
 
 /// An ECDSA trace for a dummy instance
 /// Created once since creating new instance traces each time is expensive.
@@ -85,6 +90,133 @@ impl InstanceTrace {
     }
 }
 
+/// Reasons an EC op instance can't be expressed by the AIR
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcOpError {
+    /// `p` or `q` isn't a point on the curve
+    PointNotOnCurve,
+    /// `m` isn't a field element
+    ScalarOutOfRange,
+    /// The partial sum and the doubled point share an x-coordinate
+    PointCollision,
+}
+
+impl Display for EcOpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PointNotOnCurve => write!(f, "point is not on the curve"),
+            Self::ScalarOutOfRange => write!(f, "scalar is not a field element"),
+            Self::PointCollision => {
+                write!(f, "partial sum and point have the same x-coordinate")
+            }
+        }
+    }
+}
+
+impl Error for EcOpError {}
+
+/// Trace of the 256 multiply-add steps computing `p + m * q`. The AIR uses `p`
+/// as the shift point so the final partial sum is the instance's result.
+#[derive(Clone, Debug)]
+pub struct EcOpTrace {
+    pub instance: EcOpInstance,
+    pub steps: Box<[EcMadPartialStep; 256]>,
+}
+
+impl EcOpTrace {
+    pub fn new(instance: EcOpInstance) -> Result<Self, EcOpError> {
+        let p =
+            try_point_from_u256(instance.p_x, instance.p_y).ok_or(EcOpError::PointNotOnCurve)?;
+        let q =
+            try_point_from_u256(instance.q_x, instance.q_y).ok_or(EcOpError::PointNotOnCurve)?;
+        let m = try_felt_from_u256(instance.m).ok_or(EcOpError::ScalarOutOfRange)?;
+        // check the AIR's computation succeeds before generating the steps
+        let r = mimic_ec_mad_air(m, q.into(), p.into()).ok_or(EcOpError::PointCollision)?;
+        let steps: Box<[EcMadPartialStep; 256]> = gen_ec_mad_steps(m, q.into(), p.into())
+            .into_boxed_slice()
+            .try_into()
+            .unwrap();
+        assert_eq!(r.into_affine(), steps[255].partial_sum);
+        Ok(Self { instance, steps })
+    }
+
+    /// Returns `r = p + m * q` i.e. the final partial sum
+    pub fn result(&self) -> Affine<StarkwareCurve> {
+        self.steps[255].partial_sum
+    }
+
+    /// Checks the trace's result matches the instance's result
+    pub fn verify(&self) -> bool {
+        let r = self.result();
+        let r_coords = (
+            U256::from(BigUint::from(r.x)),
+            U256::from(BigUint::from(r.y)),
+        );
+        !r.infinity && self.instance.result() == Some(r_coords)
+    }
+}
+
+/// Curve arithmetic for [EcOpInstance]. The instance is defined in the binary
+/// crate which doesn't implement the curve.
+pub trait EcOpInstanceExt {
+    /// Computes `r = p + m * q` the same way as the AIR. Returns `(r_x, r_y)`
+    /// or `None` if `p` or `q` aren't points on the curve, `m` isn't a field
+    /// element or the AIR's computation fails.
+    fn result(&self) -> Option<(U256, U256)>;
+}
+
+impl EcOpInstanceExt for EcOpInstance {
+    fn result(&self) -> Option<(U256, U256)> {
+        let p = try_point_from_u256(self.p_x, self.p_y)?;
+        let q = try_point_from_u256(self.q_x, self.q_y)?;
+        let m = try_felt_from_u256(self.m)?;
+        let r = mimic_ec_mad_air(m, q.into(), p.into())?.into_affine();
+        if r.infinity {
+            return None;
+        }
+        Some((
+            U256::from(BigUint::from(r.x)),
+            U256::from(BigUint::from(r.y)),
+        ))
+    }
+}
+
+/// Checks the cells of the instance in the EC op memory segment hold the
+/// instance's inputs and result
+pub fn verify_in_memory<F: PrimeField>(
+    instance: &EcOpInstance,
+    mem: &Memory<F>,
+    segment: Segment,
+) -> bool {
+    let Some((r_x, r_y)) = instance.result() else {
+        return false;
+    };
+    let addrs = instance.mem_addr(segment.begin_addr);
+    if addrs.r_y >= segment.stop_ptr {
+        return false;
+    }
+    [
+        (addrs.p_x, instance.p_x),
+        (addrs.p_y, instance.p_y),
+        (addrs.q_x, instance.q_x),
+        (addrs.q_y, instance.q_y),
+        (addrs.m, instance.m),
+        (addrs.r_x, r_x),
+        (addrs.r_y, r_y),
+    ]
+    .into_iter()
+    .all(|(address, value)| mem.get_checked(address.into()).map(|word| word.0) == Some(value))
+}
+
+fn try_felt_from_u256(value: U256) -> Option<Fp> {
+    (value < U256::from::<BigUint>(Fp::MODULUS.into())).then(|| Fp::from(BigUint::from(value)))
+}
+
+fn try_point_from_u256(x: U256, y: U256) -> Option<Affine<StarkwareCurve>> {
+    let point = Affine::new_unchecked(try_felt_from_u256(x)?, try_felt_from_u256(y)?);
+    point.is_on_curve().then_some(point)
+}
+
 /// Generates a dummy EC op instance using `private_key = 1`
 fn gen_dummy_instance(index: u32) -> EcOpInstance {
     let p = crate::pedersen::constants::P0;
@@ -159,3 +291,131 @@ pub(crate) fn mimic_ec_mad_air(
     }
     Some(partial_sum)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::verify_in_memory;
+    use super::EcOpError;
+    use super::EcOpInstanceExt;
+    use super::EcOpTrace;
+    use crate::pedersen::constants::P0;
+    use crate::utils::curve::Fr;
+    use crate::utils::curve::StarkwareCurve;
+    use ark_ec::short_weierstrass::Affine;
+    use ark_ec::short_weierstrass::SWCurveConfig;
+    use ark_ec::CurveGroup;
+    use binary::types::MemoryAddress;
+    use binary::EcOpInstance;
+    use binary::Memory;
+    use binary::Segment;
+    use binary::Word;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+    use num_bigint::BigUint;
+    use ruint::aliases::U256;
+    use ruint::uint;
+
+    fn felt_to_u256(value: Fp) -> U256 {
+        U256::from(BigUint::from(value))
+    }
+
+    fn instance(p: Affine<StarkwareCurve>, q: Affine<StarkwareCurve>, m: U256) -> EcOpInstance {
+        EcOpInstance {
+            index: 0,
+            p_x: felt_to_u256(p.x),
+            p_y: felt_to_u256(p.y),
+            q_x: felt_to_u256(q.x),
+            q_y: felt_to_u256(q.y),
+            m,
+        }
+    }
+
+    #[test]
+    fn result_matches_scalar_multiplication() {
+        let q = StarkwareCurve::GENERATOR;
+        let m = uint!(0xdeadbeef_U256);
+
+        let expected = (P0 + q * Fr::from(0xdeadbeefu32)).into_affine();
+
+        assert_eq!(
+            Some((felt_to_u256(expected.x), felt_to_u256(expected.y))),
+            instance(P0, q, m).result()
+        );
+    }
+
+    #[test]
+    fn result_rejects_invalid_inputs() {
+        let q = StarkwareCurve::GENERATOR;
+        let modulus = uint!(0x800000000000011000000000000000000000000000000000000000000000001_U256);
+        let mut off_curve = instance(P0, q, uint!(1_U256));
+        off_curve.q_y += uint!(1_U256);
+
+        assert_eq!(None, instance(P0, q, modulus).result());
+        assert_eq!(None, off_curve.result());
+        // partial sum and doubled point share an x-coordinate
+        assert_eq!(None, instance(q, q, uint!(1_U256)).result());
+    }
+
+    #[test]
+    fn instance_is_verified_against_memory() {
+        let instance = instance(P0, StarkwareCurve::GENERATOR, uint!(5_U256));
+        let (r_x, r_y) = instance.result().unwrap();
+        let segment = Segment::from_instances(MemoryAddress(10), 1, 7);
+        let addrs = instance.mem_addr(segment.begin_addr);
+        let word = |v: U256| Word::<Fp>::new(v);
+        let mut memory = [
+            (addrs.p_x, instance.p_x),
+            (addrs.p_y, instance.p_y),
+            (addrs.q_x, instance.q_x),
+            (addrs.q_y, instance.q_y),
+            (addrs.m, instance.m),
+            (addrs.r_x, r_x),
+            (addrs.r_y, r_y),
+        ]
+        .into_iter()
+        .map(|(address, value)| (address.into(), word(value)))
+        .collect::<Memory<Fp>>();
+        assert!(verify_in_memory(&instance, &memory, segment));
+
+        memory.extend([(addrs.r_x.into(), word(r_x + uint!(1_U256)))]);
+
+        assert!(!verify_in_memory(&instance, &memory, segment));
+    }
+
+    #[test]
+    fn ec_op_trace_matches_instance_result() {
+        let q = StarkwareCurve::GENERATOR;
+        let m = uint!(0xdeadbeef_U256);
+        let instance = instance(P0, q, m);
+
+        let trace = EcOpTrace::new(instance).unwrap();
+
+        let expected = (P0 + q * Fr::from(0xdeadbeefu32)).into_affine();
+        assert_eq!(256, trace.steps.len());
+        assert_eq!(expected, trace.result());
+        assert!(trace.verify());
+    }
+
+    #[test]
+    fn ec_op_trace_rejects_invalid_instances() {
+        let q = StarkwareCurve::GENERATOR;
+        let modulus = uint!(0x800000000000011000000000000000000000000000000000000000000000001_U256);
+        let mut off_curve = instance(P0, q, uint!(1_U256));
+        off_curve.q_y += uint!(1_U256);
+        let mut tampered = EcOpTrace::new(instance(P0, q, uint!(5_U256))).unwrap();
+        tampered.instance.m = uint!(6_U256);
+
+        assert_eq!(
+            EcOpError::PointNotOnCurve,
+            EcOpTrace::new(off_curve).unwrap_err()
+        );
+        assert_eq!(
+            EcOpError::ScalarOutOfRange,
+            EcOpTrace::new(instance(P0, q, modulus)).unwrap_err()
+        );
+        assert_eq!(
+            EcOpError::PointCollision,
+            EcOpTrace::new(instance(q, q, uint!(1_U256))).unwrap_err()
+        );
+        assert!(!tampered.verify());
+    }
+}
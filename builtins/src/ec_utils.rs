@@ -0,0 +1,137 @@
+use crate::utils::curve::StarkwareCurve;
+use ark_ec::short_weierstrass::Affine;
+use ark_ec::short_weierstrass::Projective;
+use ark_ec::Group;
+use ark_ff::PrimeField;
+use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+use num_bigint::BigUint;
+use ruint::aliases::U256;
+use ruint::uint;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Reasons the AIR can't compute an EC multiply-add
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcMultError {
+    ScalarZero,
+    /// The scalar must be less than `2^251`
+    ScalarTooLarge {
+        bits: u32,
+    },
+    /// The partial sum and the doubled point share an x-coordinate. The AIR
+    /// can't add or double points with the same x-coordinate.
+    PointCollision,
+}
+
+impl Display for EcMultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ScalarZero => write!(f, "scalar must be non-zero"),
+            Self::ScalarTooLarge { bits } => {
+                write!(f, "scalar has {bits} bits but must be less than 2^251")
+            }
+            Self::PointCollision => {
+                write!(f, "partial sum and point have the same x-coordinate")
+            }
+        }
+    }
+}
+
+impl Error for EcMultError {}
+
+/// Computes `m * point + shift_point` using the same steps as the AIR.
+///
+/// The AIR processes the bits of `m` from least to most significant. For each
+/// bit it adds `point` to the partial sum (starting at `shift_point`) if the
+/// bit is set and then doubles `point`. The slope constraints require the
+/// partial sum and `point` to have different x-coordinates at every step. As a
+/// result the AIR fails for inputs where regular scalar multiplication
+/// succeeds e.g. when an intermediate sum equals `±point`. Computing the result
+/// this way ensures a computation errors here if and only if it errors in the
+/// AIR.
+pub fn mimic_ec_mad_air(
+    m: BigUint,
+    mut point: Projective<StarkwareCurve>,
+    shift_point: Projective<StarkwareCurve>,
+) -> Result<Projective<StarkwareCurve>, EcMultError> {
+    let bits = m.bits() as u32;
+    if bits == 0 {
+        return Err(EcMultError::ScalarZero);
+    }
+    if bits >= Fp::MODULUS_BIT_SIZE {
+        return Err(EcMultError::ScalarTooLarge { bits });
+    }
+    let mut m = U256::from(m);
+    let mut partial_sum = shift_point;
+    while m != U256::ZERO {
+        if Affine::from(partial_sum).x == Affine::from(point).x {
+            return Err(EcMultError::PointCollision);
+        }
+        let bit = m & uint!(1_U256);
+        if bit == uint!(1_U256) {
+            partial_sum += point;
+        }
+        point.double_in_place();
+        m >>= 1;
+    }
+    Ok(partial_sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mimic_ec_mad_air;
+    use super::EcMultError;
+    use crate::ecdsa::SHIFT_POINT;
+    use crate::utils::curve::Fr;
+    use crate::utils::curve::StarkwareCurve;
+    use ark_ec::short_weierstrass::Projective;
+    use ark_ec::short_weierstrass::SWCurveConfig;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn mimic_ec_mad_air_matches_scalar_multiplication() {
+        let point = Projective::from(StarkwareCurve::GENERATOR);
+        let shift_point = Projective::from(SHIFT_POINT);
+
+        for m in [1u64, 2, 3, 0xdeadbeef, u64::MAX] {
+            let res = mimic_ec_mad_air(BigUint::from(m), point, shift_point).unwrap();
+
+            assert_eq!(point * Fr::from(m) + shift_point, res);
+        }
+
+        let m: BigUint = (BigUint::from(1u32) << 251) - 1u32;
+        let res = mimic_ec_mad_air(m.clone(), point, shift_point).unwrap();
+        assert_eq!(point * Fr::from(m) + shift_point, res);
+    }
+
+    #[test]
+    fn mimic_ec_mad_air_rejects_zero_scalar() {
+        let point = Projective::from(StarkwareCurve::GENERATOR);
+        let shift_point = Projective::from(SHIFT_POINT);
+
+        let res = mimic_ec_mad_air(BigUint::from(0u32), point, shift_point);
+
+        assert_eq!(Err(EcMultError::ScalarZero), res);
+    }
+
+    #[test]
+    fn mimic_ec_mad_air_rejects_large_scalar() {
+        let point = Projective::from(StarkwareCurve::GENERATOR);
+        let shift_point = Projective::from(SHIFT_POINT);
+
+        let res = mimic_ec_mad_air(BigUint::from(1u32) << 251, point, shift_point);
+
+        assert_eq!(Err(EcMultError::ScalarTooLarge { bits: 252 }), res);
+    }
+
+    #[test]
+    fn mimic_ec_mad_air_rejects_point_collision() {
+        let shift_point = Projective::from(SHIFT_POINT);
+
+        let same_res = mimic_ec_mad_air(BigUint::from(1u32), shift_point, shift_point);
+        let negated_res = mimic_ec_mad_air(BigUint::from(3u32), -shift_point, shift_point);
+
+        assert_eq!(Err(EcMultError::PointCollision), same_res);
+        assert_eq!(Err(EcMultError::PointCollision), negated_res);
+    }
+}
@@ -3,20 +3,28 @@ use ark_ec::CurveGroup;
 use ark_ec::Group;
 use ark_ec::short_weierstrass::Projective;
 use ark_ec::short_weierstrass::SWCurveConfig;
+use ark_ff::One;
 use ark_ff::Zero;
 use binary::EcdsaInstance;
 use binary::Signature;
+use binary::errors::ValidationError;
 use num_bigint::BigUint;
 use ruint::aliases::U256;
 use ruint::uint;
 use ark_ff::Field;
+use ark_ff::PrimeField;
+use crate::ec_utils::mimic_ec_mad_air;
 use crate::pedersen::pedersen_hash;
+use crate::traces::TraceError;
 use crate::utils::curve::Fr;
 use crate::utils::curve::StarkwareCurve;
 use crate::utils::curve::calculate_slope;
 use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
 use ark_ec::short_weierstrass::Affine;
-use ark_ff::PrimeField;
+use std::error::Error;
+use std::fmt::Display;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 pub mod periodic;
 
@@ -36,12 +44,228 @@ pub struct EcMadPartialStep {
     pub x_diff_inv: Fp,
 }
 
+/// Reasons an EC multiply-add can't be expressed by the AIR
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcMadError {
+    ScalarZero,
+    /// The scalar must be less than `2^251`
+    ScalarTooLarge {
+        bits: u64,
+    },
+    /// The partial sum and the fixed point share an x-coordinate
+    PointCollision {
+        step: usize,
+    },
+}
+
+impl Display for EcMadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ScalarZero => write!(f, "scalar must be non-zero"),
+            Self::ScalarTooLarge { bits } => {
+                write!(f, "scalar has {bits} bits but must be less than 2^251")
+            }
+            Self::PointCollision { step } => {
+                write!(
+                    f,
+                    "partial sum and point have the same x-coordinate at step {step}"
+                )
+            }
+        }
+    }
+}
+
+impl Error for EcMadError {}
+
+/// Constraint of an EC multiply-add step that doesn't hold
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcMadVerifyError {
+    /// `suffix_i - 2 * suffix_{i+1}` must be a bit and the final suffix zero
+    InvalidSuffix {
+        step: usize,
+    },
+    InvalidSlope {
+        step: usize,
+    },
+    InvalidXDiffInv {
+        step: usize,
+    },
+    /// The next partial sum doesn't match the slope equations
+    InvalidPartialSum {
+        step: usize,
+    },
+    /// The fixed point must be doubled (or kept) each step
+    InvalidFixedPoint {
+        step: usize,
+    },
+}
+
+impl Display for EcMadVerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (name, step) = match self {
+            Self::InvalidSuffix { step } => ("suffix", step),
+            Self::InvalidSlope { step } => ("slope", step),
+            Self::InvalidXDiffInv { step } => ("x_diff_inv", step),
+            Self::InvalidPartialSum { step } => ("partial sum", step),
+            Self::InvalidFixedPoint { step } => ("fixed point", step),
+        };
+        write!(f, "invalid {name} at step {step}")
+    }
+}
+
+impl Error for EcMadVerifyError {}
+
+/// Partial steps of an EC multiply-add `m * P + shift_point`.
+/// Used for inspecting the intermediate values in the execution trace.
+#[derive(Clone, Debug)]
+pub struct EcMadTrace(pub Vec<EcMadPartialStep>);
+
+impl EcMadTrace {
+    pub fn new(
+        scalar: BigUint,
+        point: Projective<StarkwareCurve>,
+        shift_point: Projective<StarkwareCurve>,
+    ) -> Result<Self, EcMadError> {
+        let bits = scalar.bits();
+        let scalar = U256::try_from(scalar).map_err(|_| EcMadError::ScalarTooLarge { bits })?;
+        let steps = gen_ec_mad_steps_fixed::<255>(scalar, point, shift_point)?;
+        Ok(Self(steps.to_vec()))
+    }
+
+    /// Returns `m * P + shift_point`
+    pub fn final_result(&self) -> Affine<StarkwareCurve> {
+        self.0.last().unwrap().partial_sum
+    }
+
+    /// Checks each step satisfies the slope equations
+    pub fn verify(&self) -> Result<(), EcMadVerifyError> {
+        for (step, window) in self.0.windows(2).enumerate() {
+            let (curr, next) = (&window[0], &window[1]);
+            let bit = curr.suffix - next.suffix.double();
+            if !bit.is_zero() && !bit.is_one() {
+                return Err(EcMadVerifyError::InvalidSuffix { step });
+            }
+
+            let p = curr.partial_sum;
+            let q = curr.fixed_point;
+            if curr.x_diff_inv * (p.x - q.x) != Fp::ONE {
+                return Err(EcMadVerifyError::InvalidXDiffInv { step });
+            }
+
+            let expected_partial_sum = if bit.is_one() {
+                if curr.slope * (p.x - q.x) != p.y - q.y {
+                    return Err(EcMadVerifyError::InvalidSlope { step });
+                }
+                let x = curr.slope.square() - p.x - q.x;
+                let y = curr.slope * (p.x - x) - p.y;
+                Affine::new_unchecked(x, y)
+            } else {
+                p
+            };
+            if next.partial_sum != expected_partial_sum {
+                return Err(EcMadVerifyError::InvalidPartialSum { step });
+            }
+
+            if next.fixed_point != q && next.fixed_point != (q + q).into_affine() {
+                return Err(EcMadVerifyError::InvalidFixedPoint { step });
+            }
+        }
+
+        let last_step = self.0.len() - 1;
+        if !self.0[last_step].suffix.is_zero() {
+            return Err(EcMadVerifyError::InvalidSuffix { step: last_step });
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct DoublingStep {
     pub point: Affine<StarkwareCurve>,
     pub slope: Fp,
 }
 
+impl DoublingStep {
+    /// Slope of the tangent at `point` i.e. `(3 * x^2 + a) / (2 * y)`.
+    /// The curve has prime order so no point has `y = 0`.
+    pub fn expected_slope(&self) -> Fp {
+        let Affine { x, y, .. } = self.point;
+        (x.square() * Fp::from(3u8) + StarkwareCurve::COEFF_A) / y.double()
+    }
+
+    /// Checks the slope matches the tangent at `point`
+    pub fn verify(&self) -> bool {
+        self.slope == self.expected_slope()
+    }
+}
+
+/// A doubling step with a slope that isn't the tangent at its point
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DoublingStepError {
+    pub step: usize,
+    pub computed_slope: Fp,
+    pub stored_slope: Fp,
+}
+
+impl Display for DoublingStepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            step,
+            computed_slope,
+            stored_slope,
+        } = self;
+        write!(
+            f,
+            "doubling step {step} has slope {stored_slope} but the tangent has slope {computed_slope}"
+        )
+    }
+}
+
+impl Error for DoublingStepError {}
+
+/// Checks the slope of each step in a table of point doublings
+pub fn verify_doubling_table(steps: &[DoublingStep]) -> Result<(), DoublingStepError> {
+    for (step, doubling_step) in steps.iter().enumerate() {
+        if !doubling_step.verify() {
+            return Err(DoublingStepError {
+                step,
+                computed_slope: doubling_step.expected_slope(),
+                stored_slope: doubling_step.slope,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Values of an ECDSA instance trace checked by
+/// [InstanceTrace::verify_assertions]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcdsaTraceValue {
+    /// Result of the `zg` steps i.e. `z * G - shift_point`
+    Zg,
+    /// Result of the `rq` steps i.e. `r * Q + shift_point`
+    Rq,
+    /// `B = z * G + r * Q`
+    B,
+    /// Result of the `wb` steps i.e. `w * B + shift_point`
+    Wb,
+    /// The signature's `r` which must equal the x-coordinate of `w * B`
+    R,
+}
+
+impl Display for EcdsaTraceValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Zg => "zg",
+            Self::Rq => "rq",
+            Self::B => "b",
+            Self::Wb => "wb",
+            Self::R => "r",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct InstanceTrace {
     pub instance: EcdsaInstance,
@@ -68,50 +292,48 @@ pub struct InstanceTrace {
     /// steps for `z * G` where
     /// `G` is the elliptic curve generator point and
     /// `z` is the message hash
-    pub zg_steps: Vec<EcMadPartialStep>,
+    pub zg_steps: Box<[EcMadPartialStep; 256]>,
     /// steps for the scalar multiplication `r * Q` where
     /// `Q` is the pubkey point and
     /// `r` is the signature's `r` value
-    pub rq_steps: Vec<EcMadPartialStep>,
+    pub rq_steps: Box<[EcMadPartialStep; 256]>,
     /// steps for the scalar multiplication `w * B` where
     /// `B = z * G + r * Q` and
     /// `w` is the inverse of the signature's `s` value (NOTE: that's the
     /// inverse in the curve's scalar field)
-    pub wb_steps: Vec<EcMadPartialStep>,
+    pub wb_steps: Box<[EcMadPartialStep; 256]>,
 }
 
 impl InstanceTrace {
-    // TODO: error handling
-    pub fn new(instance: EcdsaInstance) -> Self {
+    /// Generates the trace of an instance. Errors if the signature is invalid
+    /// or can't be verified by the AIR.
+    pub fn new(instance: EcdsaInstance) -> Result<Self, InvalidSignature> {
+        let pubkey = check_signature(&instance)?;
         let message = Fp::from(BigUint::from(instance.message));
-        let pubkey_x = Fp::from(BigUint::from(instance.pubkey_x));
         let r = Fp::from(BigUint::from(instance.signature.r));
         let w = Fr::from(BigUint::from(instance.signature.w));
-        let s = w.inverse().unwrap();
-        let pubkey = verify(message, r, s, pubkey_x).expect("signature is invalid");
 
         let shift_point = Projective::from(SHIFT_POINT);
         let generator = Projective::from(StarkwareCurve::GENERATOR);
 
-        let zg = Affine::from(mimic_ec_mad_air(message.into(), generator, -shift_point).unwrap());
-        let qr = Affine::from(mimic_ec_mad_air(r.into(), pubkey.into(), shift_point).unwrap());
-
-        let b = (zg + qr).into_affine();
-        let b_slope = calculate_slope(zg, qr).unwrap();
-        let b_x_diff_inv = (zg.x - qr.x).inverse().unwrap();
-        let b_doubling_steps = doubling_steps(256, b.into());
-        let wb = Affine::from(mimic_ec_mad_air(w.into(), b.into(), shift_point).unwrap());
-
         // Restrict generator max doublings to 250 to match the
         // periodic column used by AIR.
-        let zg_steps = gen_ec_mad_steps::<250>(message.into(), generator, -shift_point);
-        let rq_steps = gen_ec_mad_steps::<255>(r.into(), pubkey.into(), shift_point);
-        let wb_steps = gen_ec_mad_steps::<255>(w.into(), b.into(), shift_point);
+        let zg_steps = gen_ec_mad_steps_fixed::<250>(instance.message, generator, -shift_point)?;
+        let rq_steps =
+            gen_ec_mad_steps_fixed::<255>(instance.signature.r, pubkey.into(), shift_point)?;
+        let zg = zg_steps.last().unwrap().partial_sum;
+        let qr = rq_steps.last().unwrap().partial_sum;
 
-        assert_eq!(zg, zg_steps.last().unwrap().partial_sum);
-        assert_eq!(qr, rq_steps.last().unwrap().partial_sum);
-        assert_eq!(wb, wb_steps.last().unwrap().partial_sum);
+        let b = (zg + qr).into_affine();
+        let b_slope = calculate_slope(zg, qr).ok_or(InvalidSignature::VerificationFailed)?;
+        let b_x_diff_inv = (zg.x - qr.x)
+            .inverse()
+            .ok_or(InvalidSignature::VerificationFailed)?;
+        let b_doubling_steps = doubling_steps(256, b.into());
+        let wb_steps = gen_ec_mad_steps_fixed::<255>(instance.signature.w, b.into(), shift_point)?;
+        let wb = wb_steps.last().unwrap().partial_sum;
 
+        // scalars are non-zero and less than both moduli so are invertible
         let w = Fp::from(BigUint::from(w));
         let w_inv = w.inverse().unwrap();
         let r_inv = r.inverse().unwrap();
@@ -120,11 +342,13 @@ impl InstanceTrace {
         let pubkey_doubling_steps = doubling_steps(256, pubkey.into());
 
         let shift_point = Affine::from(shift_point);
-        let r_point_slope = calculate_slope(wb, -shift_point).unwrap();
-        let r_point_x_diff_inv = (wb.x - (-shift_point).x).inverse().unwrap();
-        assert_eq!(r, (wb - shift_point).into_affine().x);
+        let r_point_slope =
+            calculate_slope(wb, -shift_point).ok_or(InvalidSignature::VerificationFailed)?;
+        let r_point_x_diff_inv = (wb.x - (-shift_point).x)
+            .inverse()
+            .ok_or(InvalidSignature::VerificationFailed)?;
 
-        Self {
+        let trace = Self {
             instance,
             pubkey,
             pubkey_doubling_steps,
@@ -143,7 +367,47 @@ impl InstanceTrace {
             zg_steps,
             rq_steps,
             wb_steps,
+        };
+        debug_assert_eq!(Ok(()), trace.verify_assertions());
+        Ok(trace)
+    }
+
+    /// Checks the results of the multiply-add steps match the same values
+    /// computed directly and that `w * B` yields the signature's `r`. Errors
+    /// if the trace was modified or generated from an invalid instance.
+    pub fn verify_assertions(&self) -> Result<(), TraceError> {
+        let mismatch = |value| TraceError::EcdsaMismatch {
+            index: self.instance.index,
+            value,
+        };
+        let shift_point = Projective::from(SHIFT_POINT);
+        let generator = Projective::from(StarkwareCurve::GENERATOR);
+        let message = BigUint::from(self.instance.message);
+        let r = BigUint::from(self.instance.signature.r);
+        let w = BigUint::from(self.instance.signature.w);
+        let zg = self.zg_steps.last().unwrap().partial_sum;
+        let qr = self.rq_steps.last().unwrap().partial_sum;
+        let wb = self.wb_steps.last().unwrap().partial_sum;
+
+        let expected_zg = mimic_ec_mad_air(message, generator, -shift_point);
+        if expected_zg.map(Affine::from) != Ok(zg) {
+            return Err(mismatch(EcdsaTraceValue::Zg));
+        }
+        let expected_qr = mimic_ec_mad_air(r, self.pubkey.into(), shift_point);
+        if expected_qr.map(Affine::from) != Ok(qr) {
+            return Err(mismatch(EcdsaTraceValue::Rq));
+        }
+        if self.b != (zg + qr).into_affine() {
+            return Err(mismatch(EcdsaTraceValue::B));
+        }
+        let expected_wb = mimic_ec_mad_air(w, self.b.into(), shift_point);
+        if expected_wb.map(Affine::from) != Ok(wb) {
+            return Err(mismatch(EcdsaTraceValue::Wb));
+        }
+        if self.r != (wb - SHIFT_POINT).into_affine().x {
+            return Err(mismatch(EcdsaTraceValue::R));
         }
+        Ok(())
     }
 
     /// Creates a new dummy instance.
@@ -152,7 +416,7 @@ impl InstanceTrace {
         let mut dummy_trace = DUMMY_INSTANCE_TRACE
             .get_or_init(|| {
                 let dummy_instance = gen_dummy_instance(0);
-                Self::new(dummy_instance)
+                Self::new(dummy_instance).unwrap()
             })
             .clone();
         dummy_trace.instance.index = index;
@@ -160,19 +424,27 @@ impl InstanceTrace {
     }
 }
 
-/// Generates a list of the steps involved with an EC multiply-add
+/// Generates the steps involved with an EC multiply-add. There is always one
+/// step per column of the AIR (256) even if the scalar has leading zeros. Steps
+/// for the leading zeros don't change the partial sum. Steps are boxed since
+/// the array is too large to comfortably keep on the stack.
 // TODO: NOTE: MAX_POINT_DOUBLINGS is a little decoupled but this is to do with
 // the periodic column construction. If this is done for i>251 the AIR with
 // error.
-fn gen_ec_mad_steps<const MAX_POINT_DOUBLINGS: usize>(
-    x: BigUint,
+pub fn gen_ec_mad_steps_fixed<const MAX_POINT_DOUBLINGS: usize>(
+    x: U256,
     mut point: Projective<StarkwareCurve>,
     shift_point: Projective<StarkwareCurve>,
-) -> Vec<EcMadPartialStep> {
-    let x = U256::from(x);
-    // Assertions fail if the AIR will error
-    assert!(x != U256::ZERO);
-    assert!(x < uint!(2_U256).pow(uint!(251_U256)));
+) -> Result<Box<[EcMadPartialStep; 256]>, EcMadError> {
+    // Errors if the AIR will error
+    if x == U256::ZERO {
+        return Err(EcMadError::ScalarZero);
+    }
+    if x.bit_len() > 251 {
+        return Err(EcMadError::ScalarTooLarge {
+            bits: x.bit_len() as u64,
+        });
+    }
     let mut partial_sum = shift_point;
     let mut res = Vec::new();
     for i in 0..256 {
@@ -183,6 +455,9 @@ fn gen_ec_mad_steps<const MAX_POINT_DOUBLINGS: usize>(
         let mut partial_sum_next = partial_sum;
         let partial_sum_affine = partial_sum.into_affine();
         let point_affine = point.into_affine();
+        let x_diff_inv = (partial_sum_affine.x - point_affine.x)
+            .inverse()
+            .ok_or(EcMadError::PointCollision { step: i })?;
         if bit == uint!(1_U256) {
             slope = calculate_slope(point_affine, partial_sum_affine).unwrap();
             partial_sum_next += point;
@@ -192,7 +467,7 @@ fn gen_ec_mad_steps<const MAX_POINT_DOUBLINGS: usize>(
             partial_sum: partial_sum_affine,
             fixed_point: point_affine,
             suffix: Fp::from(BigUint::from(suffix)),
-            x_diff_inv: (partial_sum_affine.x - point_affine.x).inverse().unwrap(),
+            x_diff_inv,
             slope,
         });
 
@@ -201,7 +476,7 @@ fn gen_ec_mad_steps<const MAX_POINT_DOUBLINGS: usize>(
             point.double_in_place();
         }
     }
-    res
+    Ok(res.into_boxed_slice().try_into().unwrap())
 }
 
 pub fn doubling_steps(num_steps: usize, mut p: Projective<StarkwareCurve>) -> Vec<DoublingStep> {
@@ -216,6 +491,7 @@ pub fn doubling_steps(num_steps: usize, mut p: Projective<StarkwareCurve>) -> Ve
         });
         p.double_in_place();
     }
+    debug_assert_eq!(Ok(()), verify_doubling_table(&res));
     res
 }
 
@@ -303,29 +579,498 @@ fn verify(msg_hash: Fp, r: Fp, s: Fr, pubkey_x: Fp) -> Option<Affine<StarkwareCu
     None
 }
 
-/// Computes `m * point + shift_point` using the same steps like the AIR and
-/// Returns None if and only if the AIR errors.
-pub(crate) fn mimic_ec_mad_air(
-    m: BigUint,
-    mut point: Projective<StarkwareCurve>,
-    shift_point: Projective<StarkwareCurve>,
-) -> Option<Projective<StarkwareCurve>> {
-    if !(1..Fp::MODULUS_BIT_SIZE).contains(&(m.bits() as u32)) {
-        return None;
+/// Reasons the trace of an ECDSA instance can't be generated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidSignature {
+    PubkeyNotOnCurve {
+        pubkey_x: Fp,
+    },
+    /// The signature doesn't verify or the AIR can't verify it
+    VerificationFailed,
+    /// The message, `r` and `w` must be in the range `[1, 2^max_bits)`
+    ScalarOutOfRange {
+        scalar: U256,
+        max_bits: u32,
+    },
+}
+
+impl Display for InvalidSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PubkeyNotOnCurve { pubkey_x } => {
+                write!(
+                    f,
+                    "public key with x-coordinate {pubkey_x} is not on the curve"
+                )
+            }
+            Self::VerificationFailed => write!(f, "signature verification failed"),
+            Self::ScalarOutOfRange { scalar, max_bits } => {
+                write!(f, "scalar {scalar} is not in [1, 2^{max_bits})")
+            }
+        }
     }
-    let mut m = U256::from(m);
-    let mut partial_sum = shift_point;
-    #[allow(clippy::needless_range_loop)]
-    while m != U256::ZERO {
-        if Affine::from(partial_sum).x == Affine::from(point).x {
-            return None;
+}
+
+impl Error for InvalidSignature {}
+
+impl From<EcMadError> for InvalidSignature {
+    fn from(_: EcMadError) -> Self {
+        // scalars are checked before generating steps so the only error is a
+        // collision which the AIR can't handle
+        Self::VerificationFailed
+    }
+}
+
+/// Reasons an ECDSA instance fails verification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    PubkeyNotOnCurve,
+    InvalidSignature,
+    /// The message, `r` and `w` must be in the range `[1, 2^251)`
+    ScalarOutOfRange,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PubkeyNotOnCurve => write!(f, "public key is not on the curve"),
+            Self::InvalidSignature => write!(f, "signature is invalid"),
+            Self::ScalarOutOfRange => write!(f, "message, r or w is not in [1, 2^251)"),
         }
-        let bit = m & uint!(1_U256);
-        if bit == uint!(1_U256) {
-            partial_sum += point;
+    }
+}
+
+impl Error for VerifyError {}
+
+impl From<InvalidSignature> for VerifyError {
+    fn from(err: InvalidSignature) -> Self {
+        match err {
+            InvalidSignature::PubkeyNotOnCurve { .. } => Self::PubkeyNotOnCurve,
+            InvalidSignature::VerificationFailed => Self::InvalidSignature,
+            InvalidSignature::ScalarOutOfRange { .. } => Self::ScalarOutOfRange,
+        }
+    }
+}
+
+/// Checks the signature of each instance is valid. Complements
+/// [binary::AirPrivateInput::validate_against_public] which can't verify
+/// signatures itself.
+pub fn validate_signatures(instances: &[EcdsaInstance]) -> Result<(), ValidationError> {
+    verify_all(instances).map_err(|(i, _)| ValidationError::InvalidEcdsaSignature {
+        index: instances[i].index,
+    })
+}
+
+/// Checks the signature of each instance. Signatures are checked in parallel
+/// if the "parallel" feature is enabled.
+pub fn verify_batch(instances: &[EcdsaInstance]) -> Vec<bool> {
+    ark_std::cfg_iter!(instances).map(verify_instance).collect()
+}
+
+/// Checks the signature of each instance. Returns the position of the first
+/// invalid instance in `instances` along with the reason it's invalid.
+pub fn verify_all(instances: &[EcdsaInstance]) -> Result<(), (usize, VerifyError)> {
+    let check = |(i, instance)| check_signature(instance).err().map(|err| (i, err.into()));
+    #[cfg(feature = "parallel")]
+    let failure = instances.par_iter().enumerate().find_map_first(check);
+    #[cfg(not(feature = "parallel"))]
+    let failure = instances.iter().enumerate().find_map(check);
+    failure.map_or(Ok(()), Err)
+}
+
+/// Generates the trace of each instance. Traces are generated in parallel if
+/// the "parallel" feature is enabled.
+pub fn generate_ecdsa_traces(
+    instances: &[EcdsaInstance],
+) -> Vec<Result<InstanceTrace, TraceError>> {
+    ark_std::cfg_iter!(instances)
+        .map(|&instance| {
+            InstanceTrace::new(instance).map_err(|error| TraceError::InvalidEcdsaSignature {
+                index: instance.index,
+                error,
+            })
+        })
+        .collect()
+}
+
+/// Checks the signature of an instance without generating its trace. Lives here
+/// rather than on [EcdsaInstance] since the binary crate has no access to the
+/// curve.
+pub fn verify_instance(instance: &EcdsaInstance) -> bool {
+    check_signature(instance).is_ok()
+}
+
+/// Creates an instance from a public key, message hash and signature `(r, s)`.
+/// Panics if `s` is zero.
+pub fn instance_from_typed(
+    index: u32,
+    pubkey: Affine<StarkwareCurve>,
+    message: Fp,
+    (r, s): (Fr, Fr),
+) -> EcdsaInstance {
+    let w = s.inverse().expect("s must be non-zero");
+    EcdsaInstance {
+        index,
+        pubkey_x: U256::from(BigUint::from(pubkey.x)),
+        message: U256::from(BigUint::from(message)),
+        signature: Signature {
+            r: U256::from(BigUint::from(r)),
+            w: U256::from(BigUint::from(w)),
+        },
+    }
+}
+
+/// Inverse of [instance_from_typed]. Returns `(pubkey, message, r, s)` or None
+/// if the signature is invalid. The instance only stores the x-coordinate of
+/// the public key so the signature is needed to determine the y-coordinate.
+pub fn instance_into_typed(
+    instance: &EcdsaInstance,
+) -> Option<(Affine<StarkwareCurve>, Fp, Fr, Fr)> {
+    let pubkey = check_signature(instance).ok()?;
+    let message = Fp::from(BigUint::from(instance.message));
+    let r = Fr::from(BigUint::from(instance.signature.r));
+    let s = Fr::from(BigUint::from(instance.signature.w)).inverse()?;
+    Some((pubkey, message, r, s))
+}
+
+/// Like [verify] but returns an error instead of panicking if the pubkey isn't
+/// on the curve or a scalar can't be used by the AIR
+fn check_signature(instance: &EcdsaInstance) -> Result<Affine<StarkwareCurve>, InvalidSignature> {
+    const MAX_SCALAR_BITS: u32 = 251;
+    let EcdsaInstance {
+        pubkey_x,
+        message,
+        signature: Signature { r, w },
+        ..
+    } = *instance;
+    for scalar in [message, r, w] {
+        if scalar == U256::ZERO || scalar.bit_len() > MAX_SCALAR_BITS as usize {
+            return Err(InvalidSignature::ScalarOutOfRange {
+                scalar,
+                max_bits: MAX_SCALAR_BITS,
+            });
+        }
+    }
+    let pubkey_x_felt = Fp::from(BigUint::from(pubkey_x));
+    if pubkey_x >= U256::from::<BigUint>(Fp::MODULUS.into())
+        || Affine::<StarkwareCurve>::get_ys_from_x_unchecked(pubkey_x_felt).is_none()
+    {
+        return Err(InvalidSignature::PubkeyNotOnCurve {
+            pubkey_x: pubkey_x_felt,
+        });
+    }
+    let pubkey_x = pubkey_x_felt;
+
+    let message = Fp::from(BigUint::from(message));
+    let r = Fp::from(BigUint::from(r));
+    // `w` is non-zero and less than the curve order so it's invertible
+    let s = Fr::from(BigUint::from(w)).inverse().unwrap();
+    verify(message, r, s, pubkey_x).ok_or(InvalidSignature::VerificationFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::doubling_steps;
+    use super::gen_dummy_instance;
+    use super::gen_ec_mad_steps_fixed;
+    use super::generate_ecdsa_traces;
+    use super::instance_from_typed;
+    use super::instance_into_typed;
+    use super::validate_signatures;
+    use super::verify_all;
+    use super::verify_batch;
+    use super::verify_instance;
+    use super::verify_doubling_table;
+    use super::DoublingStep;
+    use super::DoublingStepError;
+    use super::EcMadError;
+    use super::EcMadTrace;
+    use super::EcMadVerifyError;
+    use super::EcdsaTraceValue;
+    use super::InstanceTrace;
+    use super::InvalidSignature;
+    use super::VerifyError;
+    use super::SHIFT_POINT;
+    use crate::traces::TraceError;
+    use crate::utils::curve::Fr;
+    use crate::utils::curve::StarkwareCurve;
+    use ark_ec::short_weierstrass::Affine;
+    use ark_ec::short_weierstrass::Projective;
+    use ark_ec::short_weierstrass::SWCurveConfig;
+    use ark_ec::CurveGroup;
+    use ark_ff::Field;
+    use ark_ff::PrimeField;
+    use binary::errors::ValidationError;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+    use num_bigint::BigUint;
+    use ruint::aliases::U256;
+
+    #[test]
+    fn generator_doubling_steps_have_tangent_slopes() {
+        let generator = Projective::from(StarkwareCurve::GENERATOR);
+        let mut steps = doubling_steps(5, generator);
+
+        assert!(steps.iter().all(DoublingStep::verify));
+        assert_eq!(Ok(()), verify_doubling_table(&steps));
+
+        let computed_slope = steps[3].slope;
+        steps[3].slope += Fp::ONE;
+        assert_eq!(
+            Err(DoublingStepError {
+                step: 3,
+                computed_slope,
+                stored_slope: computed_slope + Fp::ONE,
+            }),
+            verify_doubling_table(&steps)
+        );
+    }
+
+    #[test]
+    fn ec_mad_trace_final_result_matches_scalar_multiplication() {
+        let scalar = BigUint::from(0xdeadbeefu32);
+        let point = Projective::from(StarkwareCurve::GENERATOR);
+        let shift_point = Projective::from(SHIFT_POINT);
+
+        let trace = EcMadTrace::new(scalar, point, shift_point).unwrap();
+
+        let expected = point * Fr::from(0xdeadbeefu32) + shift_point;
+        assert_eq!(expected.into_affine(), trace.final_result());
+        assert_eq!(Ok(()), trace.verify());
+    }
+
+    #[test]
+    fn ec_mad_steps_fixed_have_256_steps() {
+        let point = Projective::from(StarkwareCurve::GENERATOR);
+        let shift_point = Projective::from(SHIFT_POINT);
+
+        for scalar in [
+            U256::from(1),
+            U256::from(0xdeadbeefu32),
+            (U256::from(1) << 251) - U256::from(1),
+        ] {
+            let steps = gen_ec_mad_steps_fixed::<255>(scalar, point, shift_point).unwrap();
+
+            assert_eq!(256, steps.len());
+            let expected = point * Fr::from(BigUint::from(scalar)) + shift_point;
+            assert_eq!(expected.into_affine(), steps[255].partial_sum);
+            // steps for leading zeros don't change the partial sum
+            let bit_len = scalar.bit_len();
+            assert!(steps[bit_len..]
+                .iter()
+                .all(|step| step.partial_sum == steps[255].partial_sum));
         }
-        point.double_in_place();
-        m >>= 1;
     }
-    Some(partial_sum)
+
+    #[test]
+    fn ecdsa_instance_trace_has_256_steps_per_scalar() {
+        let trace = InstanceTrace::new_dummy(0);
+
+        assert_eq!(256, trace.zg_steps.len());
+        assert_eq!(256, trace.rq_steps.len());
+        assert_eq!(256, trace.wb_steps.len());
+    }
+
+    #[test]
+    fn instance_trace_rejects_invalid_signatures() {
+        let mut tampered_r = gen_dummy_instance(0);
+        tampered_r.signature.r += U256::from(1);
+        let off_curve_x = (0u64..)
+            .find(|&x| Affine::<StarkwareCurve>::get_ys_from_x_unchecked(Fp::from(x)).is_none())
+            .unwrap();
+        let mut off_curve = gen_dummy_instance(0);
+        off_curve.pubkey_x = U256::from(off_curve_x);
+        let mut zero_w = gen_dummy_instance(0);
+        zero_w.signature.w = U256::ZERO;
+        let mut large_message = gen_dummy_instance(0);
+        large_message.message = U256::from(1) << 251;
+
+        assert_eq!(
+            InvalidSignature::VerificationFailed,
+            InstanceTrace::new(tampered_r).unwrap_err()
+        );
+        assert!(matches!(
+            InstanceTrace::new(off_curve),
+            Err(InvalidSignature::PubkeyNotOnCurve { pubkey_x }) if pubkey_x == Fp::from(off_curve_x)
+        ));
+        assert_eq!(
+            InvalidSignature::ScalarOutOfRange {
+                scalar: U256::ZERO,
+                max_bits: 251,
+            },
+            InstanceTrace::new(zero_w).unwrap_err()
+        );
+        assert_eq!(
+            InvalidSignature::ScalarOutOfRange {
+                scalar: U256::from(1) << 251,
+                max_bits: 251,
+            },
+            InstanceTrace::new(large_message).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn instance_trace_assertions_detect_mismatches() {
+        let trace = InstanceTrace::new(gen_dummy_instance(7)).unwrap();
+        let mismatch = |value| Err(TraceError::EcdsaMismatch { index: 7, value });
+        let other_point = SHIFT_POINT;
+
+        let mut zg_tampered = trace.clone();
+        zg_tampered.zg_steps[255].partial_sum = other_point;
+        let mut rq_tampered = trace.clone();
+        rq_tampered.rq_steps[255].partial_sum = other_point;
+        let mut b_tampered = trace.clone();
+        b_tampered.b = other_point;
+        let mut wb_tampered = trace.clone();
+        wb_tampered.wb_steps[255].partial_sum = other_point;
+        let mut r_tampered = trace.clone();
+        r_tampered.r += Fp::ONE;
+
+        assert_eq!(Ok(()), trace.verify_assertions());
+        assert_eq!(
+            mismatch(EcdsaTraceValue::Zg),
+            zg_tampered.verify_assertions()
+        );
+        assert_eq!(
+            mismatch(EcdsaTraceValue::Rq),
+            rq_tampered.verify_assertions()
+        );
+        assert_eq!(mismatch(EcdsaTraceValue::B), b_tampered.verify_assertions());
+        assert_eq!(
+            mismatch(EcdsaTraceValue::Wb),
+            wb_tampered.verify_assertions()
+        );
+        assert_eq!(mismatch(EcdsaTraceValue::R), r_tampered.verify_assertions());
+    }
+
+    #[test]
+    fn ec_mad_trace_verify_detects_invalid_slope() {
+        let scalar = BigUint::from(5u32);
+        let point = Projective::from(StarkwareCurve::GENERATOR);
+        let shift_point = Projective::from(SHIFT_POINT);
+        let mut trace = EcMadTrace::new(scalar, point, shift_point).unwrap();
+
+        trace.0[2].slope = trace.0[2].slope.double();
+
+        assert_eq!(
+            Err(EcMadVerifyError::InvalidSlope { step: 2 }),
+            trace.verify()
+        );
+    }
+
+    #[test]
+    fn ec_mad_trace_rejects_invalid_scalars() {
+        let point = Projective::from(StarkwareCurve::GENERATOR);
+        let shift_point = Projective::from(SHIFT_POINT);
+
+        let zero_res = EcMadTrace::new(BigUint::from(0u32), point, shift_point);
+        let large_res = EcMadTrace::new(BigUint::from(1u32) << 251, point, shift_point);
+
+        assert_eq!(EcMadError::ScalarZero, zero_res.unwrap_err());
+        assert_eq!(
+            EcMadError::ScalarTooLarge { bits: 252 },
+            large_res.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn verify_instance_accepts_valid_signature() {
+        let instance = gen_dummy_instance(0);
+
+        assert!(verify_instance(&instance));
+    }
+
+    #[test]
+    fn verify_instance_rejects_tampered_r() {
+        let mut instance = gen_dummy_instance(0);
+        instance.signature.r += U256::from(1);
+
+        assert!(!verify_instance(&instance));
+    }
+
+    #[test]
+    fn generate_ecdsa_traces_reports_invalid_signatures() {
+        let valid = gen_dummy_instance(0);
+        let mut tampered = gen_dummy_instance(1);
+        tampered.signature.r += U256::from(1);
+
+        let traces = generate_ecdsa_traces(&[valid, tampered]);
+
+        assert_eq!(2, traces.len());
+        assert_eq!(0, traces[0].as_ref().unwrap().instance.index);
+        assert_eq!(
+            TraceError::InvalidEcdsaSignature {
+                index: 1,
+                error: InvalidSignature::VerificationFailed,
+            },
+            *traces[1].as_ref().unwrap_err()
+        );
+    }
+
+    #[test]
+    fn typed_instance_round_trips() {
+        let instance = gen_dummy_instance(5);
+
+        let (pubkey, message, r, s) = instance_into_typed(&instance).unwrap();
+        let round_tripped = instance_from_typed(5, pubkey, message, (r, s));
+
+        assert_eq!(instance, round_tripped);
+        assert_eq!(StarkwareCurve::GENERATOR, pubkey);
+    }
+
+    #[test]
+    fn typed_instance_requires_valid_signature() {
+        let mut instance = gen_dummy_instance(0);
+        instance.signature.r += U256::from(1);
+
+        assert_eq!(None, instance_into_typed(&instance));
+    }
+
+    #[test]
+    fn verify_batch_checks_each_instance() {
+        let valid = gen_dummy_instance(0);
+        let mut tampered = gen_dummy_instance(1);
+        tampered.signature.r += U256::from(1);
+
+        let results = verify_batch(&[valid, tampered, valid]);
+
+        assert_eq!(vec![true, false, true], results);
+    }
+
+    #[test]
+    fn verify_all_reports_first_failure() {
+        let valid = gen_dummy_instance(0);
+        let mut tampered = gen_dummy_instance(1);
+        tampered.signature.r += U256::from(1);
+        let mut off_curve = gen_dummy_instance(2);
+        off_curve.pubkey_x = U256::from::<BigUint>(Fp::MODULUS.into());
+        let mut zero_w = gen_dummy_instance(3);
+        zero_w.signature.w = U256::ZERO;
+
+        assert_eq!(Ok(()), verify_all(&[valid, valid]));
+        assert_eq!(
+            Err((1, VerifyError::InvalidSignature)),
+            verify_all(&[valid, tampered, off_curve])
+        );
+        assert_eq!(
+            Err((1, VerifyError::PubkeyNotOnCurve)),
+            verify_all(&[valid, off_curve, tampered])
+        );
+        assert_eq!(
+            Err((0, VerifyError::ScalarOutOfRange)),
+            verify_all(&[zero_w, valid])
+        );
+    }
+
+    #[test]
+    fn validate_signatures_rejects_tampered_signature() {
+        let valid = gen_dummy_instance(0);
+        let mut tampered = gen_dummy_instance(1);
+        tampered.signature.r += U256::from(1);
+
+        assert!(validate_signatures(&[valid]).is_ok());
+        assert!(matches!(
+            validate_signatures(&[valid, tampered]),
+            Err(ValidationError::InvalidEcdsaSignature { index: 1 })
+        ));
+    }
 }
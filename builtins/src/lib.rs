@@ -1,7 +1,10 @@
 pub mod bitwise;
 pub mod ec_op;
+pub mod ec_utils;
 pub mod ecdsa;
 pub mod pedersen;
 pub mod poseidon;
 pub mod range_check;
+pub mod starkware_curve;
+pub mod traces;
 pub mod utils;
@@ -15,6 +15,11 @@ use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596
 use num_bigint::BigUint;
 use ruint::aliases::U256;
 use ruint::uint;
+use std::error::Error;
+use std::fmt::Display;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use crate::traces::TraceError;
 use crate::utils::curve::Fr;
 use crate::utils::curve::StarkwareCurve;
 use crate::utils::curve::calculate_slope;
@@ -35,6 +40,47 @@ pub fn pedersen_hash(a: Fp, b: Fp) -> Fp {
     Fp::new_unchecked(BigInt(res.into_mont()))
 }
 
+/// Computes the same hash as [pedersen_hash] directly from the constant points
+/// [P0] to [P4] rather than starknet-crypto's lookup tables
+pub fn compute_hash(a: Fp, b: Fp) -> Fp {
+    hash_point(a, b).into_affine().x
+}
+
+/// Computes [compute_hash] for each pair. Converting the points to affine is
+/// batched which saves an inversion per pair.
+pub fn batch_pedersen_hash(pairs: &[(Fp, Fp)]) -> Vec<Fp> {
+    let points = pairs
+        .iter()
+        .map(|&(a, b)| hash_point(a, b))
+        .collect::<Vec<_>>();
+    Projective::normalize_batch(&points)
+        .into_iter()
+        .map(|point| point.x)
+        .collect()
+}
+
+/// Hashing for [PedersenInstance]. The binary crate doesn't have the curve
+/// constants so the hash can't be an inherent method of the instance.
+pub trait PedersenInstanceExt {
+    /// Output of the Pedersen builtin instance. Errors if an input isn't a
+    /// field element.
+    fn output(&self) -> Result<Fp, PedersenError>;
+}
+
+impl PedersenInstanceExt for PedersenInstance {
+    fn output(&self) -> Result<Fp, PedersenError> {
+        let a = try_felt_from_u256(self.a)?;
+        let b = try_felt_from_u256(self.b)?;
+        Ok(compute_hash(a, b))
+    }
+}
+
+fn hash_point(a: Fp, b: Fp) -> Projective<StarkwareCurve> {
+    Projective::from(P0)
+        + process_element(a, P1.into(), P2.into())
+        + process_element(b, P3.into(), P4.into())
+}
+
 /// Based on StarkWare's Python reference implementation: <https://github.com/starkware-libs/starkex-for-spot-trading/blob/master/src/starkware/crypto/starkware/crypto/signature/pedersen_params.json>
 // TODO: remove
 #[deprecated]
@@ -60,6 +106,60 @@ fn process_element(
     p1 * x_low + p2 * x_high
 }
 
+/// Errors that prevent generating the trace of a Pedersen hash instance
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PedersenError {
+    /// Inputs must be field elements i.e. less than the modulus
+    InputTooLarge { value: U256 },
+    /// The partial sum and constant point share an x-coordinate
+    PointCollision { step: usize },
+    /// The steps of the first input don't end at the first point of the
+    /// second input
+    StepsMismatch,
+    /// The final point of the steps doesn't match the hash output
+    OutputMismatch,
+}
+
+impl Display for PedersenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InputTooLarge { value } => {
+                write!(f, "input {value} is not a valid field element")
+            }
+            Self::PointCollision { step } => {
+                write!(
+                    f,
+                    "partial sum and constant point have the same x-coordinate at step {step}"
+                )
+            }
+            Self::StepsMismatch => {
+                write!(
+                    f,
+                    "steps of the first input don't end at the second input's start"
+                )
+            }
+            Self::OutputMismatch => write!(f, "final step doesn't match the hash output"),
+        }
+    }
+}
+
+impl Error for PedersenError {}
+
+/// Generates the trace of each instance. Traces are generated in parallel if
+/// the "parallel" feature is enabled.
+pub fn generate_pedersen_traces(
+    instances: &[PedersenInstance],
+) -> Vec<Result<InstanceTrace, TraceError>> {
+    ark_std::cfg_iter!(instances)
+        .map(|&instance| {
+            InstanceTrace::try_new(instance).map_err(|error| TraceError::Pedersen {
+                index: instance.index,
+                error,
+            })
+        })
+        .collect()
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct ElementPartialStep {
     pub point: Affine<StarkwareCurve>,
@@ -81,25 +181,35 @@ pub struct InstanceTrace {
 
 impl InstanceTrace {
     pub fn new(instance: PedersenInstance) -> Self {
+        Self::try_new(instance).unwrap()
+    }
+
+    /// Generates the shift-and-add steps for both inputs. Errors if an input
+    /// isn't a field element or if a step has no well defined slope.
+    pub fn try_new(instance: PedersenInstance) -> Result<Self, PedersenError> {
         let PedersenInstance { a, b, .. } = instance;
-        let a = Fp::from(BigUint::from(a));
-        let b = Fp::from(BigUint::from(b));
+        let a = try_felt_from_u256(a)?;
+        let b = try_felt_from_u256(b)?;
 
         let a_p0 = P0;
         let a_p1 = P1;
         let a_p2 = P2;
-        let a_steps = gen_element_steps(a, a_p0, a_p1, a_p2);
+        let a_steps = gen_element_steps(a, a_p0, a_p1, a_p2)?;
 
         let b_p0 = (a_p0 + process_element(a, a_p1.into(), a_p2.into())).into();
         let b_p1 = P3;
         let b_p2 = P4;
         // check out initial value for the second input is correct
-        assert_eq!(a_steps.last().unwrap().point, b_p0);
-        let b_steps = gen_element_steps(b, b_p0, b_p1, b_p2);
+        if a_steps.last().map(|step| step.point) != Some(b_p0) {
+            return Err(PedersenError::StepsMismatch);
+        }
+        let b_steps = gen_element_steps(b, b_p0, b_p1, b_p2)?;
 
         // check the expected output matches
         let output = pedersen_hash(a, b);
-        assert_eq!(output, b_steps.last().unwrap().point.x);
+        if b_steps.last().map(|step| step.point.x) != Some(output) {
+            return Err(PedersenError::OutputMismatch);
+        }
 
         let a_bit251 = instance.a.bit(251);
         let a_bit196 = instance.a.bit(196);
@@ -113,7 +223,7 @@ impl InstanceTrace {
         let b_bit251_and_bit196_and_bit192 = b_bit251 && b_bit196 && b_bit192;
         let b_bit251_and_bit196 = b_bit251 && b_bit196;
 
-        Self {
+        Ok(Self {
             instance,
             output,
             a_steps,
@@ -122,7 +232,25 @@ impl InstanceTrace {
             a_bit251_and_bit196,
             b_bit251_and_bit196_and_bit192,
             b_bit251_and_bit196,
-        }
+        })
+    }
+
+    /// Returns the x-coordinate of the final accumulated point
+    pub fn output_x(&self) -> Fp {
+        self.b_steps.last().unwrap().point.x
+    }
+
+    /// Checks the final accumulated point matches the expected hash
+    pub fn verify_output(&self, expected: Fp) -> bool {
+        self.output == expected && self.output_x() == expected
+    }
+}
+
+fn try_felt_from_u256(value: U256) -> Result<Fp, PedersenError> {
+    if value < U256::from::<BigUint>(Fp::MODULUS.into()) {
+        Ok(Fp::from(BigUint::from(value)))
+    } else {
+        Err(PedersenError::InputTooLarge { value })
     }
 }
 
@@ -131,7 +259,7 @@ fn gen_element_steps(
     p0: Affine<StarkwareCurve>,
     p1: Affine<StarkwareCurve>,
     p2: Affine<StarkwareCurve>,
-) -> Vec<ElementPartialStep> {
+) -> Result<Vec<ElementPartialStep>, PedersenError> {
     // generate our constant points
     let mut constant_points = Vec::new();
     let mut p1_acc = Projective::from(p1);
@@ -159,7 +287,8 @@ fn gen_element_steps(
         let partial_point_affine = partial_point.into_affine();
         if bit == uint!(1_U256) {
             let constant_point = constant_points[i];
-            slope = calculate_slope(constant_point.into(), partial_point_affine).unwrap();
+            slope = calculate_slope(constant_point.into(), partial_point_affine)
+                .ok_or(PedersenError::PointCollision { step: i })?;
             partial_point_next += constant_point;
         }
 
@@ -172,13 +301,22 @@ fn gen_element_steps(
         partial_point = partial_point_next;
     }
 
-    res
+    Ok(res)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::pedersen::batch_pedersen_hash;
+    use crate::pedersen::compute_hash;
+    use crate::pedersen::generate_pedersen_traces;
     use crate::pedersen::pedersen_hash;
+    use crate::pedersen::InstanceTrace;
+    use crate::pedersen::PedersenError;
+    use crate::pedersen::PedersenInstanceExt;
+    use crate::traces::TraceError;
     use ark_ff::MontFp as Fp;
+    use binary::PedersenInstance;
+    use ruint::uint;
 
     #[test]
     fn hash_example0_works() {
@@ -209,4 +347,116 @@ mod tests {
             output
         )
     }
+
+    #[test]
+    fn instance_trace_output_matches_hash() {
+        let instance = PedersenInstance {
+            index: 0,
+            a: uint!(
+                1740729136829561885683894917751815192814966525555656371386868611731128807883_U256
+            ),
+            b: uint!(
+                919869093895560023824014392670608914007817594969197822578496829435657368346_U256
+            ),
+        };
+
+        let trace = InstanceTrace::try_new(instance).unwrap();
+
+        let expected =
+            Fp!("1382171651951541052082654537810074813456022260470662576358627909045455537762");
+        assert_eq!(expected, trace.output_x());
+        assert!(trace.verify_output(expected));
+        assert!(!trace.verify_output(Fp!("1")));
+    }
+
+    #[test]
+    fn instance_trace_rejects_inputs_outside_field() {
+        let modulus = uint!(0x800000000000011000000000000000000000000000000000000000000000001_U256);
+        let instance = PedersenInstance {
+            index: 0,
+            a: modulus,
+            b: uint!(1_U256),
+        };
+
+        assert_eq!(
+            PedersenError::InputTooLarge { value: modulus },
+            InstanceTrace::try_new(instance).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn generate_pedersen_traces_reports_failing_instance() {
+        let modulus = uint!(0x800000000000011000000000000000000000000000000000000000000000001_U256);
+        let valid = PedersenInstance::new_empty(0);
+        let invalid = PedersenInstance {
+            index: 1,
+            a: uint!(1_U256),
+            b: modulus,
+        };
+
+        let traces = generate_pedersen_traces(&[valid, invalid]);
+
+        assert_eq!(2, traces.len());
+        assert!(traces[0].is_ok());
+        assert_eq!(
+            TraceError::Pedersen {
+                index: 1,
+                error: PedersenError::InputTooLarge { value: modulus },
+            },
+            *traces[1].as_ref().unwrap_err()
+        );
+    }
+
+    #[test]
+    fn compute_hash_matches_examples() {
+        // Examples source:
+        // https://github.com/starkware-libs/starkex-resources/blob/master/crypto/starkware/crypto/signature/signature_test_data.json#L87
+        let pairs = [
+            (
+                Fp!("1740729136829561885683894917751815192814966525555656371386868611731128807883"),
+                Fp!("919869093895560023824014392670608914007817594969197822578496829435657368346"),
+            ),
+            (
+                Fp!("2514830971251288745316508723959465399194546626755475650431255835704887319877"),
+                Fp!("3405079826265633459083097571806844574925613129801245865843963067353416465931"),
+            ),
+        ];
+        let expected = vec![
+            Fp!("1382171651951541052082654537810074813456022260470662576358627909045455537762"),
+            Fp!("2962565761002374879415469392216379291665599807391815720833106117558254791559"),
+        ];
+
+        assert_eq!(expected[0], compute_hash(pairs[0].0, pairs[0].1));
+        assert_eq!(expected[1], compute_hash(pairs[1].0, pairs[1].1));
+        assert_eq!(expected, batch_pedersen_hash(&pairs));
+    }
+
+    #[test]
+    fn instance_output_matches_hash() {
+        let instance = PedersenInstance {
+            index: 0,
+            a: uint!(
+                1740729136829561885683894917751815192814966525555656371386868611731128807883_U256
+            ),
+            b: uint!(
+                919869093895560023824014392670608914007817594969197822578496829435657368346_U256
+            ),
+        };
+        let modulus = uint!(0x800000000000011000000000000000000000000000000000000000000000001_U256);
+
+        assert_eq!(
+            Ok(Fp!(
+                "1382171651951541052082654537810074813456022260470662576358627909045455537762"
+            )),
+            instance.output()
+        );
+        assert_eq!(
+            Err(PedersenError::InputTooLarge { value: modulus }),
+            PedersenInstance {
+                b: modulus,
+                ..instance
+            }
+            .output()
+        );
+    }
 }
@@ -3,7 +3,9 @@
 
 use std::iter::zip;
 use ark_ff::MontFp as Fp;
+use binary::Memory;
 use binary::PoseidonInstance;
+use binary::Segment;
 use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
 pub mod params;
 pub mod periodic;
@@ -17,7 +19,11 @@ use crate::poseidon::params::FULL_ROUND_KEYS_2ND_HALF;
 use crate::poseidon::params::PARTIAL_ROUND_KEYS_OPTIMIZED;
 use crate::utils::Mat3x3;
 use ark_ff::Field;
+use ark_ff::PrimeField;
 use num_bigint::BigUint;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use ruint::aliases::U256;
 
 /// Stores the states within a full round
 #[derive(Clone, Copy, Debug)]
@@ -59,6 +65,14 @@ pub struct InstanceTrace {
     // pub partial_rounds_state: PartialRoundsState,
 }
 
+/// Generates the trace of each instance. Traces are generated in parallel if
+/// the "parallel" feature is enabled.
+pub fn generate_poseidon_traces(instances: &[PoseidonInstance]) -> Vec<InstanceTrace> {
+    ark_std::cfg_iter!(instances)
+        .map(|&instance| InstanceTrace::new(instance))
+        .collect()
+}
+
 impl InstanceTrace {
     pub fn new(instance: PoseidonInstance) -> Self {
         let input0 = Fp::from(BigUint::from(instance.input0));
@@ -115,6 +129,59 @@ impl InstanceTrace {
     }
 }
 
+/// Trace of the unoptimized Poseidon permutation of an instance's inputs.
+/// Holds the state after each full and each partial round.
+#[derive(Clone, Debug)]
+pub struct PoseidonTrace {
+    pub instance: PoseidonInstance,
+    /// States after each of the full rounds. The first half of the full rounds
+    /// come before the partial rounds and the second half after.
+    pub full_round_states: Vec<[Fp; 3]>,
+    /// States after each of the partial rounds
+    pub partial_round_states: Vec<[Fp; 3]>,
+}
+
+impl PoseidonTrace {
+    pub fn new(instance: PoseidonInstance) -> Self {
+        let mut state = [instance.input0, instance.input1, instance.input2]
+            .map(|input| Fp::from(BigUint::from(input)));
+        let mut round_keys = ROUND_KEYS.into_iter();
+        let mut full_round_states = Vec::with_capacity(NUM_FULL_ROUNDS);
+        let mut partial_round_states = Vec::with_capacity(NUM_PARTIAL_ROUNDS);
+        for round_key in round_keys.by_ref().take(NUM_FULL_ROUNDS / 2) {
+            state = full_round(state, round_key);
+            full_round_states.push(state);
+        }
+        for round_key in round_keys.by_ref().take(NUM_PARTIAL_ROUNDS) {
+            state = partial_round(state, round_key);
+            partial_round_states.push(state);
+        }
+        for round_key in round_keys {
+            state = full_round(state, round_key);
+            full_round_states.push(state);
+        }
+
+        Self {
+            instance,
+            full_round_states,
+            partial_round_states,
+        }
+    }
+
+    /// State after the last full round i.e. the permutation's output
+    pub fn final_state(&self) -> [Fp; 3] {
+        *self.full_round_states.last().unwrap()
+    }
+
+    /// Checks the trace has a state for every round and that the final state
+    /// matches the instance's output
+    pub fn verify(&self) -> bool {
+        self.full_round_states.len() == NUM_FULL_ROUNDS
+            && self.partial_round_states.len() == NUM_PARTIAL_ROUNDS
+            && self.final_state() == self.instance.output()
+    }
+}
+
 fn gen_half_full_round_states(
     mut state: [Fp; 3],
     round_keys: [[Fp; 3]; NUM_FULL_ROUNDS / 2],
@@ -147,42 +214,108 @@ fn gen_half_full_round_states(
     rounds
 }
 
+/// Hashes two field elements. Matches `poseidon_hash` in cairo-lang:
+/// <https://github.com/starkware-libs/cairo-lang/blob/v0.12.0/src/starkware/cairo/common/poseidon_hash.py>
+pub fn poseidon_hash(x: Fp, y: Fp) -> Fp {
+    permute([x, y, Fp::from(2u8)])[0]
+}
+
+/// Hashes a list of field elements with a sponge of rate 2. The input is
+/// padded with a one followed by a zero if needed. Matches
+/// `poseidon_hash_many` in cairo-lang.
+pub fn poseidon_hash_many(values: impl IntoIterator<Item = Fp>) -> Fp {
+    let mut values = values.into_iter().collect::<Vec<Fp>>();
+    values.push(Fp::ONE);
+    if values.len() % 2 == 1 {
+        values.push(Fp::ZERO);
+    }
+    let mut state = [Fp::ZERO; 3];
+    for chunk in values.chunks_exact(2) {
+        state[0] += chunk[0];
+        state[1] += chunk[1];
+        state = permute(state);
+    }
+    state[0]
+}
+
+/// Adds the Poseidon permutation to [PoseidonInstance] which is defined in the
+/// binary crate without it.
+pub trait PoseidonInstanceExt {
+    /// Output of the Poseidon builtin instance i.e. the permutation of its
+    /// inputs
+    fn output(&self) -> [Fp; 3];
+}
+
+impl PoseidonInstanceExt for PoseidonInstance {
+    fn output(&self) -> [Fp; 3] {
+        let input0 = Fp::from(BigUint::from(self.input0));
+        let input1 = Fp::from(BigUint::from(self.input1));
+        let input2 = Fp::from(BigUint::from(self.input2));
+        permute([input0, input1, input2])
+    }
+}
+
+/// Checks the cells of the instance in the poseidon memory segment hold the
+/// instance's inputs and outputs
+pub fn verify_in_memory<F: PrimeField>(
+    instance: &PoseidonInstance,
+    mem: &Memory<F>,
+    segment: Segment,
+) -> bool {
+    let addrs = instance.mem_addr(segment.begin_addr);
+    if addrs.output2 >= segment.stop_ptr {
+        return false;
+    }
+    let [output0, output1, output2] = instance.output().map(|v| U256::from(BigUint::from(v)));
+    [
+        (addrs.input0, instance.input0),
+        (addrs.input1, instance.input1),
+        (addrs.input2, instance.input2),
+        (addrs.output0, output0),
+        (addrs.output1, output1),
+        (addrs.output2, output2),
+    ]
+    .into_iter()
+    .all(|(address, value)| mem.get_checked(address.into()).map(|word| word.0) == Some(value))
+}
+
 /// Computes the Poseidon hash using StarkWare's parameters. Source:
 /// <https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/master/code/starkadperm_x5_256_3.sage>
 fn permute(input: [Fp; 3]) -> [Fp; 3] {
     let mut state = input;
-    let mut round = 0;
+    let mut round_keys = ROUND_KEYS.into_iter();
     // first full rounds
-    for _ in 0..NUM_FULL_ROUNDS / 2 {
-        // round constants, nonlinear layer, matrix multiplication
-        for (s, round_key) in zip(&mut state, ROUND_KEYS[round]) {
-            *s = (*s + round_key).pow([3]);
-        }
-        state = Mat3x3(MDS_MATRIX) * state;
-        round += 1;
+    for round_key in round_keys.by_ref().take(NUM_FULL_ROUNDS / 2) {
+        state = full_round(state, round_key);
     }
     // Middle partial rounds
-    for _ in 0..NUM_PARTIAL_ROUNDS {
-        // round constants, nonlinear layer, matrix multiplication
-        for (s, round_key) in zip(&mut state, ROUND_KEYS[round]) {
-            *s += round_key;
-        }
-        state[2] = state[2].pow([3]);
-        state = Mat3x3(MDS_MATRIX) * state;
-        round += 1;
+    for round_key in round_keys.by_ref().take(NUM_PARTIAL_ROUNDS) {
+        state = partial_round(state, round_key);
     }
     // last full rounds
-    for _ in 0..NUM_FULL_ROUNDS / 2 {
-        // round constants, nonlinear layer, matrix multiplication
-        for (s, round_key) in zip(&mut state, ROUND_KEYS[round]) {
-            *s = (*s + round_key).pow([3]);
-        }
-        state = Mat3x3(MDS_MATRIX) * state;
-        round += 1;
+    for round_key in round_keys {
+        state = full_round(state, round_key);
     }
     state
 }
 
+/// Round constants, nonlinear layer on the whole state, matrix multiplication
+fn full_round(mut state: [Fp; 3], round_key: [Fp; 3]) -> [Fp; 3] {
+    for (s, rk) in zip(&mut state, round_key) {
+        *s = (*s + rk).pow([3]);
+    }
+    Mat3x3(MDS_MATRIX) * state
+}
+
+/// Round constants, nonlinear layer on the last element, matrix multiplication
+fn partial_round(mut state: [Fp; 3], round_key: [Fp; 3]) -> [Fp; 3] {
+    for (s, rk) in zip(&mut state, round_key) {
+        *s += rk;
+    }
+    state[2] = state[2].pow([3]);
+    Mat3x3(MDS_MATRIX) * state
+}
+
 /// Computes the Poseidon hash using StarkWare's parameters. Source:
 /// <https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/master/code/starkadperm_x5_256_3.sage>
 // TODO: docs for optimized version
@@ -243,9 +376,20 @@ fn _calc_optimized_partial_round_keys() -> [[Fp; 3]; NUM_PARTIAL_ROUNDS] {
 
 #[cfg(test)]
 mod tests {
+    use crate::poseidon::PoseidonInstanceExt;
     use crate::poseidon::permute;
+    use crate::poseidon::poseidon_hash_many;
+    use crate::poseidon::verify_in_memory;
+    use crate::poseidon::PoseidonTrace;
     use ark_ff::MontFp as Fp;
     use ark_ff::Field;
+    use binary::types::MemoryAddress;
+    use binary::Memory;
+    use binary::PoseidonInstance;
+    use binary::Segment;
+    use binary::Word;
+    use num_bigint::BigUint;
+    use ruint::aliases::U256;
     use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
 
     #[test]
@@ -259,4 +403,99 @@ mod tests {
 
         assert_eq!(expected, permute([Fp::ZERO, Fp::ZERO, Fp::ZERO]));
     }
+
+    #[test]
+    fn hash_many_pads_odd_length_input() {
+        let [a, b, c] = [Fp::from(1u8), Fp::from(2u8), Fp::from(3u8)];
+
+        let state = permute([a, b, Fp::ZERO]);
+        let expected = permute([state[0] + c, state[1] + Fp::ONE, state[2]])[0];
+
+        assert_eq!(expected, poseidon_hash_many([a, b, c]));
+    }
+
+    #[test]
+    fn hash_many_pads_even_length_input() {
+        assert_eq!(
+            permute([Fp::ONE, Fp::ZERO, Fp::ZERO])[0],
+            poseidon_hash_many([])
+        );
+    }
+
+    #[test]
+    fn instance_output_matches_starkware_example() {
+        // Example from https://github.com/starkware-industries/poseidon
+        let expected = [
+            Fp!("3446325744004048536138401612021367625846492093718951375866996507163446763827"),
+            Fp!("1590252087433376791875644726012779423683501236913937337746052470473806035332"),
+            Fp!("867921192302518434283879514999422690776342565400001269945778456016268852423"),
+        ];
+
+        assert_eq!(expected, PoseidonInstance::new_empty(0).output());
+    }
+
+    #[test]
+    fn instance_is_verified_against_memory() {
+        let instance = PoseidonInstance {
+            index: 1,
+            input0: U256::from(1),
+            input1: U256::from(2),
+            input2: U256::from(3),
+        };
+        let segment = Segment::from_instances(MemoryAddress(100), 2, 6);
+        let addrs = instance.mem_addr(segment.begin_addr);
+        let [output0, output1, output2] = instance.output();
+        let cells = [
+            (addrs.input0, Fp::from(1u8)),
+            (addrs.input1, Fp::from(2u8)),
+            (addrs.input2, Fp::from(3u8)),
+            (addrs.output0, output0),
+            (addrs.output1, output1),
+            (addrs.output2, output2),
+        ];
+        let word = |v: Fp| Word::<Fp>::new(U256::from(BigUint::from(v)));
+        let mut memory = cells
+            .into_iter()
+            .map(|(address, value)| (address.into(), word(value)))
+            .collect::<Memory<Fp>>();
+        assert!(verify_in_memory(&instance, &memory, segment));
+        assert!(!verify_in_memory(
+            &instance,
+            &memory,
+            Segment::from_instances(MemoryAddress(100), 1, 6)
+        ));
+
+        memory.extend([(addrs.output1.into(), word(output1 + Fp::ONE))]);
+
+        assert!(!verify_in_memory(&instance, &memory, segment));
+    }
+
+    #[test]
+    fn poseidon_trace_has_state_for_each_round() {
+        let instance = PoseidonInstance {
+            index: 0,
+            input0: U256::from(1),
+            input1: U256::from(2),
+            input2: U256::from(3),
+        };
+
+        let trace = PoseidonTrace::new(instance);
+
+        assert_eq!(8, trace.full_round_states.len());
+        assert_eq!(83, trace.partial_round_states.len());
+        assert_eq!(instance.output(), trace.final_state());
+        assert!(trace.verify());
+    }
+
+    #[test]
+    fn poseidon_trace_rejects_tampered_states() {
+        let mut trace = PoseidonTrace::new(PoseidonInstance::new_empty(0));
+        let mut truncated = trace.clone();
+        truncated.partial_round_states.pop();
+
+        trace.full_round_states[7][0] += Fp::ONE;
+
+        assert!(!trace.verify());
+        assert!(!truncated.verify());
+    }
 }
@@ -25,3 +25,65 @@ impl<const NUM_PARTS: usize> InstanceTrace<NUM_PARTS> {
         Self { instance, parts }
     }
 }
+
+/// Trace of a range check instance with its value split into all 16 of its
+/// 16-bit limbs, least significant limb first
+#[derive(Clone, Debug)]
+pub struct RangeCheckTrace {
+    pub instance: RangeCheckInstance,
+    pub limbs: [u16; 16],
+}
+
+impl RangeCheckTrace {
+    pub fn new(instance: RangeCheckInstance) -> Self {
+        let limbs = instance.split_into_limbs();
+        Self { instance, limbs }
+    }
+
+    /// Checks the limbs recompose into the instance's value and that the value
+    /// is within `[rc_min, rc_max]` for each of its range checked parts
+    pub fn verify_constraints(&self, rc_min: u16, rc_max: u16) -> bool {
+        let recomposed = RangeCheckInstance::from_limbs(self.instance.index, self.limbs);
+        recomposed.value == self.instance.value && recomposed.is_valid_for_range(rc_min, rc_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeCheckTrace;
+    use binary::RangeCheckInstance;
+    use ruint::aliases::U256;
+    use ruint::uint;
+
+    #[test]
+    fn range_check_trace_limbs_match_value() {
+        let instance = RangeCheckInstance {
+            index: 3,
+            value: uint!(0x0004_0003_0002_0001_U256),
+        };
+
+        let trace = RangeCheckTrace::new(instance);
+
+        assert_eq!([1, 2, 3, 4], trace.limbs[..4]);
+        assert!(trace.limbs[4..].iter().all(|&limb| limb == 0));
+        assert!(trace.verify_constraints(0, u16::MAX));
+    }
+
+    #[test]
+    fn range_check_trace_rejects_invalid_traces() {
+        let instance = RangeCheckInstance {
+            index: 0,
+            value: uint!(0x0100_0010_U256),
+        };
+        let large_instance = RangeCheckInstance {
+            index: 1,
+            value: U256::from(1) << 128,
+        };
+        let mut tampered = RangeCheckTrace::new(instance);
+        tampered.limbs[1] += 1;
+
+        assert!(!RangeCheckTrace::new(instance).verify_constraints(0, 0x00ff));
+        assert!(!RangeCheckTrace::new(large_instance).verify_constraints(0, u16::MAX));
+        assert!(!tampered.verify_constraints(0, u16::MAX));
+    }
+}
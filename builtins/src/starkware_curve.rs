@@ -0,0 +1,44 @@
+//! StarkWare's elliptic curve used by the pedersen, ECDSA and EC op builtins.
+//! Curve arithmetic done with these types is compatible with the prover.
+
+use ark_ec::short_weierstrass::Affine;
+use ark_ec::short_weierstrass::SWCurveConfig;
+pub use crate::utils::curve::calculate_slope;
+pub use crate::utils::curve::Fr;
+pub use crate::utils::curve::StarkwareCurve as Curve;
+pub use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+
+pub type StarkCurveAffine = Affine<Curve>;
+
+pub const GENERATOR: StarkCurveAffine = Curve::GENERATOR;
+
+/// Checks `(x, y)` satisfies the curve equation `y^2 = x^3 + a * x + b`
+pub fn is_on_curve(x: Fp, y: Fp) -> bool {
+    StarkCurveAffine::new_unchecked(x, y).is_on_curve()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::calculate_slope;
+    use super::is_on_curve;
+    use super::Fp;
+    use super::GENERATOR;
+    use ark_ec::CurveGroup;
+    use ark_ff::Field;
+
+    #[test]
+    fn generator_is_on_curve() {
+        assert!(is_on_curve(GENERATOR.x, GENERATOR.y));
+        assert!(!is_on_curve(GENERATOR.x, GENERATOR.y + Fp::ONE));
+    }
+
+    #[test]
+    fn slope_between_points_matches_addition() {
+        let p = GENERATOR;
+        let q = (p + p).into_affine();
+        let slope = calculate_slope(p, q).unwrap();
+
+        let sum = (p + q).into_affine();
+        assert_eq!(slope.square() - p.x - q.x, sum.x);
+    }
+}
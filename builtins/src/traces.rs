@@ -0,0 +1,130 @@
+//! Trace generation for all builtin instances of a program
+
+use crate::ecdsa;
+use crate::ecdsa::EcdsaTraceValue;
+use crate::ecdsa::InvalidSignature;
+use crate::pedersen;
+use crate::pedersen::PedersenError;
+use crate::poseidon;
+use binary::AirPrivateInput;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Errors that prevent generating the trace of a builtin instance
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceError {
+    InvalidEcdsaSignature {
+        index: u32,
+        error: InvalidSignature,
+    },
+    /// A value of an ECDSA instance trace doesn't match its recomputation
+    EcdsaMismatch {
+        index: u32,
+        value: EcdsaTraceValue,
+    },
+    Pedersen {
+        index: u32,
+        error: PedersenError,
+    },
+}
+
+impl Display for TraceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidEcdsaSignature { index, error } => {
+                write!(
+                    f,
+                    "ECDSA instance {index} has an invalid signature: {error}"
+                )
+            }
+            Self::EcdsaMismatch { index, value } => {
+                write!(
+                    f,
+                    "ECDSA instance {index} has an inconsistent {value} value"
+                )
+            }
+            Self::Pedersen { index, error } => {
+                write!(f, "Pedersen instance {index} is invalid: {error}")
+            }
+        }
+    }
+}
+
+impl Error for TraceError {}
+
+/// Instance traces of each builtin. Lives here rather than in the binary crate
+/// since the binary crate doesn't implement the builtins.
+#[derive(Clone, Debug)]
+pub struct BuiltinTraces {
+    pub ecdsa: Vec<ecdsa::InstanceTrace>,
+    pub pedersen: Vec<pedersen::InstanceTrace>,
+    pub poseidon: Vec<poseidon::InstanceTrace>,
+}
+
+/// Generates the traces of all builtin instances in the private input.
+/// Returns the first error if any instance is invalid.
+pub fn compute_builtin_traces(private: &AirPrivateInput) -> Result<BuiltinTraces, TraceError> {
+    Ok(BuiltinTraces {
+        ecdsa: ecdsa::generate_ecdsa_traces(&private.ecdsa)
+            .into_iter()
+            .collect::<Result<_, _>>()?,
+        pedersen: pedersen::generate_pedersen_traces(&private.pedersen)
+            .into_iter()
+            .collect::<Result<_, _>>()?,
+        poseidon: poseidon::generate_poseidon_traces(&private.poseidon),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compute_builtin_traces;
+    use super::TraceError;
+    use crate::ecdsa;
+    use crate::ecdsa::InvalidSignature;
+    use binary::AirPrivateInput;
+    use binary::PedersenInstance;
+    use binary::PoseidonInstance;
+    use ruint::aliases::U256;
+    use std::path::PathBuf;
+
+    fn private_input() -> AirPrivateInput {
+        AirPrivateInput {
+            trace_path: PathBuf::new(),
+            memory_path: PathBuf::new(),
+            pedersen: vec![PedersenInstance::new_empty(0)],
+            range_check: Vec::new(),
+            ecdsa: vec![ecdsa::InstanceTrace::new_dummy(0).instance],
+            bitwise: Vec::new(),
+            ec_op: Vec::new(),
+            poseidon: (0..3).map(PoseidonInstance::new_empty).collect(),
+            keccak: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn builtin_traces_are_computed_for_each_instance() {
+        let traces = compute_builtin_traces(&private_input()).unwrap();
+
+        assert_eq!(1, traces.ecdsa.len());
+        assert_eq!(1, traces.pedersen.len());
+        assert_eq!(3, traces.poseidon.len());
+        assert_eq!(2, traces.poseidon[2].instance.index);
+    }
+
+    #[test]
+    fn builtin_traces_fail_on_invalid_instance() {
+        let mut private_input = private_input();
+        let mut tampered = private_input.ecdsa[0];
+        tampered.index = 1;
+        tampered.signature.r += U256::from(1);
+        private_input.ecdsa.push(tampered);
+
+        assert_eq!(
+            TraceError::InvalidEcdsaSignature {
+                index: 1,
+                error: InvalidSignature::VerificationFailed,
+            },
+            compute_builtin_traces(&private_input).unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Helpers for inspecting the binary trace and memory files outputted by
+//! `cairo-run`.
+
+use ark_ff::PrimeField;
+use binary::AirPublicInput;
+use binary::Memory;
+use binary::MemorySegments;
+use binary::RegisterStates;
+use binary::SegmentAddressMapper;
+use binary::SegmentKind;
+use serde_json::json;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Number of bytes used to encode a single register state (pc, ap and fp)
+const TRACE_ENTRY_BYTES: usize = 3 * 8;
+
+/// Number of bytes used to encode a memory address
+const MEMORY_ADDRESS_BYTES: usize = 8;
+
+#[derive(Clone, Copy, Debug)]
+pub enum SegmentOption {
+    All,
+    Program,
+    Execution,
+    Output,
+    Pedersen,
+    RangeCheck,
+    Ecdsa,
+    Bitwise,
+    EcOp,
+    Keccak,
+    Poseidon,
+}
+
+impl SegmentOption {
+    /// Returns the address range `[start, end)` of the segment
+    fn address_range(self, segments: &MemorySegments) -> Result<(usize, usize), String> {
+        let (kind, name) = match self {
+            Self::All => return Ok((0, usize::MAX)),
+            Self::Program => (SegmentKind::Program, "program"),
+            Self::Execution => (SegmentKind::Execution, "execution"),
+            Self::Output => (SegmentKind::Output, "output"),
+            Self::Pedersen => (SegmentKind::Pedersen, "pedersen"),
+            Self::RangeCheck => (SegmentKind::RangeCheck, "range_check"),
+            Self::Ecdsa => (SegmentKind::Ecdsa, "ecdsa"),
+            Self::Bitwise => (SegmentKind::Bitwise, "bitwise"),
+            Self::EcOp => (SegmentKind::EcOp, "ec_op"),
+            Self::Keccak => (SegmentKind::Keccak, "keccak"),
+            Self::Poseidon => (SegmentKind::Poseidon, "poseidon"),
+        };
+        SegmentAddressMapper::new(segments)
+            .bounds(kind)
+            .map(|(begin, end)| (begin.into(), end.into()))
+            .ok_or_else(|| format!("the air public input has no {name} segment"))
+    }
+}
+
+impl FromStr for SegmentOption {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "all" => Self::All,
+            "program" => Self::Program,
+            "execution" => Self::Execution,
+            "output" => Self::Output,
+            "pedersen" => Self::Pedersen,
+            "range_check" => Self::RangeCheck,
+            "ecdsa" => Self::Ecdsa,
+            "bitwise" => Self::Bitwise,
+            "ec_op" => Self::EcOp,
+            "keccak" => Self::Keccak,
+            "poseidon" => Self::Poseidon,
+            _ => return Err(format!("unknown segment '{s}'")),
+        })
+    }
+}
+
+/// Prime fields that memory files can be parsed over
+#[derive(Clone, Copy, Debug)]
+pub enum FieldOption {
+    Starknet,
+    #[cfg(feature = "experimental_claims")]
+    Goldilocks,
+}
+
+impl FromStr for FieldOption {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "starknet" => Self::Starknet,
+            #[cfg(feature = "experimental_claims")]
+            "goldilocks" => Self::Goldilocks,
+            _ => return Err(format!("unsupported field '{s}'")),
+        })
+    }
+}
+
+/// Output formats of the inspect-memory command
+#[derive(Clone, Copy, Debug)]
+pub enum MemoryFormatOption {
+    Table,
+    Json,
+    Hex,
+}
+
+impl FromStr for MemoryFormatOption {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "table" => Self::Table,
+            "json" => Self::Json,
+            "hex" => Self::Hex,
+            _ => return Err(format!("unsupported format '{s}'")),
+        })
+    }
+}
+
+/// Checks the file exists and that its size is a multiple of `entry_bytes`
+fn check_file_len(path: &Path, kind: &str, entry_bytes: usize) -> Result<(), String> {
+    let len = fs::metadata(path)
+        .map_err(|err| format!("could not read {kind} file {}: {err}", path.display()))?
+        .len() as usize;
+    if len % entry_bytes != 0 {
+        return Err(format!(
+            "{kind} file {} is truncated or malformed: expected a multiple of {entry_bytes} bytes \
+             but the file has {len} bytes",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+pub fn inspect_trace(trace_path: &Path, max_rows: usize, json: bool) -> Result<(), String> {
+    check_file_len(trace_path, "trace", TRACE_ENTRY_BYTES)?;
+    let trace_file = File::open(trace_path).map_err(|err| err.to_string())?;
+    let register_states = RegisterStates::from_reader(trace_file).map_err(|err| err.to_string())?;
+    let rows = register_states.iter().take(max_rows).enumerate();
+
+    if json {
+        let rows = rows
+            .map(|(step, s)| json!({ "step": step, "pc": s.pc, "ap": s.ap, "fp": s.fp }))
+            .collect::<Vec<_>>();
+        let output = json!({ "num_steps": register_states.len(), "rows": rows });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return Ok(());
+    }
+
+    print!("{}", register_states.display_range(0, max_rows));
+    if register_states.len() > max_rows {
+        println!("... {} more rows", register_states.len() - max_rows);
+    }
+    Ok(())
+}
+
+pub fn inspect_memory<F: PrimeField>(
+    memory_path: &Path,
+    air_public_input_path: Option<&Path>,
+    segment: SegmentOption,
+    format: MemoryFormatOption,
+) -> Result<(), String> {
+    let word_bytes = F::MODULUS_BIT_SIZE.next_multiple_of(8) as usize / 8;
+    check_file_len(memory_path, "memory", MEMORY_ADDRESS_BYTES + word_bytes)?;
+
+    let (start, end) = match segment {
+        SegmentOption::All => (0, usize::MAX),
+        segment => {
+            let air_public_input_path = air_public_input_path
+                .ok_or("--air-public-input is required to locate memory segments")?;
+            let air_public_input = AirPublicInput::<F>::from_path(air_public_input_path)
+                .map_err(|err| format!("could not load public input: {err}"))?;
+            segment.address_range(&air_public_input.memory_segments)?
+        }
+    };
+
+    let memory_file = File::open(memory_path).map_err(|err| err.to_string())?;
+    let memory = Memory::<F>::from_reader(memory_file).map_err(|err| err.to_string())?;
+    if let MemoryFormatOption::Hex = format {
+        return memory
+            .hex_dump(&mut io::stdout().lock(), start, end)
+            .map_err(|err| err.to_string());
+    }
+
+    let cells = memory
+        .iter()
+        .enumerate()
+        .take(end)
+        .skip(start)
+        .filter_map(|(address, word)| word.as_ref().map(|word| (address, word.0)));
+
+    if let MemoryFormatOption::Json = format {
+        let cells = cells
+            .map(|(address, value)| json!({ "address": address, "value": format!("{value:#x}") }))
+            .collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&cells).unwrap());
+        return Ok(());
+    }
+
+    for (address, value) in cells {
+        println!("{address:>10}  {value:#066x}");
+    }
+    Ok(())
+}
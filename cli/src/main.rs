@@ -8,7 +8,11 @@ use binary::CompiledProgram;
 use binary::Layout;
 use binary::Memory;
 use binary::RegisterStates;
+use inspect::FieldOption;
+use inspect::MemoryFormatOption;
+use inspect::SegmentOption;
 use layouts::CairoWitness;
+use ministark::air::AirConfig;
 use ministark::stark::Stark;
 use ministark::Proof;
 use ministark::ProofOptions;
@@ -21,6 +25,9 @@ use std::path::PathBuf;
 use std::time::Instant;
 use structopt::StructOpt;
 
+mod inspect;
+mod memory_estimate;
+
 /// Modulus of Starkware's 252-bit prime field used for Cairo
 const STARKWARE_PRIME_HEX_STR: &str =
     "0x800000000000011000000000000000000000000000000000000000000000001";
@@ -32,38 +39,123 @@ const GOLDILOCKS_PRIME_HEX_STR: &str = "0xffffffff00000001";
 #[derive(StructOpt, Debug)]
 #[structopt(name = "sandstorm", about = "cairo prover")]
 struct SandstormOptions {
+    /// Deprecated: pass --program to prove or verify instead
     #[structopt(long, parse(from_os_str))]
-    program: PathBuf,
+    program: Option<PathBuf>,
+    /// Deprecated: pass --air-public-input to prove or verify instead
     #[structopt(long, parse(from_os_str))]
-    air_public_input: PathBuf,
+    air_public_input: Option<PathBuf>,
     #[structopt(subcommand)]
     command: Command,
 }
 
+/// Program and public input that make up the claim being proven or verified.
+/// Both can also be given before the subcommand which is how older versions
+/// of the CLI took them.
+#[derive(StructOpt, Debug)]
+struct ClaimOptions {
+    #[structopt(long, parse(from_os_str))]
+    program: Option<PathBuf>,
+    #[structopt(long, parse(from_os_str))]
+    air_public_input: Option<PathBuf>,
+}
+
+impl ClaimOptions {
+    /// Falls back to the paths given before the subcommand. Errors if a path
+    /// is given in neither place.
+    fn or_global(
+        self,
+        program: Option<PathBuf>,
+        air_public_input: Option<PathBuf>,
+    ) -> Result<(PathBuf, PathBuf), String> {
+        let program = self
+            .program
+            .or(program)
+            .ok_or("missing required argument --program")?;
+        let air_public_input = self
+            .air_public_input
+            .or(air_public_input)
+            .ok_or("missing required argument --air-public-input")?;
+        Ok((program, air_public_input))
+    }
+}
+
+#[derive(StructOpt, Debug)]
+struct ProveOptions {
+    #[structopt(long, parse(from_os_str))]
+    output: PathBuf,
+    #[structopt(long, parse(from_os_str))]
+    air_private_input: PathBuf,
+    // TODO: add validation to the proof options
+    #[structopt(long, default_value = "65")]
+    num_queries: u8,
+    #[structopt(long, default_value = "2")]
+    lde_blowup_factor: u8,
+    #[structopt(long, default_value = "16")]
+    proof_of_work_bits: u8,
+    #[structopt(long, default_value = "8")]
+    fri_folding_factor: u8,
+    #[structopt(long, default_value = "16")]
+    fri_max_remainder_coeffs: u8,
+}
+
+#[derive(StructOpt, Debug)]
+struct VerifyOptions {
+    #[structopt(long, parse(from_os_str))]
+    proof: PathBuf,
+    #[structopt(long, default_value = "80")]
+    required_security_bits: u8,
+}
+
+/// Commands that need a claim
+#[derive(Debug)]
+enum ClaimCommand {
+    Prove(ProveOptions),
+    Verify(VerifyOptions),
+}
+
 #[derive(StructOpt, Debug)]
 enum Command {
     Prove {
-        #[structopt(long, parse(from_os_str))]
-        output: PathBuf,
-        #[structopt(long, parse(from_os_str))]
-        air_private_input: PathBuf,
-        // TODO: add validation to the proof options
-        #[structopt(long, default_value = "65")]
-        num_queries: u8,
-        #[structopt(long, default_value = "2")]
-        lde_blowup_factor: u8,
-        #[structopt(long, default_value = "16")]
-        proof_of_work_bits: u8,
-        #[structopt(long, default_value = "8")]
-        fri_folding_factor: u8,
-        #[structopt(long, default_value = "16")]
-        fri_max_remainder_coeffs: u8,
+        #[structopt(flatten)]
+        claim: ClaimOptions,
+        #[structopt(flatten)]
+        options: ProveOptions,
     },
     Verify {
+        #[structopt(flatten)]
+        claim: ClaimOptions,
+        #[structopt(flatten)]
+        options: VerifyOptions,
+    },
+    /// Prints the register states stored in a binary trace file
+    InspectTrace {
+        #[structopt(long, parse(from_os_str))]
+        trace: PathBuf,
+        #[structopt(long, default_value = "100")]
+        max_rows: usize,
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Prints a hex dump of a binary memory file
+    InspectMemory {
         #[structopt(long, parse(from_os_str))]
-        proof: PathBuf,
-        #[structopt(long, default_value = "80")]
-        required_security_bits: u8,
+        memory: PathBuf,
+        #[structopt(long, parse(from_os_str))]
+        air_public_input: Option<PathBuf>,
+        /// One of: all, program, execution, output, pedersen, range_check,
+        /// ecdsa, bitwise, ec_op, keccak, poseidon. All segments other than
+        /// "all" require --air-public-input
+        #[structopt(long, default_value = "all")]
+        segment: SegmentOption,
+        #[structopt(long, default_value = "starknet")]
+        field: FieldOption,
+        /// One of: table, json, hex
+        #[structopt(long, default_value = "table")]
+        format: MemoryFormatOption,
+        /// Shorthand for --format json
+        #[structopt(long)]
+        json: bool,
     },
 }
 
@@ -71,31 +163,97 @@ fn main() {
     // read command-line args
     let SandstormOptions {
         program,
-        air_public_input,
+        air_public_input: global_air_public_input,
         command,
     } = SandstormOptions::from_args();
 
-    let program_file = File::open(program).expect("could not open program file");
-    let air_public_input_file = File::open(air_public_input).expect("could not open public input");
-    let program_json: serde_json::Value = serde_json::from_reader(program_file).unwrap();
-    let prime: String = serde_json::from_value(program_json["prime"].clone()).unwrap();
+    match command {
+        Command::InspectTrace {
+            trace,
+            max_rows,
+            json,
+        } => exit_on_err(inspect::inspect_trace(&trace, max_rows, json)),
+        Command::InspectMemory {
+            memory,
+            air_public_input,
+            segment,
+            field,
+            format,
+            json,
+        } => {
+            let air_public_input = air_public_input.or(global_air_public_input);
+            let air_public_input = air_public_input.as_deref();
+            let format = if json {
+                MemoryFormatOption::Json
+            } else {
+                format
+            };
+            exit_on_err(match field {
+                FieldOption::Starknet => {
+                    use p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+                    inspect::inspect_memory::<Fp>(&memory, air_public_input, segment, format)
+                }
+                #[cfg(feature = "experimental_claims")]
+                FieldOption::Goldilocks => {
+                    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+                    inspect::inspect_memory::<Fp>(&memory, air_public_input, segment, format)
+                }
+            })
+        }
+        Command::Prove { claim, options } => exit_on_err(
+            claim
+                .or_global(program, global_air_public_input)
+                .and_then(|claim| prove_or_verify(claim, ClaimCommand::Prove(options))),
+        ),
+        Command::Verify { claim, options } => exit_on_err(
+            claim
+                .or_global(program, global_air_public_input)
+                .and_then(|claim| prove_or_verify(claim, ClaimCommand::Verify(options))),
+        ),
+    }
+}
+
+fn exit_on_err(res: Result<(), String>) {
+    if let Err(err) = res {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn prove_or_verify(
+    (program, air_public_input): (PathBuf, PathBuf),
+    command: ClaimCommand,
+) -> Result<(), String> {
+    let program_file =
+        File::open(program).map_err(|err| format!("could not open program file: {err}"))?;
+    let air_public_input_file = File::open(air_public_input)
+        .map_err(|err| format!("could not open public input: {err}"))?;
+    let program_json: serde_json::Value = serde_json::from_reader(program_file)
+        .map_err(|err| format!("could not parse program: {err}"))?;
+    let prime: String = serde_json::from_value(program_json["prime"].clone())
+        .map_err(|err| format!("could not parse program prime: {err}"))?;
 
     match prime.to_lowercase().as_str() {
         STARKWARE_PRIME_HEX_STR => {
             use p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
-            let program: CompiledProgram<Fp> = serde_json::from_value(program_json).unwrap();
-            let air_public_input: AirPublicInput<Fp> =
-                serde_json::from_reader(air_public_input_file).unwrap();
+            let program: CompiledProgram<Fp> = serde_json::from_value(program_json)
+                .map_err(|err| format!("could not parse program: {err}"))?;
+            let air_public_input = AirPublicInput::<Fp>::from_reader(air_public_input_file)
+                .map_err(|err| format!("could not parse public input: {err}"))?;
+            air_public_input
+                .validate_public_memory_size()
+                .map_err(|err| format!("invalid public memory: {err}"))?;
+            warn_if_low_memory(&command, &air_public_input);
             match air_public_input.layout {
                 Layout::Starknet => {
                     use claims::starknet::EthVerifierClaim;
                     let claim = EthVerifierClaim::new(program, air_public_input);
-                    execute_command(command, claim);
+                    execute_command(command, claim)?;
                 }
                 Layout::Recursive => {
                     use claims::recursive::CairoVerifierClaim;
                     let claim = CairoVerifierClaim::new(program, air_public_input);
-                    execute_command(command, claim);
+                    execute_command(command, claim)?;
                 }
                 _ => unimplemented!(),
             }
@@ -109,9 +267,14 @@ fn main() {
             use p18446744069414584321::ark::Fp;
             use p18446744069414584321::ark::Fq3;
             use sandstorm::CairoClaim;
-            let program: CompiledProgram<Fp> = serde_json::from_value(program_json).unwrap();
-            let air_public_input: AirPublicInput<Fp> =
-                serde_json::from_reader(air_public_input_file).unwrap();
+            let program: CompiledProgram<Fp> = serde_json::from_value(program_json)
+                .map_err(|err| format!("could not parse program: {err}"))?;
+            let air_public_input = AirPublicInput::<Fp>::from_reader(air_public_input_file)
+                .map_err(|err| format!("could not parse public input: {err}"))?;
+            air_public_input
+                .validate_public_memory_size()
+                .map_err(|err| format!("invalid public memory: {err}"))?;
+            warn_if_low_memory(&command, &air_public_input);
             match air_public_input.layout {
                 Layout::Plain => {
                     type A = layouts::plain::AirConfig<Fp, Fq3>;
@@ -120,7 +283,7 @@ fn main() {
                     type P = PublicCoinImpl<Fq3, Sha256HashFn>;
                     type C = CairoClaim<Fp, A, T, M, P>;
                     let claim = C::new(program, air_public_input);
-                    execute_command(command, claim);
+                    execute_command(command, claim)?;
                 }
                 Layout::Starknet => {
                     unimplemented!("'starknet' layout does not support Goldilocks field")
@@ -133,14 +296,38 @@ fn main() {
         }
         prime => unimplemented!("prime field p={prime} is not supported yet. Consider enabling the \"experimental_claims\" feature."),
     }
+    Ok(())
+}
+
+/// Warns if proving is likely to run out of memory
+fn warn_if_low_memory<F: Field>(command: &ClaimCommand, air_public_input: &AirPublicInput<F>) {
+    if let ClaimCommand::Prove(ProveOptions {
+        lde_blowup_factor, ..
+    }) = command
+    {
+        let Some(log_n_steps) = air_public_input.n_steps.checked_ilog2() else {
+            return;
+        };
+        let estimate = memory_estimate::estimate_memory_bytes(
+            log_n_steps,
+            air_public_input.layout,
+            (*lde_blowup_factor).into(),
+        );
+        if let Err(err) = memory_estimate::check_available_memory(estimate) {
+            eprintln!("warning: {err}");
+        }
+    }
 }
 
 fn execute_command<Fp: PrimeField, Claim: Stark<Fp = Fp, Witness = CairoWitness<Fp>>>(
-    command: Command,
+    command: ClaimCommand,
     claim: Claim,
-) {
+) -> Result<(), String>
+where
+    Claim::AirConfig: AirConfig<PublicInputs = AirPublicInput<Fp>>,
+{
     match command {
-        Command::Prove {
+        ClaimCommand::Prove(ProveOptions {
             output,
             air_private_input,
             num_queries,
@@ -148,7 +335,7 @@ fn execute_command<Fp: PrimeField, Claim: Stark<Fp = Fp, Witness = CairoWitness<
             proof_of_work_bits,
             fri_folding_factor,
             fri_max_remainder_coeffs,
-        } => {
+        }) => {
             let options = ProofOptions::new(
                 num_queries,
                 lde_blowup_factor,
@@ -158,10 +345,10 @@ fn execute_command<Fp: PrimeField, Claim: Stark<Fp = Fp, Witness = CairoWitness<
             );
             prove(options, &air_private_input, &output, claim)
         }
-        Command::Verify {
+        ClaimCommand::Verify(VerifyOptions {
             proof,
             required_security_bits,
-        } => verify(required_security_bits, &proof, claim),
+        }) => verify(required_security_bits, &proof, claim),
     }
 }
 
@@ -169,12 +356,15 @@ fn verify<Claim: Stark<Fp = impl Field>>(
     required_security_bits: u8,
     proof_path: &PathBuf,
     claim: Claim,
-) {
-    let proof_bytes = fs::read(proof_path).unwrap();
-    let proof = Proof::<Claim>::deserialize_compressed(&*proof_bytes).unwrap();
+) -> Result<(), String> {
+    let proof_bytes =
+        fs::read(proof_path).map_err(|err| format!("could not read proof file: {err}"))?;
+    let proof = Proof::<Claim>::deserialize_compressed(&*proof_bytes)
+        .map_err(|err| format!("could not parse proof: {err}"))?;
     let now = Instant::now();
     claim.verify(proof, required_security_bits.into()).unwrap();
     println!("Proof verified in: {:?}", now.elapsed());
+    Ok(())
 }
 
 fn prove<Fp: PrimeField, Claim: Stark<Fp = Fp, Witness = CairoWitness<Fp>>>(
@@ -182,18 +372,33 @@ fn prove<Fp: PrimeField, Claim: Stark<Fp = Fp, Witness = CairoWitness<Fp>>>(
     private_input_path: &PathBuf,
     output_path: &PathBuf,
     claim: Claim,
-) {
-    let private_input_file =
-        File::open(private_input_path).expect("could not open private input file");
-    let private_input: AirPrivateInput = serde_json::from_reader(private_input_file).unwrap();
+) -> Result<(), String>
+where
+    Claim::AirConfig: AirConfig<PublicInputs = AirPublicInput<Fp>>,
+{
+    let private_input = AirPrivateInput::from_path(private_input_path)
+        .map_err(|err| format!("could not load private input: {err}"))?;
 
     let trace_path = &private_input.trace_path;
-    let trace_file = File::open(trace_path).expect("could not open trace file");
-    let register_states = RegisterStates::from_reader(trace_file);
+    let trace_file =
+        File::open(trace_path).map_err(|err| format!("could not open trace file: {err}"))?;
+    let register_states = RegisterStates::from_reader(trace_file)
+        .map_err(|err| format!("could not parse trace file: {err}"))?;
+
+    private_input
+        .validate_against_public(&claim.get_public_inputs(), &register_states)
+        .map_err(|err| format!("private input doesn't match the public input: {err}"))?;
+    builtins::ecdsa::validate_signatures(&private_input.ecdsa)
+        .map_err(|err| format!("invalid ECDSA instance: {err}"))?;
 
     let memory_path = &private_input.memory_path;
-    let memory_file = File::open(memory_path).expect("could not open memory file");
-    let memory = Memory::from_reader(memory_file);
+    let memory_file =
+        File::open(memory_path).map_err(|err| format!("could not open memory file: {err}"))?;
+    let memory = Memory::from_reader(memory_file)
+        .map_err(|err| format!("could not parse memory file: {err}"))?;
+    memory
+        .validate_instructions(&register_states)
+        .map_err(|err| format!("memory has an invalid instruction: {err}"))?;
 
     let witness = CairoWitness::new(private_input, register_states, memory);
 
@@ -210,4 +415,5 @@ fn prove<Fp: PrimeField, Claim: Stark<Fp = Fp, Witness = CairoWitness<Fp>>>(
     f.write_all(proof_bytes.as_slice()).unwrap();
     f.flush().unwrap();
     println!("Proof written to {}", output_path.as_path().display());
+    Ok(())
 }
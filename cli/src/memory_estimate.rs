@@ -0,0 +1,121 @@
+//! Rough estimates of the RAM needed to generate a proof. Used to warn
+//! before starting a proof that is likely to run out of memory.
+
+use binary::Layout;
+use ministark::air::AirConfig;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+
+/// Number of trace rows per Cairo step. Same for all supported layouts.
+const CYCLE_HEIGHT: usize = 16;
+
+/// Number of bytes in a Merkle tree node
+const DIGEST_BYTES: usize = 32;
+
+/// Number of Merkle trees built by the prover i.e. one for each of the base
+/// trace, extension trace and composition polynomial.
+const NUM_MERKLE_TREES: usize = 3;
+
+/// Fraction of the available memory the estimate can use before warning
+const MAX_AVAILABLE_MEMORY_FRACTION: f64 = 0.8;
+
+/// Returns (number of base columns, number of extension columns)
+fn num_columns(layout: Layout) -> (usize, usize) {
+    match layout {
+        Layout::Plain => (
+            layouts::plain::NUM_BASE_COLUMNS,
+            layouts::plain::NUM_EXTENSION_COLUMNS,
+        ),
+        Layout::Starknet => (
+            layouts::starknet::AirConfig::NUM_BASE_COLUMNS,
+            layouts::starknet::AirConfig::NUM_EXTENSION_COLUMNS,
+        ),
+        Layout::Recursive => (
+            layouts::recursive::AirConfig::NUM_BASE_COLUMNS,
+            layouts::recursive::AirConfig::NUM_EXTENSION_COLUMNS,
+        ),
+        layout => unimplemented!("layout {layout} is not supported yet"),
+    }
+}
+
+/// Returns (base field bytes, extension field bytes)
+const fn field_bytes(layout: Layout) -> (usize, usize) {
+    match layout {
+        // Goldilocks field and its cubic extension
+        Layout::Plain => (8, 24),
+        // Starkware's 252-bit field. No extension field is used
+        _ => (32, 32),
+    }
+}
+
+/// Estimates the number of bytes needed to prove a trace with `2^log_n_steps`
+/// steps. Only the largest allocations are counted:
+/// * trace table: all columns over the trace domain
+/// * LDE table: all columns over the LDE domain
+/// * Merkle trees: `2 * lde_domain_size` digests for each committed table
+/// * FRI layers: each layer is at least halved so all layers are bounded by
+///   twice the LDE domain size.
+pub fn estimate_memory_bytes(log_n_steps: u32, layout: Layout, blowup_factor: usize) -> usize {
+    let (num_base_columns, num_extension_columns) = num_columns(layout);
+    let (base_field_bytes, extension_field_bytes) = field_bytes(layout);
+    let trace_len = (1 << log_n_steps) * CYCLE_HEIGHT;
+    let lde_domain_size = trace_len * blowup_factor;
+
+    let row_bytes =
+        num_base_columns * base_field_bytes + num_extension_columns * extension_field_bytes;
+    let trace_table_bytes = trace_len * row_bytes;
+    let lde_table_bytes = trace_table_bytes * blowup_factor;
+    let merkle_tree_bytes = NUM_MERKLE_TREES * 2 * lde_domain_size * DIGEST_BYTES;
+    let fri_bytes = 2 * lde_domain_size * extension_field_bytes;
+    trace_table_bytes + lde_table_bytes + merkle_tree_bytes + fri_bytes
+}
+
+/// The estimated memory usage is close to or exceeds the available memory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InsufficientMemory {
+    pub estimate: usize,
+    pub available: usize,
+}
+
+impl Display for InsufficientMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const GB: f64 = (1 << 30) as f64;
+        let Self {
+            estimate,
+            available,
+        } = *self;
+        write!(
+            f,
+            "proving needs an estimated {:.1}GB of memory but only {:.1}GB is available",
+            estimate as f64 / GB,
+            available as f64 / GB
+        )
+    }
+}
+
+impl Error for InsufficientMemory {}
+
+/// Checks `estimate` bytes is within 80% of the available memory. Always
+/// succeeds if the available memory can't be determined.
+pub fn check_available_memory(estimate: usize) -> Result<(), InsufficientMemory> {
+    let Some(available) = available_memory_bytes() else {
+        return Ok(());
+    };
+    if estimate as f64 > available as f64 * MAX_AVAILABLE_MEMORY_FRACTION {
+        return Err(InsufficientMemory {
+            estimate,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// Reads the available memory from `/proc/meminfo`. Only supported on Linux.
+fn available_memory_bytes() -> Option<usize> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    // line has the form "MemAvailable:   16314364 kB"
+    let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kilobytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
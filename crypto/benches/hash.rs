@@ -0,0 +1,37 @@
+use ark_ff::UniformRand;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+use ministark::hash::ElementHashFn;
+use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+use sandstorm_crypto::hash::blake2s::Blake2sHashFn;
+use sandstorm_crypto::hash::blake3::Blake3HashFn;
+
+/// 1MiB of field elements
+const NUM_ELEMENTS: usize = (1 << 20) / 32;
+
+fn hash_elements_benches(c: &mut Criterion) {
+    let mut rng = ark_std::test_rng();
+    let elements = (0..NUM_ELEMENTS)
+        .map(|_| Fp::rand(&mut rng))
+        .collect::<Vec<Fp>>();
+
+    let mut group = c.benchmark_group("hash_elements");
+    group.throughput(Throughput::Bytes((NUM_ELEMENTS * 32) as u64));
+    group.bench_with_input(
+        BenchmarkId::new("blake2s", "1MiB"),
+        &elements,
+        |b, elements| b.iter(|| Blake2sHashFn::hash_elements(elements.iter().copied())),
+    );
+    group.bench_with_input(
+        BenchmarkId::new("blake3", "1MiB"),
+        &elements,
+        |b, elements| b.iter(|| Blake3HashFn::hash_elements(elements.iter().copied())),
+    );
+    group.finish();
+}
+
+criterion_group!(benches, hash_elements_benches);
+criterion_main!(benches);
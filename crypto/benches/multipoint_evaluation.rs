@@ -0,0 +1,56 @@
+use ark_ff::UniformRand;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::DenseUVPolynomial;
+use ark_poly::Polynomial;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+use sandstorm_crypto::poly::multipoint_evaluate;
+
+const LOG_DEGREE: usize = 20;
+
+const NUM_POINTS: [usize; 2] = [100, 1000];
+
+fn multipoint_evaluation_benches(c: &mut Criterion) {
+    let mut rng = ark_std::test_rng();
+    let coeffs = (0..1 << LOG_DEGREE)
+        .map(|_| Fp::rand(&mut rng))
+        .collect::<Vec<Fp>>();
+    let poly = DensePolynomial::from_coefficients_slice(&coeffs);
+
+    let mut group = c.benchmark_group("multipoint_evaluation");
+    group.sample_size(10);
+    for num_points in NUM_POINTS {
+        let points = (0..num_points)
+            .map(|_| Fp::rand(&mut rng))
+            .collect::<Vec<Fp>>();
+
+        group.bench_with_input(
+            BenchmarkId::new(format!("naive/2^{LOG_DEGREE}"), num_points),
+            &points,
+            |b, points| b.iter(|| points.iter().map(|x| poly.evaluate(x)).collect::<Vec<Fp>>()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new(format!("subproduct_tree/2^{LOG_DEGREE}"), num_points),
+            &points,
+            |b, points| b.iter(|| multipoint_evaluate(&coeffs, points)),
+        );
+        #[cfg(feature = "parallel")]
+        group.bench_with_input(
+            BenchmarkId::new(
+                format!("subproduct_tree_parallel/2^{LOG_DEGREE}"),
+                num_points,
+            ),
+            &points,
+            |b, points| {
+                b.iter(|| sandstorm_crypto::poly::multipoint_evaluate_parallel(&coeffs, points))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, multipoint_evaluation_benches);
+criterion_main!(benches);
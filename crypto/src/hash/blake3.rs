@@ -0,0 +1,108 @@
+use ministark::hash::ElementHashFn;
+use digest::Digest as _;
+use ministark::hash::HashFn;
+use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+use ministark::utils::SerdeOutput;
+use crate::utils::to_montgomery;
+use blake3::Hasher as Blake3;
+
+/// Faster alternative to [super::blake2s::Blake2sHashFn]. Not supported by
+/// StarkWare's verifiers.
+pub struct Blake3HashFn;
+
+impl HashFn for Blake3HashFn {
+    type Digest = SerdeOutput<Blake3>;
+    const COLLISION_RESISTANCE: u32 = 128;
+
+    fn hash(bytes: impl IntoIterator<Item = u8>) -> SerdeOutput<Blake3> {
+        let mut hasher = Blake3::new();
+        for byte in bytes {
+            hasher.update([byte]);
+        }
+        SerdeOutput::new(hasher.finalize())
+    }
+
+    fn hash_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> SerdeOutput<Blake3> {
+        let mut hasher = Blake3::new();
+        for chunk in chunks {
+            hasher.update(chunk);
+        }
+        SerdeOutput::new(hasher.finalize())
+    }
+
+    fn merge(v0: &SerdeOutput<Blake3>, v1: &SerdeOutput<Blake3>) -> SerdeOutput<Blake3> {
+        let mut hasher = Blake3::new();
+        hasher.update(**v0);
+        hasher.update(**v1);
+        SerdeOutput::new(hasher.finalize())
+    }
+
+    fn merge_with_int(seed: &SerdeOutput<Blake3>, value: u64) -> SerdeOutput<Blake3> {
+        let mut hasher = Blake3::new();
+        hasher.update(**seed);
+        hasher.update(value.to_be_bytes());
+        SerdeOutput::new(hasher.finalize())
+    }
+}
+
+impl ElementHashFn<Fp> for Blake3HashFn {
+    fn hash_elements(elements: impl IntoIterator<Item = Fp>) -> SerdeOutput<Blake3> {
+        let mut hasher = Blake3::new();
+        for element in elements {
+            hasher.update(to_montgomery(element).to_be_bytes::<32>());
+        }
+        SerdeOutput::new(hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Blake3HashFn;
+    use crate::utils::to_montgomery;
+    use ark_ff::Field;
+    use ministark::hash::ElementHashFn;
+    use ministark::hash::HashFn;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+
+    #[test]
+    fn hash_matches_reference() {
+        // test vector from the BLAKE3 reference implementation
+        let expected = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
+
+        let digest = Blake3HashFn::hash([]);
+
+        let hex = digest
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        assert_eq!(expected, hex);
+    }
+
+    #[test]
+    fn merge_is_hash_of_concatenation() {
+        let v0 = Blake3HashFn::hash(*b"Hello");
+        let v1 = Blake3HashFn::hash(*b"World!");
+
+        let merged = Blake3HashFn::merge(&v0, &v1);
+
+        assert_eq!(merged, Blake3HashFn::hash_chunks([&v0[..], &v1[..]]));
+    }
+
+    #[test]
+    fn hash_elements_hashes_montgomery_bytes() {
+        let elements = [Fp::ZERO, Fp::ONE, -Fp::ONE];
+
+        let digest = Blake3HashFn::hash_elements(elements);
+
+        let bytes = elements.map(|e| to_montgomery(e).to_be_bytes::<32>());
+        assert_eq!(
+            Blake3HashFn::hash_chunks(bytes.iter().map(|b| &b[..])),
+            digest
+        );
+        // zero is zero in Montgomery form
+        assert_eq!(
+            Blake3HashFn::hash([0; 32]),
+            Blake3HashFn::hash_elements([Fp::ZERO])
+        );
+    }
+}
@@ -1,6 +1,9 @@
 pub mod blake2s;
+#[cfg(feature = "hash-blake3")]
+pub mod blake3;
 pub mod keccak;
 pub mod pedersen;
+pub mod poseidon;
 
 #[inline]
 pub fn mask_least_significant_bytes<const N_UNMASKED_BYTES: u32>(bytes: &mut [u8]) {
@@ -2,5 +2,6 @@
 
 pub mod hash;
 pub mod merkle;
+pub mod poly;
 pub mod public_coin;
 pub mod utils;
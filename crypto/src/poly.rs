@@ -0,0 +1,279 @@
+use ark_ff::PrimeField;
+use ark_ff::Zero;
+use ark_poly::univariate::DenseOrSparsePolynomial;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::DenseUVPolynomial;
+use ark_poly::Polynomial;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Number of points at which a node in the subproduct tree stops splitting.
+/// Below this size evaluating with Horner's method is faster.
+const LEAF_SIZE: usize = 16;
+
+/// Divisors with fewer coefficients than this use schoolbook long division.
+/// Above this size division via Newton iteration (FFT multiplication) wins.
+const FAST_DIVISION_THRESHOLD: usize = 64;
+
+/// Evaluates a polynomial at many points using a subproduct tree.
+/// `poly` is given by its coefficients in ascending order of degree.
+///
+/// The points are recursively split in halves and the polynomial is reduced
+/// modulo the vanishing polynomial of each half. The remainder at a leaf has a
+/// degree smaller than the number of points in the leaf and agrees with `poly`
+/// on those points. With FFT multiplication this takes `O(n log^2 n)` field
+/// operations vs. `O(n^2)` for evaluating each point separately.
+pub fn multipoint_evaluate<F: PrimeField>(poly: &[F], points: &[F]) -> Vec<F> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let tree = SubproductTree::new(points);
+    let poly = DensePolynomial::from_coefficients_slice(poly);
+    let mut evaluations = Vec::with_capacity(points.len());
+    tree.evaluate(&poly, &mut evaluations);
+    evaluations
+}
+
+/// Parallel version of [multipoint_evaluate]. Subtrees are built and
+/// evaluated on separate threads.
+#[cfg(feature = "parallel")]
+pub fn multipoint_evaluate_parallel<F: PrimeField + Send + Sync>(
+    poly: &[F],
+    points: &[F],
+) -> Vec<F> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let tree = SubproductTree::new_parallel(points);
+    let poly = DensePolynomial::from_coefficients_slice(poly);
+    tree.evaluate_parallel(&poly)
+}
+
+/// Binary tree where each node stores the vanishing polynomial of its points
+/// i.e. `∏(X - x_i)`. The vanishing polynomial of a node is the product of the
+/// vanishing polynomials of its children.
+struct SubproductTree<'a, F: PrimeField> {
+    points: &'a [F],
+    vanishing_poly: DensePolynomial<F>,
+    children: Option<Box<[Self; 2]>>,
+}
+
+impl<'a, F: PrimeField> SubproductTree<'a, F> {
+    fn new(points: &'a [F]) -> Self {
+        if points.len() <= LEAF_SIZE {
+            return Self::leaf(points);
+        }
+        let (left_points, right_points) = points.split_at(points.len() / 2);
+        let left = Self::new(left_points);
+        let right = Self::new(right_points);
+        Self::node(points, left, right)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn new_parallel(points: &'a [F]) -> Self
+    where
+        F: Send + Sync,
+    {
+        if points.len() <= LEAF_SIZE {
+            return Self::leaf(points);
+        }
+        let (left_points, right_points) = points.split_at(points.len() / 2);
+        let (left, right) = rayon::join(
+            || Self::new_parallel(left_points),
+            || Self::new_parallel(right_points),
+        );
+        Self::node(points, left, right)
+    }
+
+    fn leaf(points: &'a [F]) -> Self {
+        let vanishing_poly = points.iter().fold(
+            DensePolynomial::from_coefficients_vec(vec![F::one()]),
+            |acc, &x| acc.naive_mul(&DensePolynomial::from_coefficients_vec(vec![-x, F::one()])),
+        );
+        Self {
+            points,
+            vanishing_poly,
+            children: None,
+        }
+    }
+
+    fn node(points: &'a [F], left: Self, right: Self) -> Self {
+        let vanishing_poly = &left.vanishing_poly * &right.vanishing_poly;
+        Self {
+            points,
+            vanishing_poly,
+            children: Some(Box::new([left, right])),
+        }
+    }
+
+    /// Appends the evaluations of `poly` at the points of this subtree
+    fn evaluate(&self, poly: &DensePolynomial<F>, evaluations: &mut Vec<F>) {
+        let remainder = poly_rem(poly, &self.vanishing_poly);
+        match &self.children {
+            Some(children) => {
+                let [left, right] = &**children;
+                left.evaluate(&remainder, evaluations);
+                right.evaluate(&remainder, evaluations);
+            }
+            None => evaluations.extend(self.points.iter().map(|x| remainder.evaluate(x))),
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn evaluate_parallel(&self, poly: &DensePolynomial<F>) -> Vec<F>
+    where
+        F: Send + Sync,
+    {
+        let remainder = poly_rem(poly, &self.vanishing_poly);
+        match &self.children {
+            Some(children) => {
+                let [left, right] = &**children;
+                let (mut left_evals, right_evals) = rayon::join(
+                    || left.evaluate_parallel(&remainder),
+                    || right.evaluate_parallel(&remainder),
+                );
+                left_evals.extend(right_evals);
+                left_evals
+            }
+            None => self
+                .points
+                .par_iter()
+                .map(|x| remainder.evaluate(x))
+                .collect(),
+        }
+    }
+}
+
+/// Returns the remainder of `a` divided by `b`. `b` must be non-zero.
+fn poly_rem<F: PrimeField>(a: &DensePolynomial<F>, b: &DensePolynomial<F>) -> DensePolynomial<F> {
+    assert!(!b.is_zero(), "division by zero polynomial");
+    if a.coeffs.len() < b.coeffs.len() {
+        return a.clone();
+    }
+    if b.coeffs.len() < FAST_DIVISION_THRESHOLD {
+        let a = DenseOrSparsePolynomial::from(a);
+        let b = DenseOrSparsePolynomial::from(b);
+        let (_, remainder) = a.divide_with_q_and_r(&b).unwrap();
+        return remainder;
+    }
+
+    // Let `rev_k(p) = X^k * p(1/X)`. Then `a = q * b + r` implies
+    // `rev_n(a) = rev_{n-m}(q) * rev_m(b) mod X^{n-m+1}` where `n = deg(a)` and
+    // `m = deg(b)`. The constant term of `rev_m(b)` is the (non-zero) leading
+    // coefficient of `b` so it can be inverted modulo `X^{n-m+1}`. The top `k`
+    // coefficients of `q` only depend on the top `k` coefficients of `a` and
+    // `b` so `q` is found in chunks of at most `m` coefficients. This keeps
+    // the cost at `O(n log m)` when `a` is much larger than `b`.
+    let m = b.coeffs.len() - 1;
+    let quotient_len = a.coeffs.len() - m;
+    let b_rev = b.coeffs.iter().rev().copied().collect::<Vec<F>>();
+    let b_rev_inv = inverse_mod_xn(&b_rev, std::cmp::min(m, quotient_len));
+    let mut remainder = a.coeffs.clone();
+    while remainder.len() > m {
+        let n = remainder.len() - 1;
+        let k = std::cmp::min(m, n - m + 1);
+        let remainder_rev = remainder.iter().rev().take(k).copied().collect::<Vec<F>>();
+        let mut quotient_chunk = mul_mod_xn(&remainder_rev, &b_rev_inv, k);
+        quotient_chunk.resize(k, F::zero());
+        quotient_chunk.reverse();
+        // subtract `quotient_chunk * b * X^{n-m-k+1}` to clear the top `k` coefficients
+        let quotient_chunk = DensePolynomial::from_coefficients_vec(quotient_chunk);
+        let shift = n + 1 - m - k;
+        for (r, v) in remainder[shift..]
+            .iter_mut()
+            .zip(&(&quotient_chunk * b).coeffs)
+        {
+            *r -= v;
+        }
+        remainder.truncate(n + 1 - k);
+    }
+    DensePolynomial::from_coefficients_vec(remainder)
+}
+
+/// Returns `g` such that `f * g = 1 mod X^n` using Newton iteration i.e.
+/// `g_{2k} = g_k * (2 - f * g_k) mod X^{2k}`. `f(0)` must be non-zero.
+fn inverse_mod_xn<F: PrimeField>(f: &[F], n: usize) -> Vec<F> {
+    let mut g = vec![f[0].inverse().expect("constant term must be non-zero")];
+    let mut k = 1;
+    while k < n {
+        k = std::cmp::min(2 * k, n);
+        let f = &f[..std::cmp::min(k, f.len())];
+        // `f * g = 1 mod X^{k/2}` so `2 - f * g` only has to be computed mod X^k
+        let mut two_minus_fg = mul_mod_xn(f, &g, k);
+        two_minus_fg.iter_mut().for_each(|v| *v = -*v);
+        if two_minus_fg.is_empty() {
+            two_minus_fg.push(F::zero());
+        }
+        two_minus_fg[0] += F::from(2u8);
+        g = mul_mod_xn(&g, &two_minus_fg, k);
+    }
+    g.resize(n, F::zero());
+    g
+}
+
+/// Returns `a * b mod X^n`
+fn mul_mod_xn<F: PrimeField>(a: &[F], b: &[F], n: usize) -> Vec<F> {
+    let a = DensePolynomial::from_coefficients_slice(&a[..std::cmp::min(a.len(), n)]);
+    let b = DensePolynomial::from_coefficients_slice(&b[..std::cmp::min(b.len(), n)]);
+    let mut product = (&a * &b).coeffs;
+    product.truncate(n);
+    product
+}
+
+#[cfg(test)]
+mod tests {
+    use super::multipoint_evaluate;
+    use ark_ff::UniformRand;
+    use ark_poly::univariate::DensePolynomial;
+    use ark_poly::DenseUVPolynomial;
+    use ark_poly::Polynomial;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+
+    fn naive_evaluate(poly: &[Fp], points: &[Fp]) -> Vec<Fp> {
+        let poly = DensePolynomial::from_coefficients_slice(poly);
+        points.iter().map(|x| poly.evaluate(x)).collect()
+    }
+
+    #[test]
+    fn multipoint_evaluate_matches_naive_evaluation() {
+        let mut rng = ark_std::test_rng();
+        let poly = (0..1000).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+        let points = (0..300).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+
+        let evaluations = multipoint_evaluate(&poly, &points);
+
+        assert_eq!(naive_evaluate(&poly, &points), evaluations);
+    }
+
+    #[test]
+    fn multipoint_evaluate_with_more_points_than_coefficients() {
+        let mut rng = ark_std::test_rng();
+        let poly = (0..10).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+        let points = (0..200).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+
+        let evaluations = multipoint_evaluate(&poly, &points);
+
+        assert_eq!(naive_evaluate(&poly, &points), evaluations);
+    }
+
+    #[test]
+    fn multipoint_evaluate_zero_polynomial() {
+        let points = [Fp::from(1u8), Fp::from(2u8), Fp::from(3u8)];
+
+        assert_eq!(vec![Fp::from(0u8); 3], multipoint_evaluate(&[], &points));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn multipoint_evaluate_parallel_matches_sequential() {
+        use super::multipoint_evaluate_parallel;
+        let mut rng = ark_std::test_rng();
+        let poly = (0..1000).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+        let points = (0..300).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+
+        assert_eq!(
+            multipoint_evaluate(&poly, &points),
+            multipoint_evaluate_parallel(&poly, &points)
+        );
+    }
+}
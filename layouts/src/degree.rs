@@ -0,0 +1,149 @@
+//! Symbolic degree of AIR constraints. Constraints are evaluated over
+//! [DegreeTracker] instead of field elements which gives the degree of each
+//! constraint in terms of the execution trace columns.
+
+use ministark::air::AirConfig;
+use ministark::constraints::AlgebraicItem;
+use num_traits::Pow;
+use std::ops::Add;
+use std::ops::Div;
+use std::ops::Mul;
+use std::ops::Neg;
+use std::ops::Sub;
+
+/// Degree of an expression in terms of the execution trace columns. Trace
+/// columns have degree 1. Everything else (constants, challenges, hints,
+/// periodic columns and `x`) has degree 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DegreeTracker(pub usize);
+
+impl DegreeTracker {
+    pub const fn constant() -> Self {
+        Self(0)
+    }
+
+    pub const fn variable() -> Self {
+        Self(1)
+    }
+}
+
+impl Add for DegreeTracker {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.max(rhs.0))
+    }
+}
+
+impl Sub for DegreeTracker {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.max(rhs.0))
+    }
+}
+
+impl Mul for DegreeTracker {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+/// Constraints only divide by polynomials in `x` (vanishing polynomials of
+/// the constraint domains). Dividing by an expression with trace columns has
+/// no well defined degree so it panics rather than understate the degree.
+impl Div for DegreeTracker {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        assert_eq!(0, rhs.0, "denominator depends on trace columns");
+        self
+    }
+}
+
+impl Neg for DegreeTracker {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self
+    }
+}
+
+impl Pow<usize> for DegreeTracker {
+    type Output = Self;
+
+    fn pow(self, exp: usize) -> Self {
+        Self(self.0 * exp)
+    }
+}
+
+/// Returns the degree of the constraint at `constraint_idx` in terms of the
+/// execution trace columns
+pub fn evaluate_constraint_degree<A: AirConfig>(trace_len: usize, constraint_idx: usize) -> usize {
+    let constraints = A::constraints(trace_len);
+    let DegreeTracker(degree) = constraints[constraint_idx].eval(&mut |leaf| match leaf {
+        AlgebraicItem::Trace(..) => DegreeTracker::variable(),
+        _ => DegreeTracker::constant(),
+    });
+    degree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::evaluate_constraint_degree;
+    use super::DegreeTracker;
+    use crate::recursive;
+    use crate::starknet;
+    use crate::CairoAirConfig;
+    use ministark::air::AirConfig;
+    use num_traits::Pow;
+
+    const TRACE_LEN: usize = 1 << 16;
+
+    #[test]
+    fn degree_tracker_arithmetic() {
+        let x = DegreeTracker::variable();
+        let c = DegreeTracker::constant();
+
+        assert_eq!(DegreeTracker(1), x + c);
+        assert_eq!(DegreeTracker(2), x * x - c);
+        assert_eq!(DegreeTracker(3), x.pow(3));
+        assert_eq!(DegreeTracker(2), x * x / c);
+    }
+
+    #[test]
+    #[should_panic(expected = "denominator depends on trace columns")]
+    fn degree_tracker_rejects_trace_denominator() {
+        let x = DegreeTracker::variable();
+
+        let _ = x * x / x;
+    }
+
+    #[test]
+    fn starknet_constraints_are_within_degree_bound() {
+        type A = starknet::AirConfig;
+        let num_constraints = A::constraints(TRACE_LEN).len();
+        for i in 0..num_constraints {
+            let degree = evaluate_constraint_degree::<A>(TRACE_LEN, i);
+            assert!(
+                degree <= A::COMPOSITION_DEGREE_BOUND,
+                "constraint {i} has degree {degree}"
+            );
+        }
+    }
+
+    #[test]
+    fn recursive_constraints_are_within_degree_bound() {
+        type A = recursive::AirConfig;
+        let num_constraints = A::constraints(TRACE_LEN).len();
+        for i in 0..num_constraints {
+            let degree = evaluate_constraint_degree::<A>(TRACE_LEN, i);
+            assert!(
+                degree <= A::COMPOSITION_DEGREE_BOUND,
+                "constraint {i} has degree {degree}"
+            );
+        }
+    }
+}
@@ -12,19 +12,28 @@ use ark_ff::Field;
 use binary::AirPrivateInput;
 use binary::AirPublicInput;
 use binary::CompiledProgram;
+use binary::Layout;
 use binary::Memory;
 use binary::RegisterStates;
+use binary::SegmentKind;
 use ministark::air::AirConfig;
 use ministark::challenges::Challenges;
 use ministark::hints::Hints;
 use ministark::Trace;
+use std::error::Error;
+use std::fmt::Display;
 
+pub mod degree;
 pub mod plain;
 pub mod recursive;
 pub mod starknet;
 pub mod utils;
 
 pub trait CairoAirConfig: AirConfig {
+    /// Maximum degree of a constraint in terms of the execution trace columns.
+    /// See [degree::evaluate_constraint_degree].
+    const COMPOSITION_DEGREE_BOUND: usize = 2;
+
     /// Public memory permutation challenges
     /// Output is of the form: (z, alpha)
     fn public_memory_challenges(challenges: &Challenges<Self::Fq>) -> (Self::Fq, Self::Fq);
@@ -62,3 +71,50 @@ pub trait CairoTrace: Trace {
         witness: CairoWitness<Self::Fp>,
     ) -> Self;
 }
+
+/// The number of builtin instances doesn't match the number of steps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RatioViolation {
+    pub builtin: SegmentKind,
+    pub expected_instances: u32,
+    pub actual_instances: u32,
+}
+
+impl Display for RatioViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            builtin,
+            expected_instances,
+            actual_instances,
+        } = self;
+        write!(
+            f,
+            "expected {expected_instances} {builtin:?} instances but there are {actual_instances}"
+        )
+    }
+}
+
+impl Error for RatioViolation {}
+
+/// Checks a trace of `n_steps` (after padding) has a whole number of
+/// instances of each builtin and that the instances in the private input fit.
+/// The remaining instances are filled with dummy instances by the trace.
+pub fn validate_ratios(
+    layout: Layout,
+    n_steps: u64,
+    private_input: &AirPrivateInput,
+) -> Result<(), RatioViolation> {
+    let counts = private_input.builtin_counts();
+    for &(builtin, ratio) in layout.builtin_ratios() {
+        let actual_instances = counts.count(builtin) as u32;
+        let expected_instances = (n_steps / u64::from(ratio)) as u32;
+        if n_steps % u64::from(ratio) != 0 || actual_instances > expected_instances {
+            return Err(RatioViolation {
+                builtin,
+                expected_instances,
+                actual_instances,
+            });
+        }
+    }
+    Ok(())
+}
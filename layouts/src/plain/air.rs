@@ -549,10 +549,10 @@ impl<Fp: GpuFftField + PrimeField, Fq: StarkExtensionOf<Fp>> ministark::air::Air
             );
 
         // assert!(range_check_min <= range_check_max);
-        let initial_ap = public_input.initial_ap().into();
-        let final_ap = public_input.final_ap().into();
-        let initial_pc = public_input.initial_pc().into();
-        let final_pc = public_input.final_pc().into();
+        let initial_ap = public_input.initial_ap().into_felt();
+        let final_ap = public_input.final_ap().into_felt();
+        let initial_pc = public_input.initial_pc().into_felt();
+        let final_pc = public_input.final_pc().into_felt();
 
         Hints::new(vec![
             (InitialAp.index(), initial_ap),
@@ -19,6 +19,7 @@ use ark_ff::batch_inversion;
 use ark_ff::FftField;
 use ark_ff::Field;
 use ark_ff::PrimeField;
+use binary::types::MemoryAddress;
 use binary::AirPublicInput;
 use binary::CompiledProgram;
 use binary::Memory;
@@ -80,7 +81,7 @@ impl<Fp: GpuFftField + PrimeField, Fq: StarkExtensionOf<Fp>> CairoTrace for Exec
         {
             // default all memory items to our padding entry
             // TODO: this is a little hacky. not good
-            let padding_address = padding_entry.address.into();
+            let padding_address = padding_entry.address.into_felt();
             let padding_value = padding_entry.value;
             for [address, value] in npc_column.array_chunks_mut() {
                 *address = padding_address;
@@ -220,7 +221,7 @@ impl<Fp: GpuFftField + PrimeField, Fq: StarkExtensionOf<Fp>> CairoTrace for Exec
             .map(|&[address_felt, value_felt]| {
                 let address: BigUint = address_felt.into_bigint().into();
                 MemoryEntry {
-                    address: address.try_into().unwrap(),
+                    address: MemoryAddress(address.try_into().unwrap()),
                     value: value_felt,
                 }
             })
@@ -233,7 +234,7 @@ impl<Fp: GpuFftField + PrimeField, Fq: StarkExtensionOf<Fp>> CairoTrace for Exec
         );
         let memory_column = ordered_memory_accesses
             .into_iter()
-            .flat_map(|e| [e.address.into(), e.value])
+            .flat_map(|e| [e.address.into_felt(), e.value])
             .collect::<Vec<Fp>>()
             .to_vec_in(GpuAllocator);
 
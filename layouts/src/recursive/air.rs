@@ -1211,9 +1211,9 @@ impl ministark::air::AirConfig for AirConfig {
         let rc_segment = segments.range_check.expect("layout requires range check");
         let bitwise_segment = segments.bitwise.expect("layout requires bitwise");
 
-        let initial_perdersen_address = pedersen_segment.begin_addr.into();
-        let initial_rc_address = rc_segment.begin_addr.into();
-        let initial_bitwise_address = bitwise_segment.begin_addr.into();
+        let initial_perdersen_address = pedersen_segment.begin_addr.into_felt();
+        let initial_rc_address = rc_segment.begin_addr.into_felt();
+        let initial_bitwise_address = bitwise_segment.begin_addr.into_felt();
 
         let memory_quotient =
             utils::compute_public_memory_quotient::<PUBLIC_MEMORY_STEP, Self::Fp, Self::Fq>(
@@ -1236,10 +1236,10 @@ impl ministark::air::AirConfig for AirConfig {
 
         // TODO: add validation on the AirPublicInput struct
         // assert!(range_check_min <= range_check_max);
-        let initial_ap = execution_info.initial_ap().into();
-        let final_ap = execution_info.final_ap().into();
-        let initial_pc = execution_info.initial_pc().into();
-        let final_pc = execution_info.final_pc().into();
+        let initial_ap = execution_info.initial_ap().into_felt();
+        let final_ap = execution_info.final_ap().into_felt();
+        let initial_pc = execution_info.initial_pc().into_felt();
+        let final_pc = execution_info.final_pc().into_felt();
 
         Hints::new(vec![
             (InitialAp.index(), initial_ap),
@@ -8,6 +8,8 @@ pub mod air;
 pub mod trace;
 
 pub use air::AirConfig;
+use binary::Layout;
+use binary::SegmentKind;
 pub use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
 pub use trace::ExecutionTrace;
 
@@ -20,10 +22,14 @@ pub const RANGE_CHECK_STEP: usize = 4;
 pub const DILUTED_CHECK_STEP: usize = 1; //TODO is that correct?
 
 /// How many cycles per pedersen hash
-pub const PEDERSEN_BUILTIN_RATIO: usize = 128;
+pub const PEDERSEN_BUILTIN_RATIO: usize = Layout::Recursive
+    .builtin_ratio(SegmentKind::Pedersen)
+    .unwrap() as usize;
 
 /// How many cycles per 128 bit range check
-pub const RANGE_CHECK_BUILTIN_RATIO: usize = 8;
+pub const RANGE_CHECK_BUILTIN_RATIO: usize = Layout::Recursive
+    .builtin_ratio(SegmentKind::RangeCheck)
+    .unwrap() as usize;
 pub const RANGE_CHECK_BUILTIN_PARTS: usize = 8;
 
 pub const NUM_BASE_COLUMNS: usize = 9;
@@ -32,4 +38,6 @@ pub const NUM_EXTENSION_COLUMNS: usize = 1;
 pub const DILUTED_CHECK_N_BITS: usize = 16;
 pub const DILUTED_CHECK_SPACING: usize = 4;
 
-pub const BITWISE_RATIO: usize = 8;
+pub const BITWISE_RATIO: usize = Layout::Recursive
+    .builtin_ratio(SegmentKind::Bitwise)
+    .unwrap() as usize;
@@ -12,6 +12,7 @@ use super::RANGE_CHECK_STEP;
 use ark_ff::BigInt;
 use ark_ff::Zero;
 use binary::BitwiseInstance;
+use binary::types::MemoryAddress;
 use binary::MemoryEntry;
 use binary::PedersenInstance;
 use binary::RangeCheckInstance;
@@ -20,9 +21,11 @@ use builtins::bitwise::dilute;
 use builtins::pedersen;
 use ark_ff::One;
 use binary::AirPublicInput;
+use binary::Layout;
 use builtins::range_check;
 use num_bigint::BigUint;
 use ruint::aliases::U256;
+use crate::validate_ratios;
 use crate::CairoWitness;
 use crate::recursive::PEDERSEN_BUILTIN_RATIO;
 use super::BITWISE_RATIO;
@@ -69,9 +72,9 @@ pub struct ExecutionTrace {
     pub range_check_max: u16,
     pub initial_registers: RegisterState,
     pub final_registers: RegisterState,
-    pub initial_pedersen_address: u32,
-    pub initial_rc_address: u32,
-    pub initial_bitwise_address: u32,
+    pub initial_pedersen_address: MemoryAddress,
+    pub initial_rc_address: MemoryAddress,
+    pub initial_bitwise_address: MemoryAddress,
     pub program: CompiledProgram<Fp>,
     npc_column: GpuVec<Fp>,
     memory_column: GpuVec<Fp>,
@@ -99,6 +102,8 @@ impl CairoTrace for ExecutionTrace {
 
         let num_cycles = register_states.len();
         assert!(num_cycles.is_power_of_two());
+        validate_ratios(Layout::Recursive, num_cycles as u64, &air_private_input)
+            .expect("invalid builtin ratios");
         let trace_len = num_cycles * CYCLE_HEIGHT;
         let public_memory = air_public_input
             .public_memory
@@ -121,7 +126,7 @@ impl CairoTrace for ExecutionTrace {
         {
             // default all memory items to our padding entry
             // TODO: this is a little hacky. not good
-            let padding_address = padding_entry.address.into();
+            let padding_address = padding_entry.address.into_felt();
             let padding_value = padding_entry.value;
             for [address, value] in npc_column.array_chunks_mut() {
                 *address = padding_address;
@@ -360,12 +365,12 @@ impl CairoTrace for ExecutionTrace {
 
                     // add the hash to the memory pool
                     let instance = pedersen_trace.instance;
-                    let (a_addr, b_addr, output_addr) = instance.mem_addr(initial_pedersen_address);
-                    npc[Npc::PedersenInput0Addr as usize] = a_addr.into();
+                    let addrs = instance.mem_addr(initial_pedersen_address);
+                    npc[Npc::PedersenInput0Addr as usize] = addrs.a.into_felt();
                     npc[Npc::PedersenInput0Val as usize] = Fp::from(BigUint::from(instance.a));
-                    npc[Npc::PedersenInput1Addr as usize] = b_addr.into();
+                    npc[Npc::PedersenInput1Addr as usize] = addrs.b.into_felt();
                     npc[Npc::PedersenInput1Val as usize] = Fp::from(BigUint::from(instance.b));
-                    npc[Npc::PedersenOutputAddr as usize] = output_addr.into();
+                    npc[Npc::PedersenOutputAddr as usize] = addrs.output.into_felt();
                     npc[Npc::PedersenOutputVal as usize] = pedersen_trace.output;
                 },
             );
@@ -406,7 +411,7 @@ impl CairoTrace for ExecutionTrace {
                 // add the range check to the memory pool
                 let instance = rc_trace.instance;
                 let addr = instance.mem_addr(initial_rc_address);
-                npc[Npc::RangeCheck128Addr as usize] = addr.into();
+                npc[Npc::RangeCheck128Addr as usize] = addr.into_felt();
                 npc[Npc::RangeCheck128Val as usize] = Fp::from(BigUint::from(instance.value));
             });
 
@@ -521,17 +526,16 @@ impl CairoTrace for ExecutionTrace {
                     let x_and_y_offset = input_y_offset + ADDR_STEP;
                     let x_xor_y_offset = x_and_y_offset + ADDR_STEP;
                     let x_or_y_offset = Npc::BitwiseXOrYAddr as usize;
-                    let (input_x_addr, input_y_addr, x_and_y_addr, x_xor_y_addr, x_or_y_addr) =
-                        instance.mem_addr(initial_bitwise_address);
-                    npc[input_x_offset] = input_x_addr.into();
+                    let addrs = instance.mem_addr(initial_bitwise_address);
+                    npc[input_x_offset] = addrs.x.into_felt();
                     npc[input_x_offset + 1] = bitwise_trace.x;
-                    npc[input_y_offset] = input_y_addr.into();
+                    npc[input_y_offset] = addrs.y.into_felt();
                     npc[input_y_offset + 1] = bitwise_trace.y;
-                    npc[x_and_y_offset] = x_and_y_addr.into();
+                    npc[x_and_y_offset] = addrs.x_and_y.into_felt();
                     npc[x_and_y_offset + 1] = bitwise_trace.x_and_y;
-                    npc[x_xor_y_offset] = x_xor_y_addr.into();
+                    npc[x_xor_y_offset] = addrs.x_xor_y.into_felt();
                     npc[x_xor_y_offset + 1] = bitwise_trace.x_xor_y;
-                    npc[x_or_y_offset] = x_or_y_addr.into();
+                    npc[x_or_y_offset] = addrs.x_or_y.into_felt();
                     npc[x_or_y_offset + 1] = bitwise_trace.x_or_y;
 
                     // return the diluted pool
@@ -601,7 +605,9 @@ impl CairoTrace for ExecutionTrace {
             let mut sorted_memory_accesses: Vec<MemoryEntry<Fp>> = npc_column
                 .array_chunks()
                 .map(|&[address, value]| {
-                    let address = u32::try_from(U256::from_limbs(address.into_bigint().0)).unwrap();
+                    let address = MemoryAddress(
+                        u32::try_from(U256::from_limbs(address.into_bigint().0)).unwrap(),
+                    );
                     MemoryEntry { value, address }
                 })
                 .chain(public_memory.clone())
@@ -611,7 +617,7 @@ impl CairoTrace for ExecutionTrace {
             for [a, b] in sorted_memory_accesses.array_windows() {
                 let a_addr = a.address;
                 let b_addr = b.address;
-                for padding_addr in a_addr.saturating_add(1)..b_addr {
+                for padding_addr in a_addr.0.saturating_add(1)..b_addr.0 {
                     padding_addrs.push(padding_addr)
                 }
             }
@@ -631,9 +637,11 @@ impl CairoTrace for ExecutionTrace {
         let memory_accesses: Vec<MemoryEntry<Fp>> = npc_column
             .array_chunks()
             .map(|&[address_felt, value_felt]| MemoryEntry {
-                address: U256::from_limbs(address_felt.into_bigint().0)
-                    .try_into()
-                    .unwrap(),
+                address: MemoryAddress(
+                    U256::from_limbs(address_felt.into_bigint().0)
+                        .try_into()
+                        .unwrap(),
+                ),
                 value: value_felt,
             })
             .collect();
@@ -645,7 +653,7 @@ impl CairoTrace for ExecutionTrace {
         );
         let memory_column = ordered_memory_accesses
             .into_iter()
-            .flat_map(|e| [e.address.into(), e.value])
+            .flat_map(|e| [e.address.into_felt(), e.value])
             .collect::<Vec<Fp>>()
             .to_vec_in(GpuAllocator);
 
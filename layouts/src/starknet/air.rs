@@ -22,7 +22,9 @@ use crate::utils::map_into_fp_array;
 use ark_ff::MontFp;
 use ark_poly::EvaluationDomain;
 use ark_poly::Radix2EvaluationDomain;
+use binary::AirPrivateInput;
 use binary::AirPublicInput;
+use binary::SegmentKind;
 use builtins::ecdsa;
 use builtins::pedersen;
 use builtins::poseidon;
@@ -43,6 +45,8 @@ use ministark::utils::FieldVariant;
 use num_bigint::BigUint;
 use num_traits::Pow;
 use strum_macros::EnumIter;
+use std::error::Error;
+use std::fmt::Display;
 
 const PEDERSEN_POINT_X: PeriodicColumn<'static, FieldVariant<Fp, Fp>> = {
     const INTERVAL_SIZE: usize = super::PEDERSEN_BUILTIN_RATIO * super::CYCLE_HEIGHT;
@@ -103,8 +107,110 @@ const POSEIDON_POSEIDON_PARTIAL_ROUND_KEY1: PeriodicColumn<'static, FieldVariant
     PeriodicColumn::new(&COEFFS, INTERVAL_SIZE)
 };
 
+/// Number of trace rows used by a single instance of each builtin. An
+/// instance is allocated every `ratio` CPU steps and each step spans
+/// [CYCLE_HEIGHT] rows. Ratios are from StarkWare's layout definitions:
+/// <https://github.com/starkware-libs/cairo-lang/blob/361fe32d5930db340ea78fe05aedfe706f6c9405/src/starkware/cairo/lang/instances.py#L157>
+pub const STARKNET_BUILTIN_ROWS: &[(SegmentKind, u32)] = &[
+    (
+        SegmentKind::Pedersen,
+        (PEDERSEN_BUILTIN_RATIO * CYCLE_HEIGHT) as u32,
+    ),
+    (
+        SegmentKind::RangeCheck,
+        (RANGE_CHECK_BUILTIN_RATIO * CYCLE_HEIGHT) as u32,
+    ),
+    (
+        SegmentKind::Ecdsa,
+        (ECDSA_BUILTIN_RATIO * CYCLE_HEIGHT) as u32,
+    ),
+    (SegmentKind::Bitwise, (BITWISE_RATIO * CYCLE_HEIGHT) as u32),
+    (
+        SegmentKind::EcOp,
+        (EC_OP_BUILTIN_RATIO * CYCLE_HEIGHT) as u32,
+    ),
+    (
+        SegmentKind::Poseidon,
+        (POSEIDON_RATIO * CYCLE_HEIGHT) as u32,
+    ),
+];
+
+/// A builtin has more instances than fit in the trace
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooManyInstancesError {
+    pub builtin: SegmentKind,
+    pub n_instances: usize,
+    pub max_instances: u64,
+}
+
+impl Display for TooManyInstancesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            builtin,
+            n_instances,
+            max_instances,
+        } = self;
+        write!(
+            f,
+            "{builtin:?} has {n_instances} instances but the trace fits at most {max_instances}"
+        )
+    }
+}
+
+impl Error for TooManyInstancesError {}
+
 pub struct AirConfig;
 
+impl AirConfig {
+    /// Number of trace rows used by `n_instances` instances of a builtin
+    pub fn rows_for_builtin(builtin: SegmentKind, n_instances: u32) -> u32 {
+        let (_, rows) = STARKNET_BUILTIN_ROWS
+            .iter()
+            .find(|(b, _)| *b == builtin)
+            .unwrap();
+        n_instances * rows
+    }
+
+    /// Checks the instances of each builtin fit in a trace of `n_steps` steps
+    pub fn validate_instance_counts(
+        private_input: &AirPrivateInput,
+        n_steps: u64,
+    ) -> Result<(), TooManyInstancesError> {
+        let trace_len = n_steps * CYCLE_HEIGHT as u64;
+        let counts = private_input.builtin_counts();
+        for &(builtin, rows_per_instance) in STARKNET_BUILTIN_ROWS {
+            let n_instances = counts.count(builtin);
+            let max_instances = trace_len / u64::from(rows_per_instance);
+            if n_instances as u64 > max_instances {
+                return Err(TooManyInstancesError {
+                    builtin,
+                    n_instances,
+                    max_instances,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Human readable names of the trace columns. Useful when debugging.
+    /// Each column is shared by several virtual columns so names describe the
+    /// main data held by the column. Names follow StarkWare's Cairo CPU AIR.
+    pub const fn column_names() -> &'static [&'static str] {
+        &[
+            "cpu_decode_opcode_rc",
+            "pedersen_hash0_ec_subset_sum_partial_sum_x",
+            "pedersen_hash0_ec_subset_sum_partial_sum_y",
+            "pedersen_hash0_ec_subset_sum_selector",
+            "pedersen_hash0_ec_subset_sum_slope",
+            "mem_pool",
+            "memory_sorted",
+            "rc16_pool_and_diluted_pool",
+            "cpu_registers_and_builtins",
+            "interaction_permutations",
+        ]
+    }
+}
+
 impl ministark::air::AirConfig for AirConfig {
     const NUM_BASE_COLUMNS: usize = 9;
     const NUM_EXTENSION_COLUMNS: usize = 1;
@@ -2420,12 +2526,12 @@ impl ministark::air::AirConfig for AirConfig {
         let ec_op_segment = segments.ec_op.expect("layout requires EC op");
         let poseidon_segment = segments.poseidon.expect("layout requires poseidon");
 
-        let initial_perdersen_address = pedersen_segment.begin_addr.into();
-        let initial_rc_address = rc_segment.begin_addr.into();
-        let initial_ecdsa_address = ecdsa_segment.begin_addr.into();
-        let initial_bitwise_address = bitwise_segment.begin_addr.into();
-        let initial_ec_op_address = ec_op_segment.begin_addr.into();
-        let initial_poseidon_address = poseidon_segment.begin_addr.into();
+        let initial_perdersen_address = pedersen_segment.begin_addr.into_felt();
+        let initial_rc_address = rc_segment.begin_addr.into_felt();
+        let initial_ecdsa_address = ecdsa_segment.begin_addr.into_felt();
+        let initial_bitwise_address = bitwise_segment.begin_addr.into_felt();
+        let initial_ec_op_address = ec_op_segment.begin_addr.into_felt();
+        let initial_poseidon_address = poseidon_segment.begin_addr.into_felt();
 
         let memory_quotient =
             utils::compute_public_memory_quotient::<PUBLIC_MEMORY_STEP, Self::Fp, Self::Fq>(
@@ -2448,10 +2554,10 @@ impl ministark::air::AirConfig for AirConfig {
 
         // TODO: add validation on the AirPublicInput struct
         // assert!(range_check_min <= range_check_max);
-        let initial_ap = execution_info.initial_ap().into();
-        let final_ap = execution_info.final_ap().into();
-        let initial_pc = execution_info.initial_pc().into();
-        let final_pc = execution_info.final_pc().into();
+        let initial_ap = execution_info.initial_ap().into_felt();
+        let final_ap = execution_info.final_ap().into_felt();
+        let initial_pc = execution_info.initial_pc().into_felt();
+        let final_pc = execution_info.final_pc().into_felt();
 
         Hints::new(vec![
             (InitialAp.index(), initial_ap),
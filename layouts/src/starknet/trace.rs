@@ -1,3 +1,4 @@
+use super::air::AirConfig;
 use super::air::Auxiliary;
 use super::air::Flag;
 use super::air::MemoryPermutation;
@@ -9,6 +10,7 @@ use super::RANGE_CHECK_STEP;
 use ark_ff::BigInt;
 use ark_ff::Zero;
 use binary::BitwiseInstance;
+use binary::types::MemoryAddress;
 use binary::MemoryEntry;
 use ark_ff::PrimeField;
 use binary::PedersenInstance;
@@ -21,10 +23,12 @@ use builtins::ecdsa;
 use builtins::pedersen;
 use ark_ff::One;
 use binary::AirPublicInput;
+use binary::Layout;
 use builtins::poseidon;
 use builtins::range_check;
 use num_bigint::BigUint;
 use ruint::aliases::U256;
+use crate::validate_ratios;
 use crate::CairoWitness;
 use crate::starknet::air::Poseidon;
 use super::BITWISE_RATIO;
@@ -52,6 +56,8 @@ use super::air::RangeCheckPermutation;
 use ark_ff::Field;
 use super::MEMORY_STEP;
 use crate::utils::get_ordered_memory_accesses;
+use crate::utils::compute_public_memory_quotient;
+use crate::utils::MemoryArgument;
 use crate::CairoTrace;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -61,6 +67,8 @@ use binary::Memory;
 use binary::RegisterState;
 use binary::RegisterStates;
 use core::iter::zip;
+use std::error::Error;
+use std::fmt::Display;
 use ministark::challenges::Challenges;
 use ministark::utils::GpuAllocator;
 use ministark::utils::GpuVec;
@@ -79,11 +87,11 @@ pub struct ExecutionTrace {
     pub range_check_max: u16,
     pub initial_registers: RegisterState,
     pub final_registers: RegisterState,
-    pub initial_pedersen_address: u32,
-    pub initial_rc_address: u32,
-    pub initial_ecdsa_address: u32,
-    pub initial_bitwise_address: u32,
-    pub initial_ec_op_address: u32,
+    pub initial_pedersen_address: MemoryAddress,
+    pub initial_rc_address: MemoryAddress,
+    pub initial_ecdsa_address: MemoryAddress,
+    pub initial_bitwise_address: MemoryAddress,
+    pub initial_ec_op_address: MemoryAddress,
     pub program: CompiledProgram<Fp>,
     npc_column: GpuVec<Fp>,
     memory_column: GpuVec<Fp>,
@@ -109,6 +117,10 @@ impl CairoTrace for ExecutionTrace {
 
         let num_cycles = register_states.len();
         assert!(num_cycles.is_power_of_two());
+        validate_ratios(Layout::Starknet, num_cycles as u64, &air_private_input)
+            .expect("invalid builtin ratios");
+        AirConfig::validate_instance_counts(&air_private_input, num_cycles as u64)
+            .expect("too many builtin instances");
         let trace_len = num_cycles * CYCLE_HEIGHT;
         let public_memory = air_public_input
             .public_memory
@@ -131,7 +143,7 @@ impl CairoTrace for ExecutionTrace {
         {
             // default all memory items to our padding entry
             // TODO: this is a little hacky. not good
-            let padding_address = padding_entry.address.into();
+            let padding_address = padding_entry.address.into_felt();
             let padding_value = padding_entry.value;
             for [address, value] in npc_column.array_chunks_mut() {
                 *address = padding_address;
@@ -375,12 +387,12 @@ impl CairoTrace for ExecutionTrace {
 
                     // add the hash to the memory pool
                     let instance = pedersen_trace.instance;
-                    let (a_addr, b_addr, output_addr) = instance.mem_addr(initial_pedersen_address);
-                    npc[Npc::PedersenInput0Addr as usize] = a_addr.into();
+                    let addrs = instance.mem_addr(initial_pedersen_address);
+                    npc[Npc::PedersenInput0Addr as usize] = addrs.a.into_felt();
                     npc[Npc::PedersenInput0Val as usize] = Fp::from(BigUint::from(instance.a));
-                    npc[Npc::PedersenInput1Addr as usize] = b_addr.into();
+                    npc[Npc::PedersenInput1Addr as usize] = addrs.b.into_felt();
                     npc[Npc::PedersenInput1Val as usize] = Fp::from(BigUint::from(instance.b));
-                    npc[Npc::PedersenOutputAddr as usize] = output_addr.into();
+                    npc[Npc::PedersenOutputAddr as usize] = addrs.output.into_felt();
                     npc[Npc::PedersenOutputVal as usize] = pedersen_trace.output;
                 },
             );
@@ -421,7 +433,7 @@ impl CairoTrace for ExecutionTrace {
                 // add the range check to the memory pool
                 let instance = rc_trace.instance;
                 let addr = instance.mem_addr(initial_rc_address);
-                npc[Npc::RangeCheck128Addr as usize] = addr.into();
+                npc[Npc::RangeCheck128Addr as usize] = addr.into_felt();
                 npc[Npc::RangeCheck128Val as usize] = Fp::from(BigUint::from(instance.value));
             });
 
@@ -439,7 +451,7 @@ impl CairoTrace for ExecutionTrace {
         let ecdsa_dummy_traces = ark_std::cfg_into_iter!(num_ecdsa_instances..u32::MAX)
             .map(ecdsa::InstanceTrace::new_dummy);
         let ecdsa_traces = ark_std::cfg_into_iter!(ecdsa_instances)
-            .map(ecdsa::InstanceTrace::new)
+            .map(|instance| ecdsa::InstanceTrace::new(instance).expect("invalid ECDSA instance"))
             .chain(ecdsa_dummy_traces);
 
         const ECDSA_STEP_ROWS: usize = ECDSA_BUILTIN_RATIO * CYCLE_HEIGHT;
@@ -466,7 +478,8 @@ impl CairoTrace for ExecutionTrace {
 
                 // #1 load in public key scalar multiplication `r * Q`
                 for ((aux_step, rq_step), pubkey_doubling_step) in
-                    zip(rq_aux_steps, ecdsa_trace.rq_steps).zip(ecdsa_trace.pubkey_doubling_steps)
+                    zip(rq_aux_steps, ecdsa_trace.rq_steps.iter())
+                        .zip(ecdsa_trace.pubkey_doubling_steps)
                 {
                     aux_step[Ecdsa::PubkeyDoublingX as usize] = pubkey_doubling_step.point.x;
                     aux_step[Ecdsa::PubkeyDoublingY as usize] = pubkey_doubling_step.point.y;
@@ -480,7 +493,7 @@ impl CairoTrace for ExecutionTrace {
 
                 // #2 load in `B` scalar multiplication `w * B` where `B = z * G + r * Q`
                 for ((aux_step, wb_step), b_doubling_step) in
-                    zip(wb_aux_steps, ecdsa_trace.wb_steps).zip(ecdsa_trace.b_doubling_steps)
+                    zip(wb_aux_steps, ecdsa_trace.wb_steps.iter()).zip(ecdsa_trace.b_doubling_steps)
                 {
                     // TODO: need a better symbol for B and pubkey scalar mults since
                     // PubkeyDoublingX is used for pubkey mult and B mult.
@@ -497,7 +510,7 @@ impl CairoTrace for ExecutionTrace {
                 // load the scalar multiplication `z * G` into the trace
                 // where `z` is the message hash and `G` is the curve generator point
                 let (zg_aux_steps, _) = aux.as_chunks_mut::<128>();
-                for (aux_step, zg_step) in zip(zg_aux_steps, ecdsa_trace.zg_steps) {
+                for (aux_step, zg_step) in zip(zg_aux_steps, ecdsa_trace.zg_steps.iter()) {
                     aux_step[Ecdsa::GeneratorPartialSumX as usize] = zg_step.partial_sum.x;
                     aux_step[Ecdsa::GeneratorPartialSumY as usize] = zg_step.partial_sum.y;
                     aux_step[Ecdsa::GeneratorPartialSumSlope as usize] = zg_step.slope;
@@ -515,10 +528,10 @@ impl CairoTrace for ExecutionTrace {
                 aux[Ecdsa::PubkeyXSquared as usize] = pubkey.x.square();
 
                 // add the instance to the memory pool
-                let (pubkey_addr, message_addr) = instance.mem_addr(initial_ecdsa_address);
-                npc[Npc::EcdsaPubkeyAddr as usize] = pubkey_addr.into();
+                let addrs = instance.mem_addr(initial_ecdsa_address);
+                npc[Npc::EcdsaPubkeyAddr as usize] = addrs.pubkey.into_felt();
                 npc[Npc::EcdsaPubkeyVal as usize] = pubkey.x;
-                npc[Npc::EcdsaMessageAddr as usize] = message_addr.into();
+                npc[Npc::EcdsaMessageAddr as usize] = addrs.msg.into_felt();
                 npc[Npc::EcdsaMessageVal as usize] = message;
             });
 
@@ -632,17 +645,16 @@ impl CairoTrace for ExecutionTrace {
                     let x_and_y_offset = input_y_offset + addr_step;
                     let x_xor_y_offset = x_and_y_offset + addr_step;
                     let x_or_y_offset = Npc::BitwiseXOrYAddr as usize;
-                    let (input_x_addr, input_y_addr, x_and_y_addr, x_xor_y_addr, x_or_y_addr) =
-                        instance.mem_addr(initial_bitwise_address);
-                    npc[input_x_offset] = input_x_addr.into();
+                    let addrs = instance.mem_addr(initial_bitwise_address);
+                    npc[input_x_offset] = addrs.x.into_felt();
                     npc[input_x_offset + 1] = bitwise_trace.x;
-                    npc[input_y_offset] = input_y_addr.into();
+                    npc[input_y_offset] = addrs.y.into_felt();
                     npc[input_y_offset + 1] = bitwise_trace.y;
-                    npc[x_and_y_offset] = x_and_y_addr.into();
+                    npc[x_and_y_offset] = addrs.x_and_y.into_felt();
                     npc[x_and_y_offset + 1] = bitwise_trace.x_and_y;
-                    npc[x_xor_y_offset] = x_xor_y_addr.into();
+                    npc[x_xor_y_offset] = addrs.x_xor_y.into_felt();
                     npc[x_xor_y_offset + 1] = bitwise_trace.x_xor_y;
-                    npc[x_or_y_offset] = x_or_y_addr.into();
+                    npc[x_or_y_offset] = addrs.x_or_y.into_felt();
                     npc[x_or_y_offset + 1] = bitwise_trace.x_or_y;
 
                     // return the diluted pool
@@ -758,21 +770,20 @@ impl CairoTrace for ExecutionTrace {
 
                 // load EC op values into memory
                 let instance = ec_op_trace.instance;
-                let (p_x_addr, p_y_addr, q_x_addr, q_y_addr, m_addr, r_x_addr, r_y_addr) =
-                    instance.mem_addr(initial_ec_op_address);
-                npc[Npc::EcOpPXAddr as usize] = p_x_addr.into();
+                let addrs = instance.mem_addr(initial_ec_op_address);
+                npc[Npc::EcOpPXAddr as usize] = addrs.p_x.into_felt();
                 npc[Npc::EcOpPXVal as usize] = ec_op_trace.p.x;
-                npc[Npc::EcOpPYAddr as usize] = p_y_addr.into();
+                npc[Npc::EcOpPYAddr as usize] = addrs.p_y.into_felt();
                 npc[Npc::EcOpPYVal as usize] = ec_op_trace.p.y;
-                npc[Npc::EcOpQXAddr as usize] = q_x_addr.into();
+                npc[Npc::EcOpQXAddr as usize] = addrs.q_x.into_felt();
                 npc[Npc::EcOpQXVal as usize] = ec_op_trace.q.x;
-                npc[Npc::EcOpQYAddr as usize] = q_y_addr.into();
+                npc[Npc::EcOpQYAddr as usize] = addrs.q_y.into_felt();
                 npc[Npc::EcOpQYVal as usize] = ec_op_trace.q.y;
-                npc[Npc::EcOpMAddr as usize] = m_addr.into();
+                npc[Npc::EcOpMAddr as usize] = addrs.m.into_felt();
                 npc[Npc::EcOpMVal as usize] = ec_op_trace.m;
-                npc[Npc::EcOpRXAddr as usize] = r_x_addr.into();
+                npc[Npc::EcOpRXAddr as usize] = addrs.r_x.into_felt();
                 npc[Npc::EcOpRXVal as usize] = ec_op_trace.r.x;
-                npc[Npc::EcOpRYAddr as usize] = r_y_addr.into();
+                npc[Npc::EcOpRYAddr as usize] = addrs.r_y.into_felt();
                 npc[Npc::EcOpRYVal as usize] = ec_op_trace.r.y;
             });
 
@@ -865,25 +876,18 @@ impl CairoTrace for ExecutionTrace {
 
                 // load EC op values into memory
                 let instance = poseidon_trace.instance;
-                let (
-                    input0_addr,
-                    input1_addr,
-                    input2_addr,
-                    output0_addr,
-                    output1_addr,
-                    output2_addr,
-                ) = instance.mem_addr(initial_poseidon_address);
-                npc[Npc::PoseidonInput0Addr as usize] = input0_addr.into();
+                let addrs = instance.mem_addr(initial_poseidon_address);
+                npc[Npc::PoseidonInput0Addr as usize] = addrs.input0.into_felt();
                 npc[Npc::PoseidonInput0Val as usize] = poseidon_trace.input0;
-                npc[Npc::PoseidonInput1Addr as usize] = input1_addr.into();
+                npc[Npc::PoseidonInput1Addr as usize] = addrs.input1.into_felt();
                 npc[Npc::PoseidonInput1Val as usize] = poseidon_trace.input1;
-                npc[Npc::PoseidonInput2Addr as usize] = input2_addr.into();
+                npc[Npc::PoseidonInput2Addr as usize] = addrs.input2.into_felt();
                 npc[Npc::PoseidonInput2Val as usize] = poseidon_trace.input2;
-                npc[Npc::PoseidonOutput0Addr as usize] = output0_addr.into();
+                npc[Npc::PoseidonOutput0Addr as usize] = addrs.output0.into_felt();
                 npc[Npc::PoseidonOutput0Val as usize] = poseidon_trace.output0;
-                npc[Npc::PoseidonOutput1Addr as usize] = output1_addr.into();
+                npc[Npc::PoseidonOutput1Addr as usize] = addrs.output1.into_felt();
                 npc[Npc::PoseidonOutput1Val as usize] = poseidon_trace.output1;
-                npc[Npc::PoseidonOutput2Addr as usize] = output2_addr.into();
+                npc[Npc::PoseidonOutput2Addr as usize] = addrs.output2.into_felt();
                 npc[Npc::PoseidonOutput2Val as usize] = poseidon_trace.output2;
             });
 
@@ -897,7 +901,9 @@ impl CairoTrace for ExecutionTrace {
             let mut sorted_memory_accesses: Vec<MemoryEntry<Fp>> = npc_column
                 .array_chunks()
                 .map(|&[address, value]| {
-                    let address = u32::try_from(U256::from_limbs(address.into_bigint().0)).unwrap();
+                    let address = MemoryAddress(
+                        u32::try_from(U256::from_limbs(address.into_bigint().0)).unwrap(),
+                    );
                     MemoryEntry { value, address }
                 })
                 .chain(public_memory.clone())
@@ -907,7 +913,7 @@ impl CairoTrace for ExecutionTrace {
             for [a, b] in sorted_memory_accesses.array_windows() {
                 let a_addr = a.address;
                 let b_addr = b.address;
-                for padding_addr in a_addr.saturating_add(1)..b_addr {
+                for padding_addr in a_addr.0.saturating_add(1)..b_addr.0 {
                     padding_addrs.push(padding_addr)
                 }
             }
@@ -927,9 +933,11 @@ impl CairoTrace for ExecutionTrace {
         let memory_accesses: Vec<MemoryEntry<Fp>> = npc_column
             .array_chunks()
             .map(|&[address_felt, value_felt]| MemoryEntry {
-                address: U256::from_limbs(address_felt.into_bigint().0)
-                    .try_into()
-                    .unwrap(),
+                address: MemoryAddress(
+                    U256::from_limbs(address_felt.into_bigint().0)
+                        .try_into()
+                        .unwrap(),
+                ),
                 value: value_felt,
             })
             .collect();
@@ -941,7 +949,7 @@ impl CairoTrace for ExecutionTrace {
         );
         let memory_column = ordered_memory_accesses
             .into_iter()
-            .flat_map(|e| [e.address.into(), e.value])
+            .flat_map(|e| [e.address.into_felt(), e.value])
             .collect::<Vec<Fp>>()
             .to_vec_in(GpuAllocator);
 
@@ -986,6 +994,86 @@ impl CairoTrace for ExecutionTrace {
     }
 }
 
+impl ExecutionTrace {
+    /// Running product of the memory permutation argument over the memory
+    /// accesses in execution order (see [MemoryArgument]). Public memory
+    /// accesses are replaced by dummy `(0, 0)` accesses.
+    pub fn memory_running_product_column(&self, z: Fp, alpha: Fp) -> Vec<Fp> {
+        let accesses = self
+            .npc_column
+            .array_chunks()
+            .map(|&[a, v]: &[Fp; MEMORY_STEP]| (a, v));
+        MemoryArgument::running_product_column(accesses, alpha, z)
+    }
+
+    /// Running product of the memory permutation argument over the memory
+    /// accesses sorted by address
+    pub fn sorted_memory_running_product_column(&self, z: Fp, alpha: Fp) -> Vec<Fp> {
+        let accesses = self
+            .memory_column
+            .array_chunks()
+            .map(|&[a, v]: &[Fp; MEMORY_STEP]| (a, v));
+        MemoryArgument::running_product_column(accesses, alpha, z)
+    }
+
+    /// Checks the final products of the execution ordered and address ordered
+    /// columns agree. The public memory cells are dummy accesses in execution
+    /// order so the ratio of the final products must equal the public memory
+    /// quotient rather than one.
+    pub fn check_final_products_equal(&self, z: Fp, alpha: Fp) -> Result<(), MemoryProductError> {
+        let public_memory_quotient = compute_public_memory_quotient::<PUBLIC_MEMORY_STEP, Fp, Fp>(
+            z,
+            alpha,
+            self.base_columns().num_rows(),
+            &self.public_memory,
+            self.padding_entry,
+        );
+        check_final_products(
+            &self.memory_running_product_column(z, alpha),
+            &self.sorted_memory_running_product_column(z, alpha),
+            public_memory_quotient,
+        )
+    }
+}
+
+/// Errors checking the final products of the memory permutation argument
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryProductError {
+    EmptyColumn,
+    Mismatch,
+}
+
+impl Display for MemoryProductError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyColumn => write!(f, "memory running product column is empty"),
+            Self::Mismatch => write!(f, "memory running products don't match"),
+        }
+    }
+}
+
+impl Error for MemoryProductError {}
+
+/// Checks the last item of the execution ordered running product equals the
+/// last item of the address ordered running product times the public memory
+/// quotient
+fn check_final_products(
+    execution_column: &[Fp],
+    sorted_column: &[Fp],
+    public_memory_quotient: Fp,
+) -> Result<(), MemoryProductError> {
+    let (Some(&execution_product), Some(&sorted_product)) =
+        (execution_column.last(), sorted_column.last())
+    else {
+        return Err(MemoryProductError::EmptyColumn);
+    };
+    if execution_product == public_memory_quotient * sorted_product {
+        Ok(())
+    } else {
+        Err(MemoryProductError::Mismatch)
+    }
+}
+
 impl Trace for ExecutionTrace {
     type Fp = Fp;
     type Fq = Fp;
@@ -1001,18 +1089,8 @@ impl Trace for ExecutionTrace {
         // see distinction between (a', v') and (a, v) in the Cairo paper.
         let z = challenges[MemoryPermutation::Z];
         let alpha = challenges[MemoryPermutation::A];
-        let program_order_accesses = self.npc_column.array_chunks::<MEMORY_STEP>();
-        let address_order_accesses = self.memory_column.array_chunks::<MEMORY_STEP>();
-        let mut mem_perm_numerators = Vec::new();
-        let mut mem_perm_denominators = Vec::new();
-        let mut numerator_acc = Fp::one();
-        let mut denominator_acc = Fp::one();
-        for (&[a, v], &[a_prime, v_prime]) in program_order_accesses.zip(address_order_accesses) {
-            numerator_acc *= z - (alpha * v + a);
-            denominator_acc *= z - (alpha * v_prime + a_prime);
-            mem_perm_numerators.push(numerator_acc);
-            mem_perm_denominators.push(denominator_acc);
-        }
+        let mem_perm_numerators = self.memory_running_product_column(z, alpha);
+        let mut mem_perm_denominators = self.sorted_memory_running_product_column(z, alpha);
         batch_inversion(&mut mem_perm_denominators);
         let mem_perm_denominators_inv = mem_perm_denominators;
 
@@ -1099,3 +1177,48 @@ impl Trace for ExecutionTrace {
         Some(Matrix::new(vec![permutation_column]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::check_final_products;
+    use super::MemoryProductError;
+    use crate::utils::MemoryArgument;
+    use ark_ff::One;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+
+    fn running_product(accesses: &[(u32, u32)]) -> Vec<Fp> {
+        let accesses = accesses.iter().map(|&(a, v)| (Fp::from(a), Fp::from(v)));
+        MemoryArgument::running_product_column(
+            accesses,
+            Fp::from(987654321u32),
+            Fp::from(123456789u32),
+        )
+    }
+
+    #[test]
+    fn final_products_match_for_permuted_accesses() {
+        let execution = running_product(&[(3, 7), (1, 5), (2, 6), (1, 5)]);
+        let sorted = running_product(&[(1, 5), (1, 5), (2, 6), (3, 7)]);
+
+        assert_eq!(Ok(()), check_final_products(&execution, &sorted, Fp::one()));
+    }
+
+    #[test]
+    fn final_products_mismatch_for_modified_access() {
+        let execution = running_product(&[(3, 7), (1, 5), (2, 6), (1, 5)]);
+        let sorted = running_product(&[(1, 5), (1, 5), (2, 9), (3, 7)]);
+
+        assert_eq!(
+            Err(MemoryProductError::Mismatch),
+            check_final_products(&execution, &sorted, Fp::one())
+        );
+    }
+
+    #[test]
+    fn final_products_of_empty_columns_is_an_error() {
+        assert_eq!(
+            Err(MemoryProductError::EmptyColumn),
+            check_final_products(&[], &[], Fp::one())
+        );
+    }
+}
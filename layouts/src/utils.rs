@@ -1,11 +1,10 @@
 use ark_ff::Field;
 use ark_ff::PrimeField;
+use binary::types::MemoryAddress;
 use binary::MemoryEntry;
 use ministark::utils::FieldVariant;
 use ministark::StarkExtensionOf;
 use ministark_gpu::GpuFftField;
-use num_traits::One;
-use num_traits::Zero;
 use ruint::aliases::U256;
 use ruint::uint;
 
@@ -32,11 +31,11 @@ pub fn compute_public_memory_quotient<
     // denominator = \prod_i( z - (addr_i + alpha * value_i) ),
     let denominator = public_memory
         .iter()
-        .map(|e| z - (alpha * e.value + Fp::from(e.address)))
+        .map(|e| z - (alpha * e.value + e.address.into_felt::<Fp>()))
         .product::<Fq>();
     let padding = {
         // padding = (z - (padding_addr + alpha * padding_value))^(S - N),
-        let padding_address = Fp::from(public_memory_padding.address);
+        let padding_address = public_memory_padding.address.into_felt::<Fp>();
         let padding_value = public_memory_padding.value;
         (z - (alpha * padding_value + padding_address)).pow([(s - n) as u64])
     };
@@ -45,6 +44,112 @@ pub fn compute_public_memory_quotient<
     numerator / (denominator * padding)
 }
 
+/// Grand products used by the memory permutation argument.
+/// Note section 9.7.2 from the Cairo whitepaper. Each memory access `(a, v)`
+/// is mapped to `z - (a + α * v)` where `z` and `α` are verifier challenges.
+/// The product over all accesses in execution order must match the product
+/// over the same accesses in address order.
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryArgument<F> {
+    pub alpha: F,
+    pub z: F,
+    pub execution_product: F,
+    pub sorted_product: F,
+}
+
+impl<F: Field> MemoryArgument<F> {
+    /// Computes the grand products of the execution ordered accesses and the
+    /// address ordered accesses.
+    pub fn compute(
+        execution_accesses: &[MemoryEntry<F>],
+        sorted_accesses: &[MemoryEntry<F>],
+        alpha: F,
+        z: F,
+    ) -> Self {
+        let product = |accesses: &[MemoryEntry<F>]| {
+            accesses
+                .iter()
+                .map(|e| z - (alpha * e.value + e.address.into_felt::<F>()))
+                .product::<F>()
+        };
+        Self {
+            alpha,
+            z,
+            execution_product: product(execution_accesses),
+            sorted_product: product(sorted_accesses),
+        }
+    }
+
+    /// Returns true if the execution ordered and address ordered products
+    /// match. Note that in Cairo the public memory cells are replaced with
+    /// dummy accesses in execution order so the products only match once the
+    /// public memory quotient is taken into account (see
+    /// [compute_public_memory_quotient]).
+    pub fn is_valid(&self) -> bool {
+        self.execution_product == self.sorted_product
+    }
+
+    /// Returns the intermediate products for a list of accesses of the form
+    /// `(address, value)` i.e. `p_i = p_{i-1} * (z - (a_i + α * v_i))`.
+    /// The `i`th item includes the `i`th access.
+    pub fn running_product_column(
+        accesses: impl IntoIterator<Item = (F, F)>,
+        alpha: F,
+        z: F,
+    ) -> Vec<F> {
+        let mut acc = F::ONE;
+        accesses
+            .into_iter()
+            .map(|(address, value)| {
+                acc *= z - (alpha * value + address);
+                acc
+            })
+            .collect()
+    }
+}
+
+/// Grand products used by the range check permutation argument. Each range
+/// checked value `v` is mapped to `z - v` where `z` is a verifier challenge.
+/// The product over the values in trace order must match the product over
+/// the sorted values.
+#[derive(Clone, Copy, Debug)]
+pub struct RangeCheckArgument<F> {
+    pub z: F,
+    pub unsorted_product: F,
+    pub sorted_product: F,
+}
+
+impl<F: Field> RangeCheckArgument<F> {
+    /// Computes the grand products `∏(z - unsorted_i)` and `∏(z - sorted_i)`
+    pub fn compute(unsorted: &[F], sorted: &[F], z: F) -> Self {
+        let product = |values: &[F]| values.iter().map(|&v| z - v).product::<F>();
+        Self {
+            z,
+            unsorted_product: product(unsorted),
+            sorted_product: product(sorted),
+        }
+    }
+
+    /// Returns true if the sorted values are a permutation of the unsorted
+    /// values (with high probability over the choice of `z`)
+    pub fn is_valid(&self) -> bool {
+        self.unsorted_product == self.sorted_product
+    }
+
+    /// Returns the intermediate products i.e. `p_i = p_{i-1} * (z - v_i)`.
+    /// The `i`th item includes the `i`th value.
+    pub fn running_product_column(values: &[F], z: F) -> Vec<F> {
+        let mut acc = F::ONE;
+        values
+            .iter()
+            .map(|&v| {
+                acc *= z - v;
+                acc
+            })
+            .collect()
+    }
+}
+
 /// Source: https://github.com/starkware-libs/starkex-contracts
 ///
 /// # Context
@@ -136,8 +241,8 @@ pub fn get_ordered_memory_accesses<const PUBLIC_MEMORY_STEP: usize, F: PrimeFiel
     // justification for this is explained in section 9.8 of the Cairo paper https://eprint.iacr.org/2021/1063.pdf.
     // SHARP starts the first address at address 1
     let (zeros, ordered_accesses) = ordered_accesses.split_at(num_pub_mem_cells);
-    assert!(zeros.iter().all(|e| e.address.is_zero()));
-    assert!(ordered_accesses[0].address.is_one());
+    assert!(zeros.iter().all(|e| e.address == MemoryAddress(0)));
+    assert_eq!(MemoryAddress(1), ordered_accesses[0].address);
 
     // check memory is "continuous" and "single valued"
     ordered_accesses
@@ -186,7 +291,7 @@ impl<F: PrimeField> MemoryPool<F> {
         // be filled with [(a:5, v:..), (a:6, v:..)] as padding.
         let mut padding_accesses = Vec::new();
         for &[a, b] in ordered_accesses.array_windows() {
-            for padding_addr in a.address.saturating_add(1)..b.address {
+            for padding_addr in (a.address.0.saturating_add(1)..b.address.0).map(MemoryAddress) {
                 padding_accesses.push(if a.address == padding_entry.address {
                     padding_entry
                 } else {
@@ -405,3 +510,79 @@ pub const fn map_into_fp_array<Fp: Field, Fq: Field, const N: usize>(
     }
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryArgument;
+    use super::RangeCheckArgument;
+    use binary::types::MemoryAddress;
+    use binary::MemoryEntry;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+
+    fn felts(values: &[u16]) -> Vec<Fp> {
+        values.iter().map(|&v| Fp::from(v)).collect()
+    }
+
+    fn entries(accesses: &[(u32, u16)]) -> Vec<MemoryEntry<Fp>> {
+        accesses
+            .iter()
+            .map(|&(address, value)| MemoryEntry {
+                address: MemoryAddress(address),
+                value: Fp::from(value),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn memory_argument_accepts_sorted_accesses() {
+        let execution = entries(&[(3, 7), (1, 5), (2, 6), (1, 5)]);
+        let sorted = entries(&[(1, 5), (1, 5), (2, 6), (3, 7)]);
+        let alpha = Fp::from(987654321u32);
+        let z = Fp::from(123456789u32);
+
+        let argument = MemoryArgument::compute(&execution, &sorted, alpha, z);
+
+        assert!(argument.is_valid());
+        let column = MemoryArgument::running_product_column(
+            sorted
+                .iter()
+                .map(|e| (e.address.into_felt::<Fp>(), e.value)),
+            alpha,
+            z,
+        );
+        assert_eq!(sorted.len(), column.len());
+        assert_eq!(argument.sorted_product, *column.last().unwrap());
+    }
+
+    #[test]
+    fn memory_argument_rejects_modified_value() {
+        let execution = entries(&[(3, 7), (1, 5), (2, 6)]);
+        let sorted = entries(&[(1, 5), (2, 8), (3, 7)]);
+        let alpha = Fp::from(987654321u32);
+        let z = Fp::from(123456789u32);
+
+        assert!(!MemoryArgument::compute(&execution, &sorted, alpha, z).is_valid());
+    }
+
+    #[test]
+    fn range_check_argument_accepts_permutation() {
+        let unsorted = felts(&[5, 1, 9, 1, 3]);
+        let sorted = felts(&[1, 1, 3, 5, 9]);
+        let z = Fp::from(123456789u32);
+
+        let argument = RangeCheckArgument::compute(&unsorted, &sorted, z);
+
+        assert!(argument.is_valid());
+        let column = RangeCheckArgument::running_product_column(&sorted, z);
+        assert_eq!(argument.sorted_product, *column.last().unwrap());
+    }
+
+    #[test]
+    fn range_check_argument_rejects_wrong_permutation() {
+        let unsorted = felts(&[5, 1, 9, 1, 3]);
+        let sorted = felts(&[1, 3, 3, 5, 9]);
+        let z = Fp::from(123456789u32);
+
+        assert!(!RangeCheckArgument::compute(&unsorted, &sorted, z).is_valid());
+    }
+}
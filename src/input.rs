@@ -1,13 +1,78 @@
-use binary::{AirPublicInput, Layout};
+use ark_ff::Field;
+use crypto::hash::poseidon::PoseidonHashFn;
+use binary::sharp;
+use binary::types::MemoryAddress;
+use binary::{AirPublicInput, Layout, Memory, MemoryEntry, MemorySegments, Segment, SegmentAddressMapper, SegmentKind};
 use ministark::hash::{ElementHashFn, Digest};
 use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
 use num_bigint::BigUint;
-use ruint::{aliases::U256, uint};
+use ruint::aliases::U256;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Errors when decoding a public input from a list of field elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputVecError {
+    /// The list ended before all values were decoded
+    TooShort,
+    /// The list has values left over after decoding
+    TooLong { extra: usize },
+    /// The value at `index` doesn't fit in its integer type
+    InvalidValue { index: usize },
+    /// The layout code doesn't match a supported layout
+    UnknownLayout,
+}
+
+impl Display for InputVecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooShort => write!(f, "public input ended unexpectedly"),
+            Self::TooLong { extra } => write!(f, "public input has {extra} unexpected values"),
+            Self::InvalidValue { index } => write!(f, "invalid value at index {index}"),
+            Self::UnknownLayout => write!(f, "unknown layout code"),
+        }
+    }
+}
+
+impl Error for InputVecError {}
+
+/// A segment the layout defines is missing from the public input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingSegmentError {
+    pub segment: SegmentKind,
+}
+
+impl Display for MissingSegmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "public input has no {:?} segment", self.segment)
+    }
+}
+
+impl Error for MissingSegmentError {}
+
+/// Returns a segment the layout defines or an error if it's missing
+fn required_segment(
+    segment: Option<Segment>,
+    kind: SegmentKind,
+) -> Result<Segment, MissingSegmentError> {
+    segment.ok_or(MissingSegmentError { segment: kind })
+}
+
+/// Public memory page as registered with SHARP's memory page fact registry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryPage {
+    pub begin_addr: MemoryAddress,
+    /// Number of entries in the page
+    pub size: u32,
+    pub hash: [u8; 32],
+}
 
 pub struct CairoAuxInput<'a>(pub &'a AirPublicInput<Fp>);
 
 impl<'a> CairoAuxInput<'a> {
-    fn base_values(&self) -> Vec<U256> {
+    /// Values common to all layouts. The output segment is only included if
+    /// the layout has an output builtin.
+    fn base_values(&self) -> Result<Vec<U256>, MissingSegmentError> {
         const OFFSET_LOG_N_STEPS: usize = 0;
         const OFFSET_RC_MIN: usize = 1;
         const OFFSET_RC_MAX: usize = 2;
@@ -18,96 +83,99 @@ impl<'a> CairoAuxInput<'a> {
         const OFFSET_EXECUTION_STOP_PTR: usize = 7;
         const OFFSET_OUTPUT_BEGIN_ADDR: usize = 8;
         const OFFSET_OUTPUT_STOP_PTR: usize = 9;
-        const OFFSET_PEDERSEN_BEGIN_ADDR: usize = 10;
-        const OFFSET_PEDERSEN_STOP_PTR: usize = 11;
-        const OFFSET_RANGE_CHECK_BEGIN_ADDR: usize = 12;
-        const OFFSET_RANGE_CHECK_STOP_PTR: usize = 13;
 
         let segments = self.0.memory_segments;
 
-        const NUM_VALS: usize = OFFSET_RANGE_CHECK_STOP_PTR + 1;
-        let mut vals = [None; NUM_VALS];
-        vals[OFFSET_LOG_N_STEPS] = Some(U256::from(self.0.n_steps.ilog2()));
-        vals[OFFSET_RC_MIN] = Some(U256::from(self.0.rc_min));
-        vals[OFFSET_RC_MAX] = Some(U256::from(self.0.rc_max));
-        vals[OFFSET_LAYOUT_CODE] = Some(U256::from(self.0.layout.sharp_code()));
-        vals[OFFSET_PROGRAM_BEGIN_ADDR] = Some(U256::from(segments.program.begin_addr));
-        vals[OFFSET_PROGRAM_STOP_PTR] = Some(U256::from(segments.program.stop_ptr));
-        vals[OFFSET_EXECUTION_BEGIN_ADDR] = Some(U256::from(segments.execution.begin_addr));
-        vals[OFFSET_EXECUTION_STOP_PTR] = Some(U256::from(segments.execution.stop_ptr));
-        vals[OFFSET_OUTPUT_BEGIN_ADDR] = segments.output.map(|s| U256::from(s.begin_addr));
-        vals[OFFSET_OUTPUT_STOP_PTR] = segments.output.map(|s| U256::from(s.stop_ptr));
-        vals[OFFSET_PEDERSEN_BEGIN_ADDR] = segments.pedersen.map(|s| U256::from(s.begin_addr));
-        vals[OFFSET_PEDERSEN_STOP_PTR] = segments.pedersen.map(|s| U256::from(s.stop_ptr));
-        vals[OFFSET_RANGE_CHECK_BEGIN_ADDR] =
-            segments.range_check.map(|s| U256::from(s.begin_addr));
-        vals[OFFSET_RANGE_CHECK_STOP_PTR] = segments.range_check.map(|s| U256::from(s.stop_ptr));
-        vals.map(Option::unwrap).to_vec()
-    }
-
-    fn layout_specific_values(&self) -> Vec<U256> {
-        let segments = self.0.memory_segments;
-        let public_memory_padding = self.0.public_memory_padding();
+        const NUM_VALS: usize = OFFSET_OUTPUT_STOP_PTR + 1;
+        let mut vals = [U256::ZERO; NUM_VALS];
+        vals[OFFSET_LOG_N_STEPS] = U256::from(self.0.n_steps.ilog2());
+        vals[OFFSET_RC_MIN] = U256::from(self.0.rc_min);
+        vals[OFFSET_RC_MAX] = U256::from(self.0.rc_max);
+        vals[OFFSET_LAYOUT_CODE] = self.0.layout.sharp_code();
+        vals[OFFSET_PROGRAM_BEGIN_ADDR] = U256::from(segments.program.begin_addr.0);
+        vals[OFFSET_PROGRAM_STOP_PTR] = U256::from(segments.program.stop_ptr.0);
+        vals[OFFSET_EXECUTION_BEGIN_ADDR] = U256::from(segments.execution.begin_addr.0);
+        vals[OFFSET_EXECUTION_STOP_PTR] = U256::from(segments.execution.stop_ptr.0);
+        if !self.0.layout.has_output() {
+            return Ok(vals[..OFFSET_OUTPUT_BEGIN_ADDR].to_vec());
+        }
+        let output = required_segment(segments.output, SegmentKind::Output)?;
+        vals[OFFSET_OUTPUT_BEGIN_ADDR] = U256::from(output.begin_addr.0);
+        vals[OFFSET_OUTPUT_STOP_PTR] = U256::from(output.stop_ptr.0);
+        Ok(vals.to_vec())
+    }
 
+    fn layout_specific_values(&self) -> Result<Vec<U256>, MissingSegmentError> {
         match self.0.layout {
-            Layout::Starknet => {
-                const OFFSET_ECDSA_BEGIN_ADDR: usize = 0;
-                const OFFSET_ECDSA_STOP_PTR: usize = 1;
-                const OFFSET_BITWISE_BEGIN_ADDR: usize = 2;
-                const OFFSET_BITWISE_STOP_ADDR: usize = 3;
-                const OFFSET_EC_OP_BEGIN_ADDR: usize = 4;
-                const OFFSET_EC_OP_STOP_ADDR: usize = 5;
-                const OFFSET_POSEIDON_BEGIN_ADDR: usize = 6;
-                const OFFSET_POSEIDON_STOP_PTR: usize = 7;
-                const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 8;
-                const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 9;
-                const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 10;
-
-                const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
-                let mut vals = [None; NUM_VALS];
-                vals[OFFSET_ECDSA_BEGIN_ADDR] = segments.ecdsa.map(|s| U256::from(s.begin_addr));
-                vals[OFFSET_ECDSA_STOP_PTR] = segments.ecdsa.map(|s| U256::from(s.stop_ptr));
-                vals[OFFSET_BITWISE_BEGIN_ADDR] =
-                    segments.bitwise.map(|s| U256::from(s.begin_addr));
-                vals[OFFSET_BITWISE_STOP_ADDR] = segments.bitwise.map(|s| U256::from(s.stop_ptr));
-                vals[OFFSET_EC_OP_BEGIN_ADDR] = segments.ec_op.map(|s| U256::from(s.begin_addr));
-                vals[OFFSET_EC_OP_STOP_ADDR] = segments.ec_op.map(|s| U256::from(s.stop_ptr));
-                vals[OFFSET_POSEIDON_BEGIN_ADDR] =
-                    segments.poseidon.map(|s| U256::from(s.begin_addr));
-                vals[OFFSET_POSEIDON_STOP_PTR] = segments.poseidon.map(|s| U256::from(s.stop_ptr));
-                vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] =
-                    Some(U256::from(public_memory_padding.address));
-                vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
-                    Some(U256::from::<BigUint>(public_memory_padding.value.into()));
-                // Only 1 memory page currently for the main memory page
-                // TODO: support more memory pages
-                vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = Some(uint!(1_U256));
-                vals.map(Option::unwrap).to_vec()
+            Layout::Plain => PlainLayout::layout_values(self),
+            Layout::Small | Layout::Dex => SmallLayout::layout_values(self),
+            Layout::Recursive | Layout::RecursiveLargeOutput => {
+                RecursiveLayout::layout_values(self)
+            }
+            Layout::Starknet => StarknetLayout::layout_values(self),
+            Layout::AllSolidity => AllSolidityLayout::layout_values(self),
+            Layout::StarknetWithKeccak => StarknetWithKeccakLayout::layout_values(self),
+        }
+    }
+
+    /// Splits the public memory into pages. The first page is the main page
+    /// and holds the program and execution segment entries. Entries of every
+    /// other segment get their own continuous page if their addresses are
+    /// contiguous. Otherwise they stay in the main page. Main page entries are
+    /// in public memory order followed by the entries of non-continuous
+    /// segments sorted by address. Continuous pages are sorted by address.
+    fn public_memory_pages(&self) -> Vec<Vec<MemoryEntry<Fp>>> {
+        let segments = &self.0.memory_segments;
+        let mapper = SegmentAddressMapper::new(segments);
+        let mut main_page = Vec::new();
+        let mut segment_pages = SegmentKind::ALL.map(|_| Vec::new());
+        for &entry in &self.0.public_memory {
+            match mapper.classify(entry.address) {
+                None | Some((SegmentKind::Program | SegmentKind::Execution, _)) => {
+                    main_page.push(entry)
+                }
+                Some((kind, _)) => segment_pages[kind as usize].push(entry),
             }
-            Layout::Recursive => {
-                const OFFSET_BITWISE_BEGIN_ADDR: usize = 0;
-                const OFFSET_BITWISE_STOP_ADDR: usize = 1;
-                const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 2;
-                const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 3;
-                const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 4;
-
-                const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
-                let mut vals = [None; NUM_VALS];
-
-                vals[OFFSET_BITWISE_BEGIN_ADDR] =
-                    segments.bitwise.map(|s| U256::from(s.begin_addr));
-                vals[OFFSET_BITWISE_STOP_ADDR] = segments.bitwise.map(|s| U256::from(s.stop_ptr));
-                vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] =
-                    Some(U256::from(public_memory_padding.address));
-                vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
-                    Some(U256::from::<BigUint>(public_memory_padding.value.into()));
-                // Only 1 memory page currently for the main memory page
-                // TODO: support more memory pages
-                vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = Some(uint!(1_U256));
-                vals.map(Option::unwrap).to_vec()
+        }
+
+        let mut pages = vec![main_page];
+        for mut page in segment_pages.into_iter().filter(|page| !page.is_empty()) {
+            page.sort_by_key(|entry| entry.address);
+            let is_continuous = page.windows(2).all(|w| w[0].address + 1 == w[1].address);
+            if is_continuous {
+                pages.push(page);
+            } else {
+                pages[0].extend(page);
             }
-            _ => unimplemented!(),
         }
+        pages
+    }
+
+    /// Number of public memory pages. See [Self::memory_pages].
+    pub fn num_memory_pages(&self) -> usize {
+        self.public_memory_pages().len()
+    }
+
+    /// Returns the public memory pages. The main page is first and its hash
+    /// is of the `(address, value)` pairs. All other pages are continuous and
+    /// their hash is of the values only.
+    pub fn memory_pages<H: ElementHashFn<Fp>>(&self) -> Vec<MemoryPage> {
+        let pages = self.public_memory_pages();
+        pages
+            .iter()
+            .enumerate()
+            .map(|(i, entries)| MemoryPage {
+                begin_addr: entries
+                    .first()
+                    .map_or(MemoryAddress(0), |entry| entry.address),
+                size: entries.len() as u32,
+                hash: if i == 0 {
+                    hash_memory_page::<H>(entries)
+                } else {
+                    H::hash_elements(entries.iter().map(|entry| entry.value)).as_bytes()
+                },
+            })
+            .collect()
     }
 
     fn memory_page_values<H: ElementHashFn<Fp>>(&self) -> Vec<U256> {
@@ -117,35 +185,801 @@ impl<'a> CairoAuxInput<'a> {
         // * First address in the page (this field is not included for the first page).
         // * Page size. (number of memory pairs)
         // * Page hash (hash of memory pairs)
-        // TODO: support other memory pages
-        const _PAGE_INFO_ADDRESS_OFFSET: usize = 0;
-        const _PAGE_INFO_SIZE_OFFSET: usize = 1;
-        const _PAGE_INFO_HASH_OFFSET: usize = 2;
-
-        // Hash the address value pairs of the main memory page
-        let main_page_hash: [u8; 32] = {
-            let memory_elements = self
-                .0
-                .public_memory
-                .iter()
-                .flat_map(|e| [e.address.into(), e.value]);
-            H::hash_elements(memory_elements).as_bytes()
-        };
+        const PAGE_INFO_ADDRESS_OFFSET: usize = 0;
+        const PAGE_INFO_SIZE_OFFSET: usize = 1;
+        const PAGE_INFO_HASH_OFFSET: usize = 2;
 
-        // NOTE: no address main memory page because It's implicitly "1".
-        let mut main_page = [None; 2];
-        main_page[0] = Some(U256::from(self.0.public_memory.len()));
-        main_page[1] = Some(U256::try_from_be_slice(&main_page_hash).unwrap());
+        let mut vals = Vec::new();
+        for (i, page) in self.memory_pages::<H>().into_iter().enumerate() {
+            let mut page_info = [None; 3];
+            page_info[PAGE_INFO_ADDRESS_OFFSET] = Some(U256::from(page.begin_addr.0));
+            page_info[PAGE_INFO_SIZE_OFFSET] = Some(U256::from(page.size));
+            page_info[PAGE_INFO_HASH_OFFSET] = Some(U256::try_from_be_slice(&page.hash).unwrap());
+            let page_info = page_info.map(Option::unwrap);
+            // NOTE: no address for the main memory page because It's implicitly "1".
+            if i == 0 {
+                vals.extend(&page_info[PAGE_INFO_SIZE_OFFSET..]);
+            } else {
+                vals.extend(page_info);
+            }
+        }
+        vals
+    }
 
-        main_page.map(Option::unwrap).to_vec()
+    /// Values written to the output segment. None if the layout has no
+    /// output builtin.
+    pub fn output_values(&self, memory: &Memory<Fp>) -> Option<Vec<MemoryEntry<Fp>>> {
+        let output = self.0.memory_segments.output?;
+        Some(memory.read_segment(output))
     }
 
-    pub fn public_input_elements<H: ElementHashFn<Fp>>(&self) -> Vec<U256> {
-        [
-            self.base_values(),
-            self.layout_specific_values(),
+    /// Errors if a segment defined by the layout is missing
+    pub fn public_input_elements<H: ElementHashFn<Fp>>(
+        &self,
+    ) -> Result<Vec<U256>, MissingSegmentError> {
+        Ok([
+            self.base_values()?,
+            self.layout_specific_values()?,
             self.memory_page_values::<H>(),
         ]
-        .concat()
+        .concat())
+    }
+
+    /// Checks the memory page values in `public_input_elements`, as returned
+    /// by [Self::public_input_elements], match hashing the public memory
+    /// entries directly. Entries are split into pages and ordered by
+    /// [Self::public_memory_pages], the same as when serializing. Catches
+    /// serialized page hashes that diverge from the memory they should commit
+    /// to.
+    pub fn verify_public_memory_hash<H: ElementHashFn<Fp>>(
+        &self,
+        public_input_elements: &[U256],
+    ) -> bool {
+        let (Ok(base_values), Ok(layout_values)) =
+            (self.base_values(), self.layout_specific_values())
+        else {
+            return false;
+        };
+        let offset = base_values.len() + layout_values.len();
+        public_input_elements.get(offset..) == Some(&*self.memory_page_values::<H>())
+    }
+
+    /// Flattens the public input into field elements of the form:
+    /// `[initial_pc, initial_ap, final_pc, final_ap, log_n_steps, rc_min,
+    /// rc_max, layout_code, builtin segments..., public memory...]`.
+    /// Each builtin segment (output, pedersen, range_check, ecdsa, bitwise,
+    /// ec_op, keccak, poseidon) is `[is_present, begin_addr, stop_ptr]`. The
+    /// public memory is its length followed by `[address, value]` pairs.
+    pub fn to_public_inputs_vec(&self) -> Vec<Fp> {
+        let public_input = self.0;
+        let segments = public_input.memory_segments;
+        let mut res = vec![
+            public_input.initial_pc().into_felt(),
+            public_input.initial_ap().into_felt(),
+            public_input.final_pc().into_felt(),
+            public_input.final_ap().into_felt(),
+            public_input.n_steps.ilog2().into(),
+            public_input.rc_min.into(),
+            public_input.rc_max.into(),
+            BigUint::from(public_input.layout.sharp_code()).into(),
+        ];
+        for segment in [
+            segments.output,
+            segments.pedersen,
+            segments.range_check,
+            segments.ecdsa,
+            segments.bitwise,
+            segments.ec_op,
+            segments.keccak,
+            segments.poseidon,
+        ] {
+            res.extend(match segment {
+                Some(Segment {
+                    begin_addr,
+                    stop_ptr,
+                }) => [Fp::ONE, begin_addr.into_felt(), stop_ptr.into_felt()],
+                None => [Fp::ZERO; 3],
+            });
+        }
+        res.push((public_input.public_memory.len() as u64).into());
+        for &MemoryEntry { address, value } in &public_input.public_memory {
+            res.extend([address.into_felt(), value]);
+        }
+        res
+    }
+
+    /// Inverse of [CairoAuxInput::to_public_inputs_vec]. Returns an owned
+    /// public input since [CairoAuxInput] only borrows one.
+    pub fn from_public_inputs_vec(inputs: &[Fp]) -> Result<AirPublicInput<Fp>, InputVecError> {
+        let mut reader = InputVecReader { inputs, index: 0 };
+        let initial_pc = MemoryAddress(reader.next_int()?);
+        let initial_ap = MemoryAddress(reader.next_int()?);
+        let final_pc = MemoryAddress(reader.next_int()?);
+        let final_ap = MemoryAddress(reader.next_int()?);
+        let log_n_steps: u32 = reader.next_int()?;
+        let n_steps = 1u64
+            .checked_shl(log_n_steps)
+            .ok_or(InputVecError::InvalidValue { index: 4 })?;
+        let rc_min = reader.next_int()?;
+        let rc_max = reader.next_int()?;
+        let layout_code = reader.next()?;
+        let layout = Layout::from_sharp_code(U256::from::<BigUint>(layout_code.into()))
+            .map_err(|_| InputVecError::UnknownLayout)?;
+        let mut builtin_segments = [None; 8];
+        for segment in &mut builtin_segments {
+            let is_present = reader.next()?;
+            let begin_addr = MemoryAddress(reader.next_int()?);
+            let stop_ptr = MemoryAddress(reader.next_int()?);
+            if is_present == Fp::ONE {
+                *segment = Some(Segment {
+                    begin_addr,
+                    stop_ptr,
+                });
+            }
+        }
+        let [output, pedersen, range_check, ecdsa, bitwise, ec_op, keccak, poseidon] =
+            builtin_segments;
+        let num_entries: usize = reader.next_int()?;
+        let public_memory = (0..num_entries)
+            .map(|_| {
+                let address = MemoryAddress(reader.next_int()?);
+                let value = reader.next()?;
+                Ok(MemoryEntry { address, value })
+            })
+            .collect::<Result<Vec<_>, InputVecError>>()?;
+        let extra = inputs.len() - reader.index;
+        if extra != 0 {
+            return Err(InputVecError::TooLong { extra });
+        }
+
+        Ok(AirPublicInput {
+            rc_min,
+            rc_max,
+            n_steps,
+            layout,
+            memory_segments: MemorySegments {
+                program: Segment {
+                    begin_addr: initial_pc,
+                    stop_ptr: final_pc,
+                },
+                execution: Segment {
+                    begin_addr: initial_ap,
+                    stop_ptr: final_ap,
+                },
+                output,
+                pedersen,
+                range_check,
+                ecdsa,
+                bitwise,
+                ec_op,
+                poseidon,
+                keccak,
+            },
+            public_memory,
+        })
+    }
+}
+
+/// Hashes the `(address, value)` pairs of a memory page, flattened the same
+/// way as [sharp::hash_memory_page], with an arbitrary element hash
+fn hash_memory_page<H: ElementHashFn<Fp>>(entries: &[MemoryEntry<Fp>]) -> [u8; 32] {
+    H::hash_elements(sharp::page_elements(entries)).as_bytes()
+}
+
+/// Hash of a memory page for layouts that use Poseidon for the public memory
+/// i.e. `poseidon_hash_many` of the `(address, value)` pairs
+pub fn poseidon_hash_memory_page(entries: &[MemoryEntry<Fp>]) -> [u8; 32] {
+    hash_memory_page::<PoseidonHashFn>(entries)
+}
+
+/// Reads values from a flattened public input
+struct InputVecReader<'a> {
+    inputs: &'a [Fp],
+    index: usize,
+}
+
+impl<'a> InputVecReader<'a> {
+    fn next(&mut self) -> Result<Fp, InputVecError> {
+        let value = *self.inputs.get(self.index).ok_or(InputVecError::TooShort)?;
+        self.index += 1;
+        Ok(value)
+    }
+
+    fn next_int<T: TryFrom<BigUint>>(&mut self) -> Result<T, InputVecError> {
+        let index = self.index;
+        let value = BigUint::from(self.next()?);
+        T::try_from(value).map_err(|_| InputVecError::InvalidValue { index })
+    }
+}
+
+/// Serializes the layout specific values of the public input in the format
+/// expected by SHARP's verifiers. Errors if a segment the layout defines is
+/// missing.
+pub trait LayoutSharpSerializer {
+    fn layout_values(aux: &CairoAuxInput) -> Result<Vec<U256>, MissingSegmentError>;
+}
+
+pub struct PlainLayout;
+
+impl LayoutSharpSerializer for PlainLayout {
+    fn layout_values(aux: &CairoAuxInput) -> Result<Vec<U256>, MissingSegmentError> {
+        const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 0;
+        const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 1;
+        const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 2;
+
+        let public_memory_padding = aux.0.public_memory_padding();
+
+        const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
+        let mut vals = [U256::ZERO; NUM_VALS];
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] = U256::from(public_memory_padding.address.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
+            U256::from::<BigUint>(public_memory_padding.value.into());
+        vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = U256::from(aux.num_memory_pages());
+        Ok(vals.to_vec())
+    }
+}
+
+/// Serializer for the small and dex layouts. Both have the same builtins.
+pub struct SmallLayout;
+
+impl LayoutSharpSerializer for SmallLayout {
+    fn layout_values(aux: &CairoAuxInput) -> Result<Vec<U256>, MissingSegmentError> {
+        const OFFSET_PEDERSEN_BEGIN_ADDR: usize = 0;
+        const OFFSET_PEDERSEN_STOP_PTR: usize = 1;
+        const OFFSET_RANGE_CHECK_BEGIN_ADDR: usize = 2;
+        const OFFSET_RANGE_CHECK_STOP_PTR: usize = 3;
+        const OFFSET_ECDSA_BEGIN_ADDR: usize = 4;
+        const OFFSET_ECDSA_STOP_PTR: usize = 5;
+        const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 6;
+        const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 7;
+        const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 8;
+
+        let segments = aux.0.memory_segments;
+        let pedersen = required_segment(segments.pedersen, SegmentKind::Pedersen)?;
+        let range_check = required_segment(segments.range_check, SegmentKind::RangeCheck)?;
+        let ecdsa = required_segment(segments.ecdsa, SegmentKind::Ecdsa)?;
+        let public_memory_padding = aux.0.public_memory_padding();
+
+        const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
+        let mut vals = [U256::ZERO; NUM_VALS];
+        vals[OFFSET_PEDERSEN_BEGIN_ADDR] = U256::from(pedersen.begin_addr.0);
+        vals[OFFSET_PEDERSEN_STOP_PTR] = U256::from(pedersen.stop_ptr.0);
+        vals[OFFSET_RANGE_CHECK_BEGIN_ADDR] = U256::from(range_check.begin_addr.0);
+        vals[OFFSET_RANGE_CHECK_STOP_PTR] = U256::from(range_check.stop_ptr.0);
+        vals[OFFSET_ECDSA_BEGIN_ADDR] = U256::from(ecdsa.begin_addr.0);
+        vals[OFFSET_ECDSA_STOP_PTR] = U256::from(ecdsa.stop_ptr.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] = U256::from(public_memory_padding.address.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
+            U256::from::<BigUint>(public_memory_padding.value.into());
+        vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = U256::from(aux.num_memory_pages());
+        Ok(vals.to_vec())
+    }
+}
+
+pub struct StarknetLayout;
+
+impl LayoutSharpSerializer for StarknetLayout {
+    fn layout_values(aux: &CairoAuxInput) -> Result<Vec<U256>, MissingSegmentError> {
+        const OFFSET_PEDERSEN_BEGIN_ADDR: usize = 0;
+        const OFFSET_PEDERSEN_STOP_PTR: usize = 1;
+        const OFFSET_RANGE_CHECK_BEGIN_ADDR: usize = 2;
+        const OFFSET_RANGE_CHECK_STOP_PTR: usize = 3;
+        const OFFSET_ECDSA_BEGIN_ADDR: usize = 4;
+        const OFFSET_ECDSA_STOP_PTR: usize = 5;
+        const OFFSET_BITWISE_BEGIN_ADDR: usize = 6;
+        const OFFSET_BITWISE_STOP_ADDR: usize = 7;
+        const OFFSET_EC_OP_BEGIN_ADDR: usize = 8;
+        const OFFSET_EC_OP_STOP_ADDR: usize = 9;
+        const OFFSET_POSEIDON_BEGIN_ADDR: usize = 10;
+        const OFFSET_POSEIDON_STOP_PTR: usize = 11;
+        const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 12;
+        const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 13;
+        const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 14;
+
+        let segments = aux.0.memory_segments;
+        let pedersen = required_segment(segments.pedersen, SegmentKind::Pedersen)?;
+        let range_check = required_segment(segments.range_check, SegmentKind::RangeCheck)?;
+        let ecdsa = required_segment(segments.ecdsa, SegmentKind::Ecdsa)?;
+        let bitwise = required_segment(segments.bitwise, SegmentKind::Bitwise)?;
+        let ec_op = required_segment(segments.ec_op, SegmentKind::EcOp)?;
+        let poseidon = required_segment(segments.poseidon, SegmentKind::Poseidon)?;
+        let public_memory_padding = aux.0.public_memory_padding();
+
+        const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
+        let mut vals = [U256::ZERO; NUM_VALS];
+        vals[OFFSET_PEDERSEN_BEGIN_ADDR] = U256::from(pedersen.begin_addr.0);
+        vals[OFFSET_PEDERSEN_STOP_PTR] = U256::from(pedersen.stop_ptr.0);
+        vals[OFFSET_RANGE_CHECK_BEGIN_ADDR] = U256::from(range_check.begin_addr.0);
+        vals[OFFSET_RANGE_CHECK_STOP_PTR] = U256::from(range_check.stop_ptr.0);
+        vals[OFFSET_ECDSA_BEGIN_ADDR] = U256::from(ecdsa.begin_addr.0);
+        vals[OFFSET_ECDSA_STOP_PTR] = U256::from(ecdsa.stop_ptr.0);
+        vals[OFFSET_BITWISE_BEGIN_ADDR] = U256::from(bitwise.begin_addr.0);
+        vals[OFFSET_BITWISE_STOP_ADDR] = U256::from(bitwise.stop_ptr.0);
+        vals[OFFSET_EC_OP_BEGIN_ADDR] = U256::from(ec_op.begin_addr.0);
+        vals[OFFSET_EC_OP_STOP_ADDR] = U256::from(ec_op.stop_ptr.0);
+        vals[OFFSET_POSEIDON_BEGIN_ADDR] = U256::from(poseidon.begin_addr.0);
+        vals[OFFSET_POSEIDON_STOP_PTR] = U256::from(poseidon.stop_ptr.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] = U256::from(public_memory_padding.address.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
+            U256::from::<BigUint>(public_memory_padding.value.into());
+        vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = U256::from(aux.num_memory_pages());
+        Ok(vals.to_vec())
+    }
+}
+
+pub struct StarknetWithKeccakLayout;
+
+impl LayoutSharpSerializer for StarknetWithKeccakLayout {
+    fn layout_values(aux: &CairoAuxInput) -> Result<Vec<U256>, MissingSegmentError> {
+        const OFFSET_PEDERSEN_BEGIN_ADDR: usize = 0;
+        const OFFSET_PEDERSEN_STOP_PTR: usize = 1;
+        const OFFSET_RANGE_CHECK_BEGIN_ADDR: usize = 2;
+        const OFFSET_RANGE_CHECK_STOP_PTR: usize = 3;
+        const OFFSET_ECDSA_BEGIN_ADDR: usize = 4;
+        const OFFSET_ECDSA_STOP_PTR: usize = 5;
+        const OFFSET_BITWISE_BEGIN_ADDR: usize = 6;
+        const OFFSET_BITWISE_STOP_ADDR: usize = 7;
+        const OFFSET_EC_OP_BEGIN_ADDR: usize = 8;
+        const OFFSET_EC_OP_STOP_ADDR: usize = 9;
+        const OFFSET_KECCAK_BEGIN_ADDR: usize = 10;
+        const OFFSET_KECCAK_STOP_PTR: usize = 11;
+        const OFFSET_POSEIDON_BEGIN_ADDR: usize = 12;
+        const OFFSET_POSEIDON_STOP_PTR: usize = 13;
+        const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 14;
+        const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 15;
+        const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 16;
+
+        let segments = aux.0.memory_segments;
+        let pedersen = required_segment(segments.pedersen, SegmentKind::Pedersen)?;
+        let range_check = required_segment(segments.range_check, SegmentKind::RangeCheck)?;
+        let ecdsa = required_segment(segments.ecdsa, SegmentKind::Ecdsa)?;
+        let bitwise = required_segment(segments.bitwise, SegmentKind::Bitwise)?;
+        let ec_op = required_segment(segments.ec_op, SegmentKind::EcOp)?;
+        let keccak = required_segment(segments.keccak, SegmentKind::Keccak)?;
+        let poseidon = required_segment(segments.poseidon, SegmentKind::Poseidon)?;
+        let public_memory_padding = aux.0.public_memory_padding();
+
+        const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
+        let mut vals = [U256::ZERO; NUM_VALS];
+        vals[OFFSET_PEDERSEN_BEGIN_ADDR] = U256::from(pedersen.begin_addr.0);
+        vals[OFFSET_PEDERSEN_STOP_PTR] = U256::from(pedersen.stop_ptr.0);
+        vals[OFFSET_RANGE_CHECK_BEGIN_ADDR] = U256::from(range_check.begin_addr.0);
+        vals[OFFSET_RANGE_CHECK_STOP_PTR] = U256::from(range_check.stop_ptr.0);
+        vals[OFFSET_ECDSA_BEGIN_ADDR] = U256::from(ecdsa.begin_addr.0);
+        vals[OFFSET_ECDSA_STOP_PTR] = U256::from(ecdsa.stop_ptr.0);
+        vals[OFFSET_BITWISE_BEGIN_ADDR] = U256::from(bitwise.begin_addr.0);
+        vals[OFFSET_BITWISE_STOP_ADDR] = U256::from(bitwise.stop_ptr.0);
+        vals[OFFSET_EC_OP_BEGIN_ADDR] = U256::from(ec_op.begin_addr.0);
+        vals[OFFSET_EC_OP_STOP_ADDR] = U256::from(ec_op.stop_ptr.0);
+        vals[OFFSET_KECCAK_BEGIN_ADDR] = U256::from(keccak.begin_addr.0);
+        vals[OFFSET_KECCAK_STOP_PTR] = U256::from(keccak.stop_ptr.0);
+        vals[OFFSET_POSEIDON_BEGIN_ADDR] = U256::from(poseidon.begin_addr.0);
+        vals[OFFSET_POSEIDON_STOP_PTR] = U256::from(poseidon.stop_ptr.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] = U256::from(public_memory_padding.address.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
+            U256::from::<BigUint>(public_memory_padding.value.into());
+        vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = U256::from(aux.num_memory_pages());
+        Ok(vals.to_vec())
+    }
+}
+
+/// Serializer for the recursive and recursive_large_output layouts. Both have
+/// the same builtins.
+pub struct RecursiveLayout;
+
+impl LayoutSharpSerializer for RecursiveLayout {
+    fn layout_values(aux: &CairoAuxInput) -> Result<Vec<U256>, MissingSegmentError> {
+        const OFFSET_PEDERSEN_BEGIN_ADDR: usize = 0;
+        const OFFSET_PEDERSEN_STOP_PTR: usize = 1;
+        const OFFSET_RANGE_CHECK_BEGIN_ADDR: usize = 2;
+        const OFFSET_RANGE_CHECK_STOP_PTR: usize = 3;
+        const OFFSET_BITWISE_BEGIN_ADDR: usize = 4;
+        const OFFSET_BITWISE_STOP_ADDR: usize = 5;
+        const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 6;
+        const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 7;
+        const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 8;
+
+        let segments = aux.0.memory_segments;
+        let pedersen = required_segment(segments.pedersen, SegmentKind::Pedersen)?;
+        let range_check = required_segment(segments.range_check, SegmentKind::RangeCheck)?;
+        let bitwise = required_segment(segments.bitwise, SegmentKind::Bitwise)?;
+        let public_memory_padding = aux.0.public_memory_padding();
+
+        const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
+        let mut vals = [U256::ZERO; NUM_VALS];
+        vals[OFFSET_PEDERSEN_BEGIN_ADDR] = U256::from(pedersen.begin_addr.0);
+        vals[OFFSET_PEDERSEN_STOP_PTR] = U256::from(pedersen.stop_ptr.0);
+        vals[OFFSET_RANGE_CHECK_BEGIN_ADDR] = U256::from(range_check.begin_addr.0);
+        vals[OFFSET_RANGE_CHECK_STOP_PTR] = U256::from(range_check.stop_ptr.0);
+        vals[OFFSET_BITWISE_BEGIN_ADDR] = U256::from(bitwise.begin_addr.0);
+        vals[OFFSET_BITWISE_STOP_ADDR] = U256::from(bitwise.stop_ptr.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] = U256::from(public_memory_padding.address.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
+            U256::from::<BigUint>(public_memory_padding.value.into());
+        vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = U256::from(aux.num_memory_pages());
+        Ok(vals.to_vec())
+    }
+}
+
+pub struct AllSolidityLayout;
+
+impl LayoutSharpSerializer for AllSolidityLayout {
+    fn layout_values(aux: &CairoAuxInput) -> Result<Vec<U256>, MissingSegmentError> {
+        const OFFSET_PEDERSEN_BEGIN_ADDR: usize = 0;
+        const OFFSET_PEDERSEN_STOP_PTR: usize = 1;
+        const OFFSET_RANGE_CHECK_BEGIN_ADDR: usize = 2;
+        const OFFSET_RANGE_CHECK_STOP_PTR: usize = 3;
+        const OFFSET_ECDSA_BEGIN_ADDR: usize = 4;
+        const OFFSET_ECDSA_STOP_PTR: usize = 5;
+        const OFFSET_BITWISE_BEGIN_ADDR: usize = 6;
+        const OFFSET_BITWISE_STOP_ADDR: usize = 7;
+        const OFFSET_EC_OP_BEGIN_ADDR: usize = 8;
+        const OFFSET_EC_OP_STOP_ADDR: usize = 9;
+        const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: usize = 10;
+        const OFFSET_PUBLIC_MEMORY_PADDING_VALUE: usize = 11;
+        const OFFSET_N_PUBLIC_MEMORY_PAGES: usize = 12;
+
+        let segments = aux.0.memory_segments;
+        let pedersen = required_segment(segments.pedersen, SegmentKind::Pedersen)?;
+        let range_check = required_segment(segments.range_check, SegmentKind::RangeCheck)?;
+        let ecdsa = required_segment(segments.ecdsa, SegmentKind::Ecdsa)?;
+        let bitwise = required_segment(segments.bitwise, SegmentKind::Bitwise)?;
+        let ec_op = required_segment(segments.ec_op, SegmentKind::EcOp)?;
+        let public_memory_padding = aux.0.public_memory_padding();
+
+        const NUM_VALS: usize = OFFSET_N_PUBLIC_MEMORY_PAGES + 1;
+        let mut vals = [U256::ZERO; NUM_VALS];
+        vals[OFFSET_PEDERSEN_BEGIN_ADDR] = U256::from(pedersen.begin_addr.0);
+        vals[OFFSET_PEDERSEN_STOP_PTR] = U256::from(pedersen.stop_ptr.0);
+        vals[OFFSET_RANGE_CHECK_BEGIN_ADDR] = U256::from(range_check.begin_addr.0);
+        vals[OFFSET_RANGE_CHECK_STOP_PTR] = U256::from(range_check.stop_ptr.0);
+        vals[OFFSET_ECDSA_BEGIN_ADDR] = U256::from(ecdsa.begin_addr.0);
+        vals[OFFSET_ECDSA_STOP_PTR] = U256::from(ecdsa.stop_ptr.0);
+        vals[OFFSET_BITWISE_BEGIN_ADDR] = U256::from(bitwise.begin_addr.0);
+        vals[OFFSET_BITWISE_STOP_ADDR] = U256::from(bitwise.stop_ptr.0);
+        vals[OFFSET_EC_OP_BEGIN_ADDR] = U256::from(ec_op.begin_addr.0);
+        vals[OFFSET_EC_OP_STOP_ADDR] = U256::from(ec_op.stop_ptr.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_ADDR] = U256::from(public_memory_padding.address.0);
+        vals[OFFSET_PUBLIC_MEMORY_PADDING_VALUE] =
+            U256::from::<BigUint>(public_memory_padding.value.into());
+        vals[OFFSET_N_PUBLIC_MEMORY_PAGES] = U256::from(aux.num_memory_pages());
+        Ok(vals.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::poseidon_hash_memory_page;
+    use super::CairoAuxInput;
+    use super::MissingSegmentError;
+    use binary::types::MemoryAddress;
+    use binary::AirPublicInput;
+    use binary::Layout;
+    use binary::MemoryEntry;
+    use binary::MemorySegments;
+    use binary::Segment;
+    use binary::SegmentKind;
+    use crypto::hash::pedersen::PedersenHashFn;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+    use ruint::aliases::U256;
+    use ruint::uint;
+
+    /// Public input with each segment `i` spanning `[100 * i, 100 * i + 10)`
+    fn public_input_with_segments(layout: Layout, builtins: &[SegmentKind]) -> AirPublicInput<Fp> {
+        let segment = |i: u32| Segment {
+            begin_addr: MemoryAddress(100 * i),
+            stop_ptr: MemoryAddress(100 * i + 10),
+        };
+        let mut memory_segments = MemorySegments::new(segment(1), segment(2));
+        for (i, builtin) in (3..).zip(builtins) {
+            memory_segments = match builtin {
+                SegmentKind::Output => memory_segments.with_output_segment(segment(i)),
+                SegmentKind::Pedersen => memory_segments.with_pedersen_segment(segment(i)),
+                SegmentKind::RangeCheck => memory_segments.with_range_check_segment(segment(i)),
+                SegmentKind::Ecdsa => memory_segments.with_ecdsa_segment(segment(i)),
+                SegmentKind::Bitwise => memory_segments.with_bitwise_segment(segment(i)),
+                SegmentKind::EcOp => memory_segments.with_ec_op_segment(segment(i)),
+                SegmentKind::Keccak => memory_segments.with_keccak_segment(segment(i)),
+                SegmentKind::Poseidon => memory_segments.with_poseidon_segment(segment(i)),
+                SegmentKind::Program | SegmentKind::Execution => unreachable!(),
+            };
+        }
+        AirPublicInput {
+            rc_min: 0,
+            rc_max: 10,
+            n_steps: 1 << 10,
+            layout,
+            memory_segments,
+            public_memory: vec![MemoryEntry {
+                address: MemoryAddress(1),
+                value: Fp::from(7u8),
+            }],
+        }
+    }
+
+    /// Begin address and stop pointer of segments `first..=last`
+    fn segment_values(first: u32, last: u32) -> Vec<U256> {
+        (first..=last)
+            .flat_map(|i| [U256::from(100 * i), U256::from(100 * i + 10)])
+            .collect()
+    }
+
+    /// Public memory padding (address 1, value 7) and number of memory pages
+    fn public_memory_values() -> Vec<U256> {
+        [1u8, 7, 1].map(U256::from).to_vec()
+    }
+
+    #[test]
+    fn plain_layout_values() {
+        let public_input = public_input_with_segments(Layout::Plain, &[]);
+        let aux_input = CairoAuxInput(&public_input);
+
+        let base_values = aux_input.base_values().unwrap();
+        let values = aux_input.layout_specific_values().unwrap();
+
+        // no output segment
+        assert_eq!(8, base_values.len());
+        assert_eq!(3, values.len());
+        assert_eq!(public_memory_values(), values);
+    }
+
+    #[test]
+    fn missing_layout_segment_is_an_error() {
+        use SegmentKind::*;
+        let public_input = public_input_with_segments(Layout::Recursive, &[Output, Pedersen]);
+        let aux_input = CairoAuxInput(&public_input);
+
+        assert_eq!(
+            Err(MissingSegmentError {
+                segment: RangeCheck
+            }),
+            aux_input.layout_specific_values()
+        );
+        let public_input = public_input_with_segments(Layout::Recursive, &[]);
+        assert_eq!(
+            Err(MissingSegmentError { segment: Output }),
+            CairoAuxInput(&public_input).base_values()
+        );
+    }
+
+    #[test]
+    fn small_and_dex_layout_values() {
+        use SegmentKind::*;
+        let builtins = [Output, Pedersen, RangeCheck, Ecdsa];
+        for layout in [Layout::Small, Layout::Dex] {
+            let public_input = public_input_with_segments(layout, &builtins);
+
+            let values = CairoAuxInput(&public_input)
+                .layout_specific_values()
+                .unwrap();
+
+            let expected = [segment_values(4, 6), public_memory_values()].concat();
+            assert_eq!(9, values.len());
+            assert_eq!(expected, values);
+        }
+    }
+
+    #[test]
+    fn recursive_layout_values() {
+        use SegmentKind::*;
+        let builtins = [Output, Pedersen, RangeCheck, Bitwise];
+        for layout in [Layout::Recursive, Layout::RecursiveLargeOutput] {
+            let public_input = public_input_with_segments(layout, &builtins);
+
+            let values = CairoAuxInput(&public_input)
+                .layout_specific_values()
+                .unwrap();
+
+            let expected = [segment_values(4, 6), public_memory_values()].concat();
+            assert_eq!(9, values.len());
+            assert_eq!(expected, values);
+        }
+    }
+
+    #[test]
+    fn starknet_layout_values() {
+        use SegmentKind::*;
+        let builtins = [Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Poseidon];
+        let public_input = public_input_with_segments(Layout::Starknet, &builtins);
+
+        let values = CairoAuxInput(&public_input)
+            .layout_specific_values()
+            .unwrap();
+
+        let expected = [segment_values(4, 9), public_memory_values()].concat();
+        assert_eq!(15, values.len());
+        assert_eq!(expected, values);
+    }
+
+    #[test]
+    fn starknet_with_keccak_layout_values() {
+        use SegmentKind::*;
+        let builtins = [
+            Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Keccak, Poseidon,
+        ];
+        let public_input = public_input_with_segments(Layout::StarknetWithKeccak, &builtins);
+
+        let values = CairoAuxInput(&public_input)
+            .layout_specific_values()
+            .unwrap();
+
+        let expected = [segment_values(4, 10), public_memory_values()].concat();
+        assert_eq!(17, values.len());
+        assert_eq!(expected, values);
+    }
+
+    #[test]
+    fn all_solidity_layout_values() {
+        use SegmentKind::*;
+        let builtins = [Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp];
+        let public_input = public_input_with_segments(Layout::AllSolidity, &builtins);
+
+        let values = CairoAuxInput(&public_input)
+            .layout_specific_values()
+            .unwrap();
+
+        let expected = [segment_values(4, 8), public_memory_values()].concat();
+        assert_eq!(13, values.len());
+        assert_eq!(expected, values);
+    }
+
+    #[test]
+    fn public_inputs_vec_round_trips() {
+        let air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(include_str!("../example/air-public-input.json")).unwrap();
+        let inputs = CairoAuxInput(&air_public_input).to_public_inputs_vec();
+
+        let decoded = CairoAuxInput::from_public_inputs_vec(&inputs).unwrap();
+
+        assert_eq!(inputs, CairoAuxInput(&decoded).to_public_inputs_vec());
+        assert_eq!(air_public_input.public_memory, decoded.public_memory);
+    }
+
+    #[test]
+    fn public_inputs_vec_round_trips_for_each_layout() {
+        for layout in Layout::ALL {
+            let mut builtins = layout.required_builtins().to_vec();
+            if layout.has_output() {
+                builtins.insert(0, SegmentKind::Output);
+            }
+            let public_input = public_input_with_segments(layout, &builtins);
+            let inputs = CairoAuxInput(&public_input).to_public_inputs_vec();
+
+            let decoded = CairoAuxInput::from_public_inputs_vec(&inputs).unwrap();
+
+            assert_eq!(public_input, decoded);
+        }
+    }
+
+    /// Adds the values `1, 2, ...` at `addresses` to the public memory
+    fn add_public_memory(
+        public_input: &mut AirPublicInput<Fp>,
+        addresses: impl Iterator<Item = u32>,
+    ) {
+        for (value, address) in (1u32..).zip(addresses) {
+            public_input.public_memory.push(MemoryEntry {
+                address: MemoryAddress(address),
+                value: value.into(),
+            });
+        }
+    }
+
+    #[test]
+    fn output_is_a_separate_memory_page() {
+        use SegmentKind::*;
+        let mut public_input =
+            public_input_with_segments(Layout::Recursive, &[Output, Pedersen, RangeCheck, Bitwise]);
+        add_public_memory(&mut public_input, 100..103);
+        add_public_memory(&mut public_input, 300..305);
+        let aux_input = CairoAuxInput(&public_input);
+
+        let pages = aux_input.memory_pages::<PedersenHashFn>();
+        let values = aux_input.memory_page_values::<PedersenHashFn>();
+
+        assert_eq!(2, aux_input.num_memory_pages());
+        assert_eq!(
+            (4, MemoryAddress(300), 5),
+            (pages[0].size, pages[1].begin_addr, pages[1].size)
+        );
+        // main page is (size, hash) and other pages are (begin_addr, size, hash)
+        assert_eq!(2 + 3, values.len());
+        assert_eq!(
+            U256::from(2),
+            *aux_input.layout_specific_values().unwrap().last().unwrap()
+        );
+    }
+
+    #[test]
+    fn each_builtin_segment_is_a_separate_memory_page() {
+        use SegmentKind::*;
+        let mut public_input = public_input_with_segments(Layout::Recursive, &[Output, Pedersen]);
+        add_public_memory(&mut public_input, 400..402);
+        add_public_memory(&mut public_input, 300..305);
+        let aux_input = CairoAuxInput(&public_input);
+
+        let values = aux_input.memory_page_values::<PedersenHashFn>();
+
+        assert_eq!(3, aux_input.num_memory_pages());
+        assert_eq!(2 + 3 + 3, values.len());
+        // pages are ordered by segment
+        assert_eq!(U256::from(300), values[2]);
+        assert_eq!(U256::from(400), values[5]);
+    }
+
+    #[test]
+    fn public_memory_hash_detects_modified_entry() {
+        use SegmentKind::*;
+        let builtins = [Output, Pedersen, RangeCheck, Bitwise];
+        let mut public_input = public_input_with_segments(Layout::Recursive, &builtins);
+        add_public_memory(&mut public_input, 100..103);
+        add_public_memory(&mut public_input, 300..305);
+        let elements = CairoAuxInput(&public_input)
+            .public_input_elements::<PedersenHashFn>()
+            .unwrap();
+        let verify = |public_input: &AirPublicInput<Fp>| {
+            CairoAuxInput(public_input).verify_public_memory_hash::<PedersenHashFn>(&elements)
+        };
+        assert!(verify(&public_input));
+
+        // entry of the output page
+        public_input.public_memory[6].value += Fp::from(1u8);
+        assert!(!verify(&public_input));
+
+        // entry of the main page
+        public_input.public_memory[6].value -= Fp::from(1u8);
+        public_input.public_memory[2].value += Fp::from(1u8);
+        assert!(!verify(&public_input));
+    }
+
+    #[test]
+    fn non_continuous_entries_stay_in_main_page() {
+        use SegmentKind::*;
+        let mut public_input = public_input_with_segments(Layout::Recursive, &[Output]);
+        add_public_memory(&mut public_input, [300, 302].into_iter());
+        let aux_input = CairoAuxInput(&public_input);
+
+        let pages = aux_input.memory_pages::<PedersenHashFn>();
+
+        assert_eq!(1, pages.len());
+        assert_eq!(3, pages[0].size);
+    }
+
+    #[test]
+    fn public_memory_hash_verifies_out_of_order_entries() {
+        use SegmentKind::*;
+        let builtins = [Output, Pedersen, RangeCheck, Bitwise];
+        let mut public_input = public_input_with_segments(Layout::Recursive, &builtins);
+        // non-continuous output entries out of address order
+        add_public_memory(&mut public_input, [302, 300].into_iter());
+        // continuous pedersen entries out of address order
+        add_public_memory(&mut public_input, [401, 400].into_iter());
+        // program entries listed after the builtin entries
+        add_public_memory(&mut public_input, [102, 101].into_iter());
+        let aux_input = CairoAuxInput(&public_input);
+
+        let elements = aux_input.public_input_elements::<PedersenHashFn>().unwrap();
+        let pages = aux_input.memory_pages::<PedersenHashFn>();
+
+        assert_eq!(2, pages.len());
+        assert_eq!(
+            (MemoryAddress(400), 2),
+            (pages[1].begin_addr, pages[1].size)
+        );
+        assert!(aux_input.verify_public_memory_hash::<PedersenHashFn>(&elements));
+    }
+
+    #[test]
+    fn poseidon_hash_memory_page_matches_reference() {
+        let entries = [(1u32, 7u8), (2, 9), (3, 11)].map(|(address, value)| MemoryEntry {
+            address: MemoryAddress(address),
+            value: Fp::from(value),
+        });
+
+        let hash = poseidon_hash_memory_page(&entries);
+
+        // starknet-crypto's `poseidon_hash_many(&[1, 7, 2, 9, 3, 11])`
+        let expected =
+            uint!(0x3b0c46b4df4945229767747364771e81c33229db2233ccc3365c66e1d0d5ad1_U256);
+        assert_eq!(expected.to_be_bytes::<32>(), hash);
     }
 }
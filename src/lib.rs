@@ -30,6 +30,9 @@ use std::marker::PhantomData;
 
 pub mod claims;
 pub mod input;
+#[cfg(feature = "sqlite")]
+pub mod proof_db;
+pub mod proof_size;
 
 pub struct CairoClaim<
     Fp: GpuFftField + PrimeField,
@@ -146,7 +149,10 @@ impl CairoPublicCoin for SolidityVerifierPublicCoin {
     fn from_public_input(public_input: &AirPublicInput<Fp>) -> Self {
         let aux_input = CairoAuxInput(public_input);
         let mut seed = Vec::new();
-        for element in aux_input.public_input_elements::<CanonicalKeccak256HashFn>() {
+        let elements = aux_input
+            .public_input_elements::<CanonicalKeccak256HashFn>()
+            .expect("public input is missing a segment of its layout");
+        for element in elements {
             seed.extend_from_slice(&element.to_be_bytes::<32>())
         }
         Self::new(CanonicalKeccak256HashFn::hash_chunks([&*seed]))
@@ -157,7 +163,10 @@ impl CairoPublicCoin for CairoVerifierPublicCoin {
     fn from_public_input(public_input: &AirPublicInput<Fp>) -> Self {
         let aux_input = CairoAuxInput(public_input);
         let mut seed = Vec::new();
-        for element in aux_input.public_input_elements::<PedersenHashFn>() {
+        let elements = aux_input
+            .public_input_elements::<PedersenHashFn>()
+            .expect("public input is missing a segment of its layout");
+        for element in elements {
             seed.extend_from_slice(&element.to_be_bytes::<32>())
         }
         Self::new(MixedMerkleDigest::LowLevel(Blake2sHashFn::hash_chunks([
@@ -0,0 +1,232 @@
+//! SQLite storage for generated proofs. Useful when many proofs are generated
+//! continuously (e.g. by a sequencer) and later batched for submission.
+
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::SerializationError;
+use binary::AirPublicInput;
+use binary::Layout;
+use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+use num_bigint::BigUint;
+use ruint::aliases::U256;
+use rusqlite::params;
+use rusqlite::Connection;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+use std::time::SystemTime;
+use std::time::SystemTimeError;
+use std::time::UNIX_EPOCH;
+
+const CREATE_PROOFS_TABLE: &str = "CREATE TABLE IF NOT EXISTS proofs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    program_hash BLOB NOT NULL,
+    layout TEXT NOT NULL,
+    log_n_steps INTEGER NOT NULL,
+    proof_bytes BLOB NOT NULL,
+    aux_input BLOB NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS proofs_program_hash ON proofs (program_hash);
+CREATE INDEX IF NOT EXISTS proofs_layout ON proofs (layout);";
+
+const SELECT_PROOFS: &str = "SELECT id, program_hash, log_n_steps, proof_bytes, aux_input, \
+                             created_at FROM proofs";
+
+#[derive(Debug)]
+pub enum DbError {
+    Sqlite(rusqlite::Error),
+    Serialization(SerializationError),
+    /// The public input has no steps so there's no `log_n_steps` to store
+    ZeroSteps,
+    /// The system clock is set before the unix epoch
+    Clock(SystemTimeError),
+}
+
+impl Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sqlite(err) => write!(f, "database error: {err}"),
+            Self::Serialization(err) => write!(f, "serialization error: {err}"),
+            Self::ZeroSteps => write!(f, "public input has zero steps"),
+            Self::Clock(err) => write!(f, "invalid system time: {err}"),
+        }
+    }
+}
+
+impl Error for DbError {}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Sqlite(err)
+    }
+}
+
+impl From<SerializationError> for DbError {
+    fn from(err: SerializationError) -> Self {
+        Self::Serialization(err)
+    }
+}
+
+impl From<SystemTimeError> for DbError {
+    fn from(err: SystemTimeError) -> Self {
+        Self::Clock(err)
+    }
+}
+
+/// A stored proof. The proof is kept in its compressed serialized form since
+/// deserializing requires the claim it was generated for.
+#[derive(Debug)]
+pub struct ProofRecord {
+    pub id: i64,
+    pub program_hash: Fp,
+    pub layout: Layout,
+    pub log_n_steps: u32,
+    pub proof_bytes: Vec<u8>,
+    pub air_public_input: AirPublicInput<Fp>,
+    /// Seconds since the unix epoch
+    pub created_at: i64,
+}
+
+pub struct ProofDatabase {
+    connection: Connection,
+}
+
+impl ProofDatabase {
+    /// Opens (or creates) a proof database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DbError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Creates a proof database that only lives in memory
+    pub fn open_in_memory() -> Result<Self, DbError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> Result<Self, DbError> {
+        connection.execute_batch(CREATE_PROOFS_TABLE)?;
+        Ok(Self { connection })
+    }
+
+    /// Stores a proof of the program with hash `program_hash`. Returns the id
+    /// of the new record.
+    pub fn insert(
+        &self,
+        proof: &impl CanonicalSerialize,
+        air_public_input: &AirPublicInput<Fp>,
+        program_hash: Fp,
+    ) -> Result<i64, DbError> {
+        let log_n_steps = air_public_input
+            .n_steps
+            .checked_ilog2()
+            .ok_or(DbError::ZeroSteps)?;
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes)?;
+        let mut aux_input = Vec::new();
+        air_public_input.serialize_compressed(&mut aux_input)?;
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        self.connection.execute(
+            "INSERT INTO proofs (program_hash, layout, log_n_steps, proof_bytes, aux_input, \
+             created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                &felt_to_bytes(program_hash)[..],
+                air_public_input.layout.to_string(),
+                log_n_steps,
+                proof_bytes,
+                aux_input,
+                created_at,
+            ],
+        )?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    pub fn find_by_program_hash(&self, program_hash: Fp) -> Result<Vec<ProofRecord>, DbError> {
+        self.query(
+            &format!("{SELECT_PROOFS} WHERE program_hash = ?1 ORDER BY id"),
+            &felt_to_bytes(program_hash)[..],
+        )
+    }
+
+    pub fn find_by_layout(&self, layout: Layout) -> Result<Vec<ProofRecord>, DbError> {
+        self.query(
+            &format!("{SELECT_PROOFS} WHERE layout = ?1 ORDER BY id"),
+            layout.to_string(),
+        )
+    }
+
+    fn query(&self, sql: &str, param: impl rusqlite::ToSql) -> Result<Vec<ProofRecord>, DbError> {
+        let mut statement = self.connection.prepare(sql)?;
+        let rows = statement.query_map(params![param], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+                row.get::<_, Vec<u8>>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?;
+        let mut records = Vec::new();
+        for row in rows {
+            let (id, program_hash, log_n_steps, proof_bytes, aux_input, created_at) = row?;
+            let air_public_input = AirPublicInput::deserialize_compressed(&*aux_input)?;
+            records.push(ProofRecord {
+                id,
+                program_hash: Fp::from(BigUint::from_bytes_be(&program_hash)),
+                layout: air_public_input.layout,
+                log_n_steps,
+                proof_bytes,
+                air_public_input,
+                created_at,
+            });
+        }
+        Ok(records)
+    }
+}
+
+fn felt_to_bytes(value: Fp) -> [u8; 32] {
+    U256::from(BigUint::from(value)).to_be_bytes::<32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DbError;
+    use super::ProofDatabase;
+    use binary::AirPublicInput;
+    use binary::Layout;
+    use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
+
+    const AIR_PUBLIC_INPUT: &str = include_str!("../example/bootloader/air-public-input.json");
+
+    #[test]
+    fn inserted_proofs_can_be_queried() {
+        let air_public_input: AirPublicInput<Fp> = serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        let db = ProofDatabase::open_in_memory().unwrap();
+        let proof = vec![1u8, 2, 3];
+        let program_hash = Fp::from(7u8);
+
+        let id = db.insert(&proof, &air_public_input, program_hash).unwrap();
+
+        let records = db.find_by_program_hash(program_hash).unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(id, records[0].id);
+        assert_eq!(program_hash, records[0].program_hash);
+        assert_eq!(air_public_input, records[0].air_public_input);
+        assert_eq!(1, db.find_by_layout(Layout::Starknet).unwrap().len());
+        assert!(db.find_by_layout(Layout::Recursive).unwrap().is_empty());
+        assert!(db.find_by_program_hash(Fp::from(8u8)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn inserting_public_input_without_steps_fails() {
+        let mut air_public_input: AirPublicInput<Fp> =
+            serde_json::from_str(AIR_PUBLIC_INPUT).unwrap();
+        air_public_input.n_steps = 0;
+        let db = ProofDatabase::open_in_memory().unwrap();
+
+        let res = db.insert(&vec![1u8], &air_public_input, Fp::from(7u8));
+
+        assert!(matches!(res, Err(DbError::ZeroSteps)));
+        assert!(db.find_by_layout(Layout::Starknet).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+use ark_serialize::CanonicalSerialize;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Maximum size of a transaction accepted by Ethereum clients. Geth rejects
+/// transactions larger than 4 slots of 32KB.
+const ETHEREUM_MAX_TX_BYTES: usize = 128 * 1024;
+
+/// Number of bytes in a compressed serialized proof
+pub fn proof_size_bytes(proof: &impl CanonicalSerialize) -> usize {
+    proof.compressed_size()
+}
+
+/// Upper bound on the size of a serialized proof e.g. for verifiers that
+/// receive proofs as calldata
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofSizeBudget {
+    pub max_bytes: usize,
+}
+
+impl ProofSizeBudget {
+    pub const fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+
+    /// Budget for proofs submitted as calldata of a single Ethereum L1
+    /// transaction
+    pub const fn for_ethereum_l1_calldata() -> Self {
+        Self::new(ETHEREUM_MAX_TX_BYTES)
+    }
+
+    pub fn check(&self, proof: &impl CanonicalSerialize) -> Result<(), ProofTooLargeError> {
+        let actual = proof_size_bytes(proof);
+        if actual > self.max_bytes {
+            return Err(ProofTooLargeError {
+                actual,
+                max: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofTooLargeError {
+    pub actual: usize,
+    pub max: usize,
+}
+
+impl Display for ProofTooLargeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { actual, max } = self;
+        write!(f, "proof is {actual} bytes but the budget is {max} bytes")
+    }
+}
+
+impl Error for ProofTooLargeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::ProofSizeBudget;
+    use super::ProofTooLargeError;
+
+    #[test]
+    fn check_rejects_proofs_over_budget() {
+        // a `Vec<u8>` serializes as an 8 byte length followed by its elements
+        let proof = vec![0u8; 92];
+        let budget = ProofSizeBudget::new(100);
+
+        assert_eq!(Ok(()), budget.check(&proof));
+        assert_eq!(
+            Err(ProofTooLargeError {
+                actual: 101,
+                max: 100
+            }),
+            budget.check(&vec![0u8; 93])
+        );
+    }
+}